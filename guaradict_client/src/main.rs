@@ -1,17 +1,49 @@
+use std::env;
 use std::io;
 use std::time::Duration;
 use tokio::net::TcpStream;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::AsyncWriteExt;
 use tokio::time;
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
 
-use guaradict_core::commands::client;
+use guaradict_core::cluster::ReadPreference;
+use guaradict_core::commands::{client, frame};
+use guaradict_core::replica::ReplicaStatus;
+
+mod rebalance;
+mod router;
+mod status;
+
+use router::Router;
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("rebalance") {
+        return rebalance::run(&args[2..]).await;
+    }
+
+    // `guaradict_client [--host <ip:porta>] [--read-preference primary|replica|nearest]`
+    let mut host = "127.0.0.1:13141".to_string();
+    let mut read_preference = ReadPreference::Primary;
+    let mut options = args.iter().skip(1);
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--host" => host = options.next().cloned().ok_or_else(|| io::Error::other("--host exige um endereço"))?,
+            "--read-preference" => {
+                read_preference = options
+                    .next()
+                    .ok_or_else(|| io::Error::other("--read-preference exige uma preferência"))?
+                    .parse()
+                    .map_err(io::Error::other)?
+            }
+            _ => return Err(io::Error::other(format!("Argumento desconhecido: {}", option))),
+        }
+    }
+
     loop {
-        match connect_and_interact().await {
+        match connect_and_interact(&host, read_preference).await {
             Ok(()) => break,
             Err(err) => {
                 eprintln!("Erro na conexão: {}", err);
@@ -22,8 +54,8 @@ async fn main() -> io::Result<()> {
     Ok(())
 }
 
-async fn connect_and_interact() -> io::Result<()> {
-    let mut stream = TcpStream::connect("127.0.0.1:13141").await?;
+async fn connect_and_interact(host: &str, read_preference: ReadPreference) -> io::Result<()> {
+    let mut router = Router::connect(host, read_preference).await?;
     println!("Conexão estabelecida com o servidor.");
 
     let mut rl = DefaultEditor::new().expect("Erro iniciando REPL");
@@ -41,17 +73,13 @@ async fn connect_and_interact() -> io::Result<()> {
                 }
 
                 match client::Command::parse(&line) {
-                    Ok(command) => {
-                        let command_str = command.execute();
-                        if let Err(err) = send_command(&mut stream, &command_str).await {
+                    Ok(command) => match router.request(&command).await {
+                        Ok(response) => print_response(&command, &response),
+                        Err(err) => {
                             eprintln!("Erro ao enviar comando: {}", err);
                             return Err(err);
                         }
-                        if let Err(err) = read_response(&mut stream).await {
-                            eprintln!("Erro ao ler resposta: {}", err);
-                            return Err(err);
-                        }
-                    }
+                    },
                     Err(err) => {
                         println!("Erro ao analisar comando: {:?}", err);
                     }
@@ -78,13 +106,31 @@ async fn connect_and_interact() -> io::Result<()> {
 }
 
 async fn send_command(stream: &mut TcpStream, command: &str) -> io::Result<()> {
-    stream.write_all(command.as_bytes()).await?;
+    stream.write_all(format!("{}\n", command).as_bytes()).await?;
     Ok(())
 }
 
-async fn read_response(stream: &mut TcpStream) -> io::Result<()> {
-    let mut buffer = [0; 1024];
-    let n = stream.read(&mut buffer).await?;
-    println!("{}", String::from_utf8_lossy(&buffer[..n]));
-    Ok(())
+async fn read_response(stream: &mut TcpStream) -> io::Result<String> {
+    frame::read(stream).await
+}
+
+fn print_response(command: &client::Command, response: &str) {
+    // MGET responde com uma lista na ordem das chaves; exibe cada chave ao lado da definição
+    if let client::Command::MGet(keys) = command {
+        if let Ok(definitions) = serde_json::from_str::<Vec<Option<String>>>(response) {
+            for (key, definition) in keys.iter().zip(definitions) {
+                println!("{}: {}", key, definition.as_deref().unwrap_or("(nil)"));
+            }
+            return;
+        }
+    }
+
+    if let client::Command::ClusterStatus = command {
+        if let Ok(nodes) = serde_json::from_str::<Vec<ReplicaStatus>>(response) {
+            status::print(&nodes);
+            return;
+        }
+    }
+
+    println!("{}", response);
 }
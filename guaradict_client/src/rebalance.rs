@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::io;
+use tokio::net::TcpStream;
+
+use guaradict_core::cluster::{plan_rebalance, SlotMove, SlotRange, SlotState};
+use guaradict_core::commands::client;
+
+use crate::{read_response, send_command};
+
+// Chaves copiadas por MIGRATE
+const MIGRATE_BATCH: usize = 100;
+
+/// `guaradict_client rebalance [--host <ip:porta>] [--dry-run]`: consulta os
+/// primários do cluster pelo nó em `--host`, planeja a divisão igual dos
+/// slots e move os slots um a um, sem parar o cluster. Com `--dry-run` só
+/// mostra o plano.
+pub async fn run(args: &[String]) -> io::Result<()> {
+    let mut host = "127.0.0.1:13141".to_string();
+    let mut dry_run = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--host" => host = args.next().cloned().ok_or_else(|| io::Error::other("--host exige um endereço"))?,
+            "--dry-run" => dry_run = true,
+            _ => return Err(io::Error::other(format!("Argumento desconhecido: {}", arg))),
+        }
+    }
+
+    let mut seed = TcpStream::connect(&host).await?;
+    let nodes = cluster_nodes(&request(&mut seed, &client::Command::ClusterNodes).await?)?;
+    let plan = plan_rebalance(&nodes.iter().map(|node| (node.name.clone(), node.slots.clone())).collect::<Vec<_>>());
+
+    if plan.is_empty() {
+        println!("O cluster já está equilibrado");
+        return Ok(());
+    }
+    for slot_move in &plan {
+        println!("{} -> {}: slots {} ({})", slot_move.from, slot_move.to, slot_move.range, slot_move.range.slots().count());
+    }
+    if dry_run {
+        return Ok(());
+    }
+
+    let mut cluster = Cluster::connect(&nodes).await?;
+    let mut keys = 0;
+    for slot_move in &plan {
+        for slot in slot_move.range.slots() {
+            keys += cluster.move_slot(slot, slot_move).await?;
+        }
+        println!("Slots {} movidos para {}", slot_move.range, slot_move.to);
+    }
+    println!("Rebalanceamento concluído: {} chaves movidas", keys);
+
+    Ok(())
+}
+
+struct Node {
+    name: String,
+    addr: String,
+    slots: Vec<SlotRange>,
+}
+
+// Lê a resposta do CLUSTER NODES
+fn cluster_nodes(response: &str) -> io::Result<Vec<Node>> {
+    let invalid = || io::Error::other(format!("Resposta inesperada do CLUSTER NODES: {}", response));
+    let nodes = serde_json::from_str::<Vec<serde_json::Value>>(response).map_err(|_| invalid())?;
+
+    nodes
+        .iter()
+        .map(|node| {
+            let slots = node["slots"]
+                .as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|range| range.as_str().and_then(|range| range.parse().ok()).ok_or_else(invalid))
+                .collect::<io::Result<Vec<SlotRange>>>()?;
+            Ok(Node {
+                name: node["name"].as_str().ok_or_else(invalid)?.to_string(),
+                addr: node["addr"].as_str().ok_or_else(invalid)?.to_string(),
+                slots,
+            })
+        })
+        .collect()
+}
+
+/// Uma conexão com cada primário do cluster.
+struct Cluster {
+    connections: HashMap<String, TcpStream>,
+}
+
+impl Cluster {
+    async fn connect(nodes: &[Node]) -> io::Result<Self> {
+        let mut connections = HashMap::new();
+        for node in nodes {
+            let stream = TcpStream::connect(&node.addr)
+                .await
+                .map_err(|e| io::Error::other(format!("Falha ao conectar a {} ({}): {}", node.name, node.addr, e)))?;
+            connections.insert(node.name.clone(), stream);
+        }
+        Ok(Self { connections })
+    }
+
+    // Move um slot: o destino passa a importá-lo, a origem a migrá-lo, as
+    // chaves são copiadas em lotes e, por fim, todos os nós passam a
+    // apontar para o novo dono. Retorna quantas chaves foram movidas
+    async fn move_slot(&mut self, slot: u16, slot_move: &SlotMove) -> io::Result<usize> {
+        self.set_slot(&slot_move.to, slot, SlotState::Importing(slot_move.from.clone())).await?;
+        self.set_slot(&slot_move.from, slot, SlotState::Migrating(slot_move.to.clone())).await?;
+
+        let mut moved = 0;
+        loop {
+            let response = self.request(&slot_move.from, &client::Command::Migrate(slot, MIGRATE_BATCH)).await?;
+            match response.parse::<usize>() {
+                Ok(0) => break,
+                Ok(count) => moved += count,
+                Err(_) => return Err(io::Error::other(format!("Falha ao migrar o slot {}: {}", slot, response))),
+            }
+        }
+
+        // O novo dono primeiro, para que os redirecionamentos já o encontrem
+        let mut names = self.connections.keys().cloned().collect::<Vec<String>>();
+        names.sort_by_key(|name| (*name != slot_move.to, *name != slot_move.from, name.clone()));
+        for name in names {
+            self.set_slot(&name, slot, SlotState::Node(slot_move.to.clone())).await?;
+        }
+
+        Ok(moved)
+    }
+
+    async fn set_slot(&mut self, node: &str, slot: u16, state: SlotState) -> io::Result<()> {
+        let response = self.request(node, &client::Command::ClusterSetSlot(slot, state)).await?;
+        if response != "OK" {
+            return Err(io::Error::other(format!("{} recusou o CLUSTER SETSLOT {}: {}", node, slot, response)));
+        }
+        Ok(())
+    }
+
+    async fn request(&mut self, node: &str, command: &client::Command) -> io::Result<String> {
+        let stream = self
+            .connections
+            .get_mut(node)
+            .ok_or_else(|| io::Error::other(format!("Nó desconhecido no cluster: {}", node)))?;
+        request(stream, command).await
+    }
+}
+
+async fn request(stream: &mut TcpStream, command: &client::Command) -> io::Result<String> {
+    send_command(stream, &command.execute()).await?;
+    read_response(stream).await
+}
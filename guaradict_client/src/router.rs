@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+use guaradict_core::cluster::{NodeRole, ReadPreference, Redirect, Topology};
+use guaradict_core::commands::client;
+
+use crate::{read_response, send_command};
+
+// Idade máxima da topologia antes de consultá-la de novo
+const TOPOLOGY_MAX_AGE: Duration = Duration::from_secs(30);
+// Redirecionamentos seguidos num mesmo comando antes de desistir
+const MAX_REDIRECTS: usize = 5;
+
+/// Manda cada comando ao nó que atende as suas chaves: as escritas ao
+/// primário dono do slot e as leituras conforme a `ReadPreference`. Segue
+/// os `MOVED` e `ASK` e volta a consultar a topologia quando ela muda.
+pub struct Router {
+    seed: String,
+    read_preference: ReadPreference,
+    topology: Topology,
+    connections: HashMap<String, TcpStream>,
+    // Nó que atende a transação aberta com WATCH ou MULTI até o EXEC
+    pinned: Option<String>,
+    multi: bool,
+}
+
+impl Router {
+    pub async fn connect(seed: &str, read_preference: ReadPreference) -> io::Result<Self> {
+        let mut router = Self {
+            seed: seed.to_string(),
+            read_preference,
+            topology: Topology::single(seed),
+            connections: HashMap::new(),
+            pinned: None,
+            multi: false,
+        };
+        router.refresh().await?;
+        Ok(router)
+    }
+
+    pub async fn request(&mut self, command: &client::Command) -> io::Result<String> {
+        if self.topology.is_stale(TOPOLOGY_MAX_AGE) {
+            if let Err(e) = self.refresh().await {
+                eprintln!("Falha ao atualizar a topologia: {}", e);
+            }
+        }
+
+        let mut addr = match &self.pinned {
+            Some(addr) => addr.clone(),
+            None => {
+                self.topology
+                    .route(&command.keys(), command.is_read_only(), self.read_preference)
+                    .unwrap_or_else(|| self.seed.clone())
+            }
+        };
+
+        let line = command.execute();
+        let mut response = self.send(&addr, &line).await?;
+        for _ in 0..MAX_REDIRECTS {
+            match Redirect::parse(&response) {
+                Some(Redirect::Moved { slot, addr: owner }) => {
+                    self.topology.moved(slot, &owner);
+                    addr = owner;
+                    response = self.send(&addr, &line).await?;
+                }
+                Some(Redirect::Ask { addr: target, .. }) => {
+                    self.send(&target, &client::Command::Asking.execute()).await?;
+                    response = self.send(&target, &line).await?;
+                }
+                None => break,
+            }
+        }
+
+        match command {
+            client::Command::Multi => {
+                self.pinned = Some(addr);
+                self.multi = true;
+            }
+            client::Command::Watch(_) => self.pinned = Some(addr),
+            client::Command::Exec | client::Command::Discard => {
+                self.pinned = None;
+                self.multi = false;
+            }
+            client::Command::Unwatch if !self.multi => self.pinned = None,
+            _ => {}
+        }
+
+        Ok(response)
+    }
+
+    // Consulta os slots em qualquer nó conhecido, começando pelo inicial, e
+    // as réplicas de cada primário
+    async fn refresh(&mut self) -> io::Result<()> {
+        let mut candidates = vec![self.seed.clone()];
+        candidates.extend(self.topology.primaries().into_iter().filter(|addr| *addr != self.seed));
+
+        let mut last_error = io::Error::other("Nenhum nó disponível");
+        for addr in candidates {
+            let response = match self.send(&addr, &client::Command::ClusterSlots.execute()).await {
+                Ok(response) => response,
+                Err(e) => {
+                    last_error = e;
+                    continue;
+                }
+            };
+            let mut topology = Topology::from_cluster_slots(&addr, &response).map_err(io::Error::other)?;
+
+            for primary in topology.primaries() {
+                match self.send(&primary, &client::Command::Role.execute()).await {
+                    Ok(response) => match serde_json::from_str::<NodeRole>(&response) {
+                        Ok(role) => topology.set_role(&primary, role),
+                        Err(_) => eprintln!("Resposta inesperada do ROLE em {}: {}", primary, response),
+                    },
+                    Err(e) => eprintln!("Falha ao consultar o ROLE em {}: {}", primary, e),
+                }
+            }
+
+            self.topology = topology;
+            return Ok(());
+        }
+
+        Err(last_error)
+    }
+
+    async fn send(&mut self, addr: &str, line: &str) -> io::Result<String> {
+        let result = match self.connections.get_mut(addr) {
+            Some(stream) => Self::exchange(stream, line).await,
+            None => match TcpStream::connect(addr).await {
+                Ok(stream) => Self::exchange(self.connections.entry(addr.to_string()).or_insert(stream), line).await,
+                Err(e) => Err(e),
+            },
+        };
+        // Sem conexão com o nó, a topologia pode ter mudado
+        if result.is_err() {
+            self.connections.remove(addr);
+            self.topology.invalidate();
+        }
+        result
+    }
+
+    async fn exchange(stream: &mut TcpStream, line: &str) -> io::Result<String> {
+        send_command(stream, line).await?;
+        read_response(stream).await
+    }
+}
@@ -0,0 +1,59 @@
+use guaradict_core::replica::{ReplicaState, ReplicaStatus, Role};
+
+const HEADERS: [&str; 11] = ["NOME", "PAPEL", "ENDEREÇO", "ESTADO", "PRONTO", "PING (ms)", "FALHAS", "OFFSET", "ATRASO (ops)", "ATRASO (bytes)", "ATRASO (s)"];
+
+/// Exibe a resposta do CLUSTER STATUS como uma tabela, um nó por linha.
+pub fn print(nodes: &[ReplicaStatus]) {
+    let rows = nodes.iter().map(row).collect::<Vec<[String; 11]>>();
+
+    let mut widths = HEADERS.map(|header| header.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    println!("{}", line(&HEADERS.map(str::to_string), &widths));
+    println!("{}", widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<String>>().join("  "));
+    for row in &rows {
+        println!("{}", line(row, &widths));
+    }
+}
+
+fn row(node: &ReplicaStatus) -> [String; 11] {
+    let role = match node.role {
+        Some(Role::Primary) => "primário",
+        Some(Role::Replica) => "réplica",
+        None => "-",
+    };
+    let state = match node.state {
+        ReplicaState::Connecting => "conectando",
+        ReplicaState::Ready => "pronta",
+        ReplicaState::Suspect => "suspeita",
+        ReplicaState::Down => "caída",
+    };
+    [
+        node.name.clone(),
+        role.to_string(),
+        node.addr.map_or("-".to_string(), |addr| addr.to_string()),
+        state.to_string(),
+        if node.ready { "sim" } else { "não" }.to_string(),
+        format!("{:.2}", node.ping.as_secs_f64() * 1000.0),
+        node.failures.to_string(),
+        node.offset.to_string(),
+        node.lag.operations.to_string(),
+        node.lag.bytes.to_string(),
+        format!("{:.2}", node.lag.seconds),
+    ]
+}
+
+fn line(cells: &[String; 11], widths: &[usize; 11]) -> String {
+    cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - cell.chars().count())))
+        .collect::<Vec<String>>()
+        .join("  ")
+        .trim_end()
+        .to_string()
+}
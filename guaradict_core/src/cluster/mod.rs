@@ -0,0 +1,8 @@
+mod slots;
+pub use slots::*;
+
+mod rebalance;
+pub use rebalance::*;
+
+mod topology;
+pub use topology::*;
@@ -0,0 +1,66 @@
+use super::SlotRange;
+
+/// Slots a mover de um primário para outro num rebalanceamento.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotMove {
+    pub range: SlotRange,
+    pub from: String,
+    pub to: String,
+}
+
+/// Planeja a divisão dos slots atribuídos em partes iguais entre `nodes`,
+/// que traz cada primário com os seus slots. Quando a divisão não é exata,
+/// o slot a mais fica com os nós que já têm mais slots. Os nós acima da cota
+/// cedem os slots do fim dos seus intervalos aos nós abaixo dela, o que move
+/// o mínimo de slots.
+pub fn plan_rebalance(nodes: &[(String, Vec<SlotRange>)]) -> Vec<SlotMove> {
+    if nodes.is_empty() {
+        return Vec::new();
+    }
+
+    let counts = nodes
+        .iter()
+        .map(|(_, ranges)| ranges.iter().map(|range| (range.end - range.start) as usize + 1).sum::<usize>())
+        .collect::<Vec<usize>>();
+    let total = counts.iter().sum::<usize>();
+
+    // Ordem dos nós pela quantidade de slots, do maior para o menor
+    let mut order = (0..nodes.len()).collect::<Vec<usize>>();
+    order.sort_by(|a, b| counts[*b].cmp(&counts[*a]).then(a.cmp(b)));
+    let mut quotas = vec![total / nodes.len(); nodes.len()];
+    for index in order.iter().take(total % nodes.len()) {
+        quotas[*index] += 1;
+    }
+
+    // Slots cedidos por cada nó acima da cota, do fim para o início
+    let mut surplus = Vec::new();
+    for (index, (name, ranges)) in nodes.iter().enumerate() {
+        let mut slots = ranges.iter().flat_map(SlotRange::slots).collect::<Vec<u16>>();
+        slots.sort_unstable();
+        let excess = counts[index].saturating_sub(quotas[index]);
+        let mut given = slots.split_off(slots.len() - excess);
+        given.reverse();
+        surplus.extend(given.into_iter().map(|slot| (slot, name)));
+    }
+
+    let mut moves: Vec<SlotMove> = Vec::new();
+    let mut surplus = surplus.into_iter();
+    for (index, (name, _)) in nodes.iter().enumerate() {
+        for _ in counts[index]..quotas[index] {
+            let Some((slot, from)) = surplus.next() else {
+                break;
+            };
+            match moves.last_mut() {
+                // Junta slots consecutivos do mesmo par de nós num intervalo
+                Some(last) if last.from == *from && last.to == *name && last.range.start == slot + 1 => last.range.start = slot,
+                _ => moves.push(SlotMove {
+                    range: SlotRange { start: slot, end: slot },
+                    from: from.clone(),
+                    to: name.clone(),
+                }),
+            }
+        }
+    }
+
+    moves
+}
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::ClusterError;
+
+/// Quantidade de slots em que o espaço de chaves é dividido.
+pub const SLOT_COUNT: u16 = 16384;
+
+/// Slot de uma chave: o CRC16 (XMODEM) da chave módulo `SLOT_COUNT`. Se a
+/// chave tiver um trecho não vazio entre chaves, como em `{usuario:1}:perfil`,
+/// só esse trecho entra no cálculo, o que põe chaves relacionadas no mesmo
+/// slot e permite usá-las juntas num comando.
+pub fn key_slot(key: &str) -> u16 {
+    crc16(hash_tag(key).as_bytes()) % SLOT_COUNT
+}
+
+fn hash_tag(key: &str) -> &str {
+    let Some(start) = key.find('{') else {
+        return key;
+    };
+    match key[start + 1..].find('}') {
+        Some(len) if len > 0 => &key[start + 1..start + 1 + len],
+        _ => key,
+    }
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Intervalo fechado de slots. Na configuração é escrito como `"0-8191"`,
+/// ou só `"42"` para um slot.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct SlotRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl SlotRange {
+    pub fn new(start: u16, end: u16) -> Result<Self, ClusterError> {
+        if start > end || end >= SLOT_COUNT {
+            return Err(ClusterError::InvalidSlotRange(format!("{}-{}", start, end)));
+        }
+        Ok(Self { start, end })
+    }
+
+    pub fn contains(&self, slot: u16) -> bool {
+        (self.start..=self.end).contains(&slot)
+    }
+
+    pub fn slots(&self) -> impl Iterator<Item = u16> {
+        self.start..=self.end
+    }
+}
+
+impl FromStr for SlotRange {
+    type Err = ClusterError;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let invalid = || ClusterError::InvalidSlotRange(range.to_string());
+        let (start, end) = range.split_once('-').unwrap_or((range, range));
+        let start = start.trim().parse().map_err(|_| invalid())?;
+        let end = end.trim().parse().map_err(|_| invalid())?;
+        Self::new(start, end).map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<String> for SlotRange {
+    type Error = ClusterError;
+
+    fn try_from(range: String) -> Result<Self, Self::Error> {
+        range.parse()
+    }
+}
+
+impl From<SlotRange> for String {
+    fn from(range: SlotRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl fmt::Display for SlotRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+/// Primário do cluster, o endereço em que atende os clientes e o do
+/// barramento do cluster, por onde recebe as chaves dos slots migrados.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClusterNode {
+    pub name: String,
+    pub addr: String,
+    pub bus: String,
+}
+
+/// Intervalo contíguo de slots e o nó dono dele, como no CLUSTER SLOTS.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SlotAssignment {
+    pub start: u16,
+    pub end: u16,
+    pub node: String,
+    pub addr: String,
+}
+
+/// Alteração de um slot pelo CLUSTER SETSLOT. Para mover um slot, o
+/// destino o marca como `Importing` do dono, o dono o marca como `Migrating`
+/// para o destino, as chaves são copiadas com MIGRATE e, por fim, todos os
+/// nós recebem `Node` com o novo dono.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlotState {
+    Migrating(String),
+    Importing(String),
+    Node(String),
+    Stable,
+}
+
+impl fmt::Display for SlotState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlotState::Migrating(node) => write!(f, "MIGRATING {}", node),
+            SlotState::Importing(node) => write!(f, "IMPORTING {}", node),
+            SlotState::Node(node) => write!(f, "NODE {}", node),
+            SlotState::Stable => write!(f, "STABLE"),
+        }
+    }
+}
+
+/// Dono de cada slot do cluster. Um nó só atende as chaves dos slots que
+/// são seus; as demais são redirecionadas ao dono com `MOVED`.
+///
+/// Durante a migração de um slot, o dono continua atendendo as chaves que
+/// ainda tem e responde `ASK` para as que já foram (ou nunca existiram), e
+/// o destino atende essas chaves só quando o comando vem precedido de
+/// ASKING. Alterações feitas pelo CLUSTER SETSLOT ficam só em memória.
+#[derive(Debug, Clone)]
+pub struct SlotMap {
+    local: String,
+    nodes: Vec<ClusterNode>,
+    // Índice em `nodes` do dono de cada slot
+    owners: Vec<Option<usize>>,
+    // Slots deste nó sendo movidos e o destino de cada um
+    migrating: HashMap<u16, usize>,
+    // Slots sendo recebidos por este nó e o dono atual de cada um
+    importing: HashMap<u16, usize>,
+}
+
+impl SlotMap {
+    /// `nodes` traz cada primário, inclusive este nó (`local`), com os slots
+    /// atribuídos a ele. Um slot atribuído a dois nós é um erro.
+    pub fn new(local: &str, nodes: Vec<(ClusterNode, Vec<SlotRange>)>) -> Result<Self, ClusterError> {
+        let mut map = Self {
+            local: local.to_string(),
+            nodes: Vec::with_capacity(nodes.len()),
+            owners: vec![None; SLOT_COUNT as usize],
+            migrating: HashMap::new(),
+            importing: HashMap::new(),
+        };
+
+        for (index, (node, ranges)) in nodes.into_iter().enumerate() {
+            for slot in ranges.iter().flat_map(SlotRange::slots) {
+                if let Some(owner) = map.owners[slot as usize] {
+                    return Err(ClusterError::SlotOverlap {
+                        slot,
+                        first: map.nodes[owner].name.clone(),
+                        second: node.name,
+                    });
+                }
+                map.owners[slot as usize] = Some(index);
+            }
+            map.nodes.push(node);
+        }
+
+        Ok(map)
+    }
+
+    pub fn local(&self) -> &str {
+        &self.local
+    }
+
+    pub fn nodes(&self) -> &[ClusterNode] {
+        &self.nodes
+    }
+
+    pub fn owner(&self, slot: u16) -> Option<&ClusterNode> {
+        self.owners.get(slot as usize).copied().flatten().map(|index| &self.nodes[index])
+    }
+
+    pub fn is_local(&self, slot: u16) -> bool {
+        self.owner(slot).is_some_and(|owner| owner.name == self.local)
+    }
+
+    /// Slots do nó, agrupados em intervalos contíguos.
+    pub fn node_slots(&self, name: &str) -> Vec<SlotRange> {
+        self.assignments()
+            .into_iter()
+            .filter(|assignment| assignment.node == name)
+            .map(|assignment| SlotRange { start: assignment.start, end: assignment.end })
+            .collect()
+    }
+
+    /// Intervalos contíguos de slots com o mesmo dono, em ordem. Slots sem
+    /// dono ficam de fora.
+    pub fn assignments(&self) -> Vec<SlotAssignment> {
+        let mut assignments: Vec<SlotAssignment> = Vec::new();
+
+        for (slot, owner) in self.owners.iter().enumerate() {
+            let Some(owner) = owner.map(|index| &self.nodes[index]) else {
+                continue;
+            };
+            let slot = slot as u16;
+            match assignments.last_mut() {
+                Some(last) if last.node == owner.name && last.end + 1 == slot => last.end = slot,
+                _ => assignments.push(SlotAssignment {
+                    start: slot,
+                    end: slot,
+                    node: owner.name.clone(),
+                    addr: owner.addr.clone(),
+                }),
+            }
+        }
+
+        assignments
+    }
+
+    pub fn node(&self, name: &str) -> Option<&ClusterNode> {
+        self.nodes.iter().find(|node| node.name == name)
+    }
+
+    /// Destino do slot, se ele estiver sendo movido por este nó.
+    pub fn migrating(&self, slot: u16) -> Option<&ClusterNode> {
+        self.migrating.get(&slot).map(|index| &self.nodes[*index])
+    }
+
+    /// Dono atual do slot, se ele estiver sendo recebido por este nó.
+    pub fn importing(&self, slot: u16) -> Option<&ClusterNode> {
+        self.importing.get(&slot).map(|index| &self.nodes[*index])
+    }
+
+    pub fn set_slot(&mut self, slot: u16, state: SlotState) -> Result<(), ClusterError> {
+        if slot >= SLOT_COUNT {
+            return Err(ClusterError::InvalidSlotRange(slot.to_string()));
+        }
+        let index = |name: &str| {
+            self.nodes
+                .iter()
+                .position(|node| node.name == name)
+                .ok_or_else(|| ClusterError::UnknownNode(name.to_string()))
+        };
+
+        match state {
+            SlotState::Migrating(target) => {
+                let target = index(&target)?;
+                if !self.is_local(slot) {
+                    return Err(ClusterError::SlotNotOwned(slot));
+                }
+                self.migrating.insert(slot, target);
+            }
+            SlotState::Importing(source) => {
+                let source = index(&source)?;
+                if self.owners[slot as usize] != Some(source) {
+                    return Err(ClusterError::SlotNotOwned(slot));
+                }
+                self.importing.insert(slot, source);
+            }
+            SlotState::Node(owner) => {
+                self.owners[slot as usize] = Some(index(&owner)?);
+                self.migrating.remove(&slot);
+                self.importing.remove(&slot);
+            }
+            SlotState::Stable => {
+                self.migrating.remove(&slot);
+                self.importing.remove(&slot);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifica se este nó atende um comando sobre `keys`. Todas as chaves
+    /// precisam estar no mesmo slot; se ele for de outro nó, o erro traz o
+    /// redirecionamento. Comandos sem chaves são sempre atendidos.
+    ///
+    /// `exists` diz se a chave está neste nó, o que decide entre atender e
+    /// responder `ASK` num slot em migração; `asking` indica que o cliente
+    /// foi redirecionado com `ASK` para cá.
+    pub fn route<'a, I, F>(&self, keys: I, asking: bool, exists: F) -> Result<(), ClusterError>
+    where
+        I: IntoIterator<Item = &'a str>,
+        F: Fn(&str) -> bool,
+    {
+        let keys = keys.into_iter().collect::<Vec<&str>>();
+        let Some(slot) = keys.first().map(|key| key_slot(key)) else {
+            return Ok(());
+        };
+        if keys.iter().any(|key| key_slot(key) != slot) {
+            return Err(ClusterError::CrossSlot);
+        }
+
+        if asking && self.importing.contains_key(&slot) {
+            return Ok(());
+        }
+
+        match self.owner(slot) {
+            Some(owner) if owner.name == self.local => match self.migrating(slot) {
+                Some(target) if !keys.iter().all(|key| exists(key)) => Err(ClusterError::Ask { slot, addr: target.addr.clone() }),
+                _ => Ok(()),
+            },
+            Some(owner) => Err(ClusterError::Moved { slot, addr: owner.addr.clone() }),
+            None => Err(ClusterError::SlotUnassigned(slot)),
+        }
+    }
+}
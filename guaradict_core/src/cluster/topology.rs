@@ -0,0 +1,244 @@
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+use super::{key_slot, SlotAssignment, SLOT_COUNT};
+use crate::errors::ClusterError;
+use crate::replica::{ReplicationLag, Role};
+
+/// Resposta dos comandos CLUSTER num nó fora do modo cluster.
+pub const CLUSTER_DISABLED: &str = "Cluster mode is not enabled";
+
+/// Para onde os drivers mandam as leituras; as escritas vão sempre ao
+/// primário. `Replica` reveza as leituras entre as réplicas prontas e
+/// `Nearest` usa a réplica pronta com o menor ping medido pelo monitor do
+/// primário. Réplicas acima do `maxLag` não contam como prontas; sem
+/// réplicas prontas, a leitura vai ao primário.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadPreference {
+    #[default]
+    Primary,
+    Replica,
+    Nearest,
+}
+
+impl FromStr for ReadPreference {
+    type Err = &'static str;
+
+    fn from_str(preference: &str) -> Result<Self, Self::Err> {
+        match preference {
+            "primary" => Ok(ReadPreference::Primary),
+            "replica" => Ok(ReadPreference::Replica),
+            "nearest" => Ok(ReadPreference::Nearest),
+            _ => Err("Preferência de leitura inválida"),
+        }
+    }
+}
+
+/// Nó da topologia vista pelos drivers.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TopologyNode {
+    pub name: String,
+    pub addr: String,
+    /// Ping medido pelo monitor do primário, em milissegundos. Zero no
+    /// próprio primário.
+    #[serde(default)]
+    pub ping: f64,
+    #[serde(default)]
+    pub ready: bool,
+    #[serde(default)]
+    pub lag: ReplicationLag,
+    /// Atraso acima do `maxLag` do primário: a réplica não recebe leituras.
+    #[serde(default)]
+    pub lagging: bool,
+}
+
+impl TopologyNode {
+    fn primary(name: &str, addr: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            addr: addr.to_string(),
+            ping: 0.0,
+            ready: true,
+            lag: ReplicationLag::default(),
+            lagging: false,
+        }
+    }
+}
+
+/// Resposta do ROLE: o papel do nó, o primário que ele segue e, num
+/// primário, as réplicas com o estado visto pelo monitor.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeRole {
+    pub role: Role,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leader: Option<String>,
+    #[serde(default)]
+    pub replicas: Vec<TopologyNode>,
+}
+
+/// Primário e as réplicas que recebem as escritas dele.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shard {
+    pub primary: TopologyNode,
+    pub replicas: Vec<TopologyNode>,
+}
+
+/// Topologia do cluster mantida pelos drivers: o primário dono de cada
+/// slot e as réplicas de cada primário. É montada com o CLUSTER SLOTS e o
+/// ROLE de cada primário e corrigida pelos `MOVED` recebidos; depois de um
+/// `MOVED`, ou de `max_age` sem consulta, fica desatualizada e deve ser
+/// consultada de novo.
+#[derive(Debug, Clone)]
+pub struct Topology {
+    shards: Vec<Shard>,
+    // Índice em `shards` do dono de cada slot
+    owners: Vec<Option<usize>>,
+    fetched: Instant,
+    stale: bool,
+    // Vez da próxima réplica com `ReadPreference::Replica`
+    turn: usize,
+}
+
+impl Topology {
+    /// Todos os slots no primário em `addr`, como num servidor fora do
+    /// modo cluster.
+    pub fn single(addr: &str) -> Self {
+        Self::from_assignments(vec![SlotAssignment {
+            start: 0,
+            end: SLOT_COUNT - 1,
+            node: addr.to_string(),
+            addr: addr.to_string(),
+        }])
+    }
+
+    pub fn from_assignments(assignments: Vec<SlotAssignment>) -> Self {
+        let mut topology = Self {
+            shards: Vec::new(),
+            owners: vec![None; SLOT_COUNT as usize],
+            fetched: Instant::now(),
+            stale: false,
+            turn: 0,
+        };
+
+        for assignment in assignments {
+            let index = topology.shard_index(&assignment.node, &assignment.addr);
+            for slot in assignment.start..=assignment.end.min(SLOT_COUNT - 1) {
+                topology.owners[slot as usize] = Some(index);
+            }
+        }
+
+        topology
+    }
+
+    /// Topologia a partir da resposta do CLUSTER SLOTS enviado a `seed`.
+    /// Fora do modo cluster, `seed` atende todos os slots.
+    pub fn from_cluster_slots(seed: &str, response: &str) -> Result<Self, ClusterError> {
+        if response == CLUSTER_DISABLED {
+            return Ok(Self::single(seed));
+        }
+
+        serde_json::from_str::<Vec<SlotAssignment>>(response)
+            .map(Self::from_assignments)
+            .map_err(|_| ClusterError::InvalidTopology(response.to_string()))
+    }
+
+    pub fn shards(&self) -> &[Shard] {
+        &self.shards
+    }
+
+    /// Endereços dos primários, para consultar o ROLE de cada um.
+    pub fn primaries(&self) -> Vec<String> {
+        self.shards.iter().map(|shard| shard.primary.addr.clone()).collect()
+    }
+
+    /// Aplica a resposta do ROLE do primário em `addr`: o nome dele e as
+    /// suas réplicas.
+    pub fn set_role(&mut self, addr: &str, role: NodeRole) {
+        if let Some(shard) = self.shards.iter_mut().find(|shard| shard.primary.addr == addr) {
+            shard.primary.name = role.name;
+            shard.replicas = role.replicas;
+        }
+    }
+
+    /// Endereço do nó que deve receber um comando sobre `keys`. O slot é o
+    /// da primeira chave; se as demais estiverem em outros slots, o próprio
+    /// servidor recusa o comando. Comandos sem chaves, ou de slots sem dono
+    /// conhecido, retornam `None` e ficam com a conexão padrão do driver.
+    pub fn route(&mut self, keys: &[&str], read_only: bool, preference: ReadPreference) -> Option<String> {
+        let slot = key_slot(keys.first()?);
+        let index = self.owners[slot as usize]?;
+
+        if !read_only || preference == ReadPreference::Primary {
+            return Some(self.shards[index].primary.addr.clone());
+        }
+
+        let turn = self.turn;
+        self.turn = self.turn.wrapping_add(1);
+
+        let shard = &self.shards[index];
+        let mut ready = shard.replicas.iter().filter(|replica| replica.ready && !replica.lagging).peekable();
+        let replica = match preference {
+            ReadPreference::Replica if ready.peek().is_some() => {
+                let ready = ready.collect::<Vec<&TopologyNode>>();
+                Some(ready[turn % ready.len()])
+            }
+            ReadPreference::Nearest => ready.min_by(|a, b| a.ping.total_cmp(&b.ping)),
+            _ => None,
+        };
+
+        Some(replica.unwrap_or(&shard.primary).addr.clone())
+    }
+
+    /// Registra um `MOVED`: o slot passou ao primário em `addr`. O resto da
+    /// topologia pode ter mudado junto, então ela fica desatualizada.
+    pub fn moved(&mut self, slot: u16, addr: &str) {
+        if slot < SLOT_COUNT {
+            let index = self.shard_index(addr, addr);
+            self.owners[slot as usize] = Some(index);
+        }
+        self.stale = true;
+    }
+
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.stale || self.fetched.elapsed() >= max_age
+    }
+
+    // Índice do primário em `addr`, que é incluído se ainda não existir
+    fn shard_index(&mut self, name: &str, addr: &str) -> usize {
+        if let Some(index) = self.shards.iter().position(|shard| shard.primary.addr == addr) {
+            return index;
+        }
+        self.shards.push(Shard {
+            primary: TopologyNode::primary(name, addr),
+            replicas: Vec::new(),
+        });
+        self.shards.len() - 1
+    }
+}
+
+/// Redirecionamento respondido por um nó do cluster. `Moved` indica o novo
+/// dono do slot; `Ask` vale só para o comando, que deve ser repetido no
+/// destino precedido de ASKING.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Redirect {
+    Moved { slot: u16, addr: String },
+    Ask { slot: u16, addr: String },
+}
+
+impl Redirect {
+    pub fn parse(response: &str) -> Option<Self> {
+        let parts = response.split_whitespace().collect::<Vec<&str>>();
+        match parts.as_slice() {
+            ["MOVED", slot, addr] => Some(Redirect::Moved { slot: slot.parse().ok()?, addr: addr.to_string() }),
+            ["ASK", slot, addr] => Some(Redirect::Ask { slot: slot.parse().ok()?, addr: addr.to_string() }),
+            _ => None,
+        }
+    }
+}
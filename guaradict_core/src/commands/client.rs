@@ -1,9 +1,89 @@
-#[derive(Debug, Clone)]
+use crate::cluster::SlotState;
+use crate::replica::Acks;
+use crate::value::{Sense, Value};
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Command {
     Get(String),
+    GetWithVersion(String),
+    Cas(String, u64, String),
     Set(String, String),
     Add(String, String),
     Del(String),
+    MGet(Vec<String>),
+    MSet(Vec<(String, String)>),
+    MDel(Vec<String>),
+    GetEntry(String),
+    AddSense(String, Sense),
+    DelSense(String, usize),
+    HSet(String, String, String),
+    HGet(String, String),
+    HDel(String, String),
+    HGetAll(String),
+    HIncrBy(String, String, i64),
+    LPush(String, Vec<String>),
+    RPush(String, Vec<String>),
+    LPop(String),
+    RPop(String),
+    BLPop(String, u64),
+    BRPop(String, u64),
+    LRange(String, i64, i64),
+    LLen(String),
+    SAdd(String, Vec<String>),
+    SRem(String, Vec<String>),
+    SIsMember(String, String),
+    SMembers(String),
+    SCard(String),
+    SUnion(Vec<String>),
+    SInter(Vec<String>),
+    SDiff(Vec<String>),
+    Search(String),
+    Multi,
+    Exec,
+    Discard,
+    Watch(Vec<String>),
+    Unwatch,
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+    PSubscribe(Vec<String>),
+    PUnsubscribe(Vec<String>),
+    Publish(String, String),
+    WatchKey(Vec<String>),
+    UnwatchKey(Vec<String>),
+    WatchPrefix(Vec<String>),
+    UnwatchPrefix(Vec<String>),
+    Eval(String, Vec<String>, Vec<String>),
+    EvalSha(String, Vec<String>, Vec<String>),
+    ScriptLoad(String),
+    ScriptExists(Vec<String>),
+    ScriptFlush,
+    Acks(Acks, Box<Command>),
+    Wait(usize, u64),
+    RaftAdd(String, String),
+    RaftRemove(String),
+    RaftStatus,
+    Conflicts,
+    Role,
+    Lag,
+    Metrics,
+    CrdtIncrBy(String, i64),
+    CrdtSAdd(String, Vec<String>),
+    CrdtSRem(String, Vec<String>),
+    CrdtSet(String, String),
+    CrdtHSet(String, String, String),
+    CrdtHDel(String, String),
+    CrdtGet(String),
+    ClusterKeySlot(String),
+    ClusterSlots,
+    ClusterNodes,
+    ClusterStatus,
+    ClusterSetSlot(u16, SlotState),
+    ClusterGetKeysInSlot(u16, usize),
+    ClusterCountKeysInSlot(u16),
+    Asking,
+    Migrate(u16, usize),
+    Restore(String, Value),
+    Ping,
     Quit,
 }
 
@@ -11,27 +91,395 @@ impl Command {
     pub fn execute(&self) -> String {
         match self {
             Command::Get(key) => format!("GET {}", key),
+            Command::GetWithVersion(key) => format!("GET {} WITHVERSION", key),
+            Command::Cas(key, version, value) => format!("CAS {} {} {}", key, version, value),
             Command::Set(key, value) => format!("SET {} {}", key, value),
             Command::Add(key, value) => format!("ADD {} {}", key, value),
             Command::Del(key) => format!("DEL {}", key),
-            Command::Quit => format!("QUIT"),
+            Command::MGet(keys) => format!("MGET {}", keys.join(" ")),
+            Command::MSet(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), serde_json::Value::from(value.as_str())))
+                    .collect::<serde_json::Map<String, serde_json::Value>>();
+                format!("MSET {}", serde_json::Value::Object(entries))
+            },
+            Command::MDel(keys) => format!("MDEL {}", keys.join(" ")),
+            Command::GetEntry(key) => format!("GETENTRY {}", key),
+            Command::AddSense(key, sense) => format!("ADDSENSE {} {}", key, serde_json::to_string(sense).unwrap()),
+            Command::DelSense(key, position) => format!("DELSENSE {} {}", key, position),
+            Command::HSet(key, field, value) => format!("HSET {} {} {}", key, field, value),
+            Command::HGet(key, field) => format!("HGET {} {}", key, field),
+            Command::HDel(key, field) => format!("HDEL {} {}", key, field),
+            Command::HGetAll(key) => format!("HGETALL {}", key),
+            Command::HIncrBy(key, field, by) => format!("HINCRBY {} {} {}", key, field, by),
+            Command::LPush(key, values) => format!("LPUSH {} {}", key, values.join(" ")),
+            Command::RPush(key, values) => format!("RPUSH {} {}", key, values.join(" ")),
+            Command::LPop(key) => format!("LPOP {}", key),
+            Command::RPop(key) => format!("RPOP {}", key),
+            Command::BLPop(key, timeout) => format!("BLPOP {} {}", key, timeout),
+            Command::BRPop(key, timeout) => format!("BRPOP {} {}", key, timeout),
+            Command::LRange(key, start, stop) => format!("LRANGE {} {} {}", key, start, stop),
+            Command::LLen(key) => format!("LLEN {}", key),
+            Command::SAdd(key, members) => format!("SADD {} {}", key, members.join(" ")),
+            Command::SRem(key, members) => format!("SREM {} {}", key, members.join(" ")),
+            Command::SIsMember(key, member) => format!("SISMEMBER {} {}", key, member),
+            Command::SMembers(key) => format!("SMEMBERS {}", key),
+            Command::SCard(key) => format!("SCARD {}", key),
+            Command::SUnion(keys) => format!("SUNION {}", keys.join(" ")),
+            Command::SInter(keys) => format!("SINTER {}", keys.join(" ")),
+            Command::SDiff(keys) => format!("SDIFF {}", keys.join(" ")),
+            Command::Search(query) => format!("SEARCH {}", query),
+            Command::Multi => "MULTI".to_string(),
+            Command::Exec => "EXEC".to_string(),
+            Command::Discard => "DISCARD".to_string(),
+            Command::Watch(keys) => format!("WATCH {}", keys.join(" ")),
+            Command::Unwatch => "UNWATCH".to_string(),
+            Command::Subscribe(channels) => format!("SUBSCRIBE {}", channels.join(" ")),
+            Command::Unsubscribe(channels) => format!("UNSUBSCRIBE {}", channels.join(" ")).trim_end().to_string(),
+            Command::PSubscribe(patterns) => format!("PSUBSCRIBE {}", patterns.join(" ")),
+            Command::PUnsubscribe(patterns) => format!("PUNSUBSCRIBE {}", patterns.join(" ")).trim_end().to_string(),
+            Command::Publish(channel, message) => format!("PUBLISH {} {}", channel, message),
+            Command::WatchKey(keys) => format!("WATCHKEY {}", keys.join(" ")),
+            Command::UnwatchKey(keys) => format!("UNWATCHKEY {}", keys.join(" ")).trim_end().to_string(),
+            Command::WatchPrefix(prefixes) => format!("WATCHPREFIX {}", prefixes.join(" ")),
+            Command::UnwatchPrefix(prefixes) => format!("UNWATCHPREFIX {}", prefixes.join(" ")).trim_end().to_string(),
+            Command::Eval(script, keys, args) => format!("EVAL {}", serde_json::json!({ "script": script, "keys": keys, "args": args })),
+            Command::EvalSha(hash, keys, args) => {
+                let values = keys.iter().chain(args.iter()).map(String::as_str).collect::<Vec<&str>>();
+                format!("EVALSHA {} {} {}", hash, keys.len(), values.join(" ")).trim_end().to_string()
+            },
+            Command::ScriptLoad(script) => format!("SCRIPT LOAD {}", script),
+            Command::ScriptExists(hashes) => format!("SCRIPT EXISTS {}", hashes.join(" ")),
+            Command::ScriptFlush => "SCRIPT FLUSH".to_string(),
+            Command::Acks(acks, command) => format!("ACKS {} {}", acks, command.execute()),
+            Command::Wait(replicas, timeout) => format!("WAIT {} {}", replicas, timeout),
+            Command::RaftAdd(name, addr) => format!("RAFTADD {} {}", name, addr),
+            Command::RaftRemove(name) => format!("RAFTREMOVE {}", name),
+            Command::RaftStatus => "RAFTSTATUS".to_string(),
+            Command::Conflicts => "CONFLICTS".to_string(),
+            Command::Role => "ROLE".to_string(),
+            Command::Lag => "LAG".to_string(),
+            Command::Metrics => "METRICS".to_string(),
+            Command::CrdtIncrBy(key, by) => format!("CRDT INCRBY {} {}", key, by),
+            Command::CrdtSAdd(key, members) => format!("CRDT SADD {} {}", key, members.join(" ")),
+            Command::CrdtSRem(key, members) => format!("CRDT SREM {} {}", key, members.join(" ")),
+            Command::CrdtSet(key, value) => format!("CRDT SET {} {}", key, value),
+            Command::CrdtHSet(key, field, value) => format!("CRDT HSET {} {} {}", key, field, value),
+            Command::CrdtHDel(key, field) => format!("CRDT HDEL {} {}", key, field),
+            Command::CrdtGet(key) => format!("CRDT GET {}", key),
+            Command::ClusterKeySlot(key) => format!("CLUSTER KEYSLOT {}", key),
+            Command::ClusterSlots => "CLUSTER SLOTS".to_string(),
+            Command::ClusterNodes => "CLUSTER NODES".to_string(),
+            Command::ClusterStatus => "CLUSTER STATUS".to_string(),
+            Command::ClusterSetSlot(slot, state) => format!("CLUSTER SETSLOT {} {}", slot, state),
+            Command::ClusterGetKeysInSlot(slot, count) => format!("CLUSTER GETKEYSINSLOT {} {}", slot, count),
+            Command::ClusterCountKeysInSlot(slot) => format!("CLUSTER COUNTKEYSINSLOT {}", slot),
+            Command::Asking => "ASKING".to_string(),
+            Command::Migrate(slot, count) => format!("MIGRATE {} {}", slot, count),
+            Command::Restore(key, value) => format!("RESTORE {} {}", key, serde_json::to_string(value).unwrap()),
+            Command::Ping => "PING".to_string(),
+            Command::Quit => "QUIT".to_string(),
+        }
+    }
+
+    /// Chaves do dicionário lidas ou alteradas pelo comando, usadas para
+    /// encontrar o nó que o atende no modo cluster.
+    pub fn keys(&self) -> Vec<&str> {
+        match self {
+            Command::Get(key)
+            | Command::GetWithVersion(key)
+            | Command::Cas(key, ..)
+            | Command::Set(key, _)
+            | Command::Add(key, _)
+            | Command::Del(key)
+            | Command::GetEntry(key)
+            | Command::AddSense(key, _)
+            | Command::DelSense(key, _)
+            | Command::HSet(key, ..)
+            | Command::HGet(key, _)
+            | Command::HDel(key, _)
+            | Command::HGetAll(key)
+            | Command::HIncrBy(key, ..)
+            | Command::LPush(key, _)
+            | Command::RPush(key, _)
+            | Command::LPop(key)
+            | Command::RPop(key)
+            | Command::BLPop(key, _)
+            | Command::BRPop(key, _)
+            | Command::LRange(key, ..)
+            | Command::LLen(key)
+            | Command::SAdd(key, _)
+            | Command::SRem(key, _)
+            | Command::SIsMember(key, _)
+            | Command::SMembers(key)
+            | Command::SCard(key)
+            | Command::CrdtIncrBy(key, _)
+            | Command::CrdtSAdd(key, _)
+            | Command::CrdtSRem(key, _)
+            | Command::CrdtSet(key, _)
+            | Command::CrdtHSet(key, ..)
+            | Command::CrdtHDel(key, _)
+            | Command::CrdtGet(key)
+            | Command::Restore(key, _) => vec![key],
+            Command::MGet(keys)
+            | Command::MDel(keys)
+            | Command::SUnion(keys)
+            | Command::SInter(keys)
+            | Command::SDiff(keys)
+            | Command::Watch(keys)
+            | Command::Eval(_, keys, _)
+            | Command::EvalSha(_, keys, _) => keys.iter().map(String::as_str).collect(),
+            Command::MSet(entries) => entries.iter().map(|(key, _)| key.as_str()).collect(),
+            Command::Acks(_, command) => command.keys(),
+            _ => Vec::new(),
         }
     }
 
+    /// Comandos que não alteram o dicionário, aceitos também pelos
+    /// seguidores do Raft e que os drivers podem mandar às réplicas.
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Command::Get(_)
+                | Command::GetWithVersion(_)
+                | Command::MGet(_)
+                | Command::GetEntry(_)
+                | Command::HGet(..)
+                | Command::HGetAll(_)
+                | Command::LRange(..)
+                | Command::LLen(_)
+                | Command::SIsMember(..)
+                | Command::SMembers(_)
+                | Command::SCard(_)
+                | Command::SUnion(_)
+                | Command::SInter(_)
+                | Command::SDiff(_)
+                | Command::Search(_)
+                | Command::CrdtGet(_)
+                | Command::Ping
+                | Command::Quit
+        )
+    }
+
 
     pub fn parse(input: &str) -> Result<Command, &'static str> {
-        let parts: Vec<&str> = input.trim().split_whitespace().collect();
+        let parts: Vec<&str> = input.split_whitespace().collect();
         match parts.as_slice() {
             ["GET", key] => Ok(Command::Get(key.to_string())),
+            ["GET", key, "WITHVERSION"] => Ok(Command::GetWithVersion(key.to_string())),
+            ["CAS", key, version, value @ ..] if !value.is_empty() => {
+                let version = version.parse().map_err(|_| "Versão inválida")?;
+                Ok(Command::Cas(key.to_string(), version, value.join(" ")))
+            },
             ["SET", key, value @ ..] => Ok(Command::Set(key.to_string(), value.join(" "))),
             ["DEL", key] => Ok(Command::Del(key.to_string())),
+            ["MGET", keys @ ..] if !keys.is_empty() => Ok(Command::MGet(to_strings(keys))),
+            ["MSET", entries @ ..] if !entries.is_empty() => parse_mset(entries),
+            ["MDEL", keys @ ..] if !keys.is_empty() => Ok(Command::MDel(to_strings(keys))),
             ["ADD", key, value @ ..] => Ok(Command::Add(key.to_string(), value.join(" "))),
+            ["GETENTRY", key] => Ok(Command::GetEntry(key.to_string())),
+            ["ADDSENSE", key, sense @ ..] => {
+                let sense = serde_json::from_str(&sense.join(" ")).map_err(|_| "Acepção inválida")?;
+                Ok(Command::AddSense(key.to_string(), sense))
+            },
+            ["DELSENSE", key, position] => {
+                let position = position.parse().map_err(|_| "Posição de acepção inválida")?;
+                Ok(Command::DelSense(key.to_string(), position))
+            },
+            ["HSET", key, field, value @ ..] if !value.is_empty() => Ok(Command::HSet(key.to_string(), field.to_string(), value.join(" "))),
+            ["HGET", key, field] => Ok(Command::HGet(key.to_string(), field.to_string())),
+            ["HDEL", key, field] => Ok(Command::HDel(key.to_string(), field.to_string())),
+            ["HGETALL", key] => Ok(Command::HGetAll(key.to_string())),
+            ["HINCRBY", key, field, by] => {
+                let by = by.parse().map_err(|_| "Incremento inválido")?;
+                Ok(Command::HIncrBy(key.to_string(), field.to_string(), by))
+            },
+            ["LPUSH", key, values @ ..] if !values.is_empty() => Ok(Command::LPush(key.to_string(), to_strings(values))),
+            ["RPUSH", key, values @ ..] if !values.is_empty() => Ok(Command::RPush(key.to_string(), to_strings(values))),
+            ["LPOP", key] => Ok(Command::LPop(key.to_string())),
+            ["RPOP", key] => Ok(Command::RPop(key.to_string())),
+            ["BLPOP", key, timeout] => {
+                let timeout = timeout.parse().map_err(|_| "Timeout inválido")?;
+                Ok(Command::BLPop(key.to_string(), timeout))
+            },
+            ["BRPOP", key, timeout] => {
+                let timeout = timeout.parse().map_err(|_| "Timeout inválido")?;
+                Ok(Command::BRPop(key.to_string(), timeout))
+            },
+            ["LRANGE", key, start, stop] => {
+                let start = start.parse().map_err(|_| "Índice inválido")?;
+                let stop = stop.parse().map_err(|_| "Índice inválido")?;
+                Ok(Command::LRange(key.to_string(), start, stop))
+            },
+            ["LLEN", key] => Ok(Command::LLen(key.to_string())),
+            ["SADD", key, members @ ..] if !members.is_empty() => Ok(Command::SAdd(key.to_string(), to_strings(members))),
+            ["SREM", key, members @ ..] if !members.is_empty() => Ok(Command::SRem(key.to_string(), to_strings(members))),
+            ["SISMEMBER", key, member] => Ok(Command::SIsMember(key.to_string(), member.to_string())),
+            ["SMEMBERS", key] => Ok(Command::SMembers(key.to_string())),
+            ["SCARD", key] => Ok(Command::SCard(key.to_string())),
+            ["SUNION", keys @ ..] if !keys.is_empty() => Ok(Command::SUnion(to_strings(keys))),
+            ["SINTER", keys @ ..] if !keys.is_empty() => Ok(Command::SInter(to_strings(keys))),
+            ["SDIFF", keys @ ..] if !keys.is_empty() => Ok(Command::SDiff(to_strings(keys))),
+            ["SEARCH", query @ ..] if !query.is_empty() => Ok(Command::Search(query.join(" "))),
+            ["MULTI"] => Ok(Command::Multi),
+            ["EXEC"] => Ok(Command::Exec),
+            ["DISCARD"] => Ok(Command::Discard),
+            ["WATCH", keys @ ..] if !keys.is_empty() => Ok(Command::Watch(to_strings(keys))),
+            ["UNWATCH"] => Ok(Command::Unwatch),
+            ["SUBSCRIBE", channels @ ..] if !channels.is_empty() => Ok(Command::Subscribe(to_strings(channels))),
+            ["UNSUBSCRIBE", channels @ ..] => Ok(Command::Unsubscribe(to_strings(channels))),
+            ["PSUBSCRIBE", patterns @ ..] if !patterns.is_empty() => Ok(Command::PSubscribe(to_strings(patterns))),
+            ["PUNSUBSCRIBE", patterns @ ..] => Ok(Command::PUnsubscribe(to_strings(patterns))),
+            ["PUBLISH", channel, message @ ..] if !message.is_empty() => Ok(Command::Publish(channel.to_string(), message.join(" "))),
+            ["WATCHKEY", keys @ ..] if !keys.is_empty() => Ok(Command::WatchKey(to_strings(keys))),
+            ["UNWATCHKEY", keys @ ..] => Ok(Command::UnwatchKey(to_strings(keys))),
+            ["WATCHPREFIX", prefixes @ ..] if !prefixes.is_empty() => Ok(Command::WatchPrefix(to_strings(prefixes))),
+            ["UNWATCHPREFIX", prefixes @ ..] => Ok(Command::UnwatchPrefix(to_strings(prefixes))),
+            ["EVAL", script @ ..] if !script.is_empty() => parse_eval(&script.join(" ")),
+            ["EVALSHA", hash] => Ok(Command::EvalSha(hash.to_string(), Vec::new(), Vec::new())),
+            ["EVALSHA", hash, numkeys, values @ ..] => {
+                let numkeys: usize = numkeys.parse().map_err(|_| "Número de chaves inválido")?;
+                if numkeys > values.len() {
+                    return Err("Número de chaves inválido");
+                }
+                let (keys, args) = values.split_at(numkeys);
+                Ok(Command::EvalSha(hash.to_string(), to_strings(keys), to_strings(args)))
+            },
+            ["SCRIPT", "LOAD", script @ ..] if !script.is_empty() => Ok(Command::ScriptLoad(script.join(" "))),
+            ["SCRIPT", "EXISTS", hashes @ ..] if !hashes.is_empty() => Ok(Command::ScriptExists(to_strings(hashes))),
+            ["SCRIPT", "FLUSH"] => Ok(Command::ScriptFlush),
+            ["ACKS", _, _, ..] => parse_acks(input),
+            ["WAIT", replicas, timeout] => {
+                let replicas = replicas.parse().map_err(|_| "Número de réplicas inválido")?;
+                let timeout = timeout.parse().map_err(|_| "Tempo limite inválido")?;
+                Ok(Command::Wait(replicas, timeout))
+            },
+            ["RAFTADD", name, addr] => Ok(Command::RaftAdd(name.to_string(), addr.to_string())),
+            ["RAFTREMOVE", name] => Ok(Command::RaftRemove(name.to_string())),
+            ["RAFTSTATUS"] => Ok(Command::RaftStatus),
+            ["CONFLICTS"] => Ok(Command::Conflicts),
+            ["ROLE"] => Ok(Command::Role),
+            ["LAG"] => Ok(Command::Lag),
+            ["METRICS"] => Ok(Command::Metrics),
+            ["CRDT", "INCRBY", key, by] => {
+                let by = by.parse().map_err(|_| "Incremento inválido")?;
+                Ok(Command::CrdtIncrBy(key.to_string(), by))
+            },
+            ["CRDT", "SADD", key, members @ ..] if !members.is_empty() => Ok(Command::CrdtSAdd(key.to_string(), to_strings(members))),
+            ["CRDT", "SREM", key, members @ ..] if !members.is_empty() => Ok(Command::CrdtSRem(key.to_string(), to_strings(members))),
+            ["CRDT", "SET", key, value @ ..] if !value.is_empty() => Ok(Command::CrdtSet(key.to_string(), value.join(" "))),
+            ["CRDT", "HSET", key, field, value @ ..] if !value.is_empty() => {
+                Ok(Command::CrdtHSet(key.to_string(), field.to_string(), value.join(" ")))
+            },
+            ["CRDT", "HDEL", key, field] => Ok(Command::CrdtHDel(key.to_string(), field.to_string())),
+            ["CRDT", "GET", key] => Ok(Command::CrdtGet(key.to_string())),
+            ["CLUSTER", "KEYSLOT", key] => Ok(Command::ClusterKeySlot(key.to_string())),
+            ["CLUSTER", "SLOTS"] => Ok(Command::ClusterSlots),
+            ["CLUSTER", "NODES"] => Ok(Command::ClusterNodes),
+            // REPLICAS é um atalho para o CLUSTER STATUS
+            ["CLUSTER", "STATUS"] | ["REPLICAS"] => Ok(Command::ClusterStatus),
+            ["CLUSTER", "SETSLOT", slot, state @ ..] => {
+                let slot = slot.parse().map_err(|_| "Slot inválido")?;
+                let state = match state {
+                    ["MIGRATING", node] => SlotState::Migrating(node.to_string()),
+                    ["IMPORTING", node] => SlotState::Importing(node.to_string()),
+                    ["NODE", node] => SlotState::Node(node.to_string()),
+                    ["STABLE"] => SlotState::Stable,
+                    _ => return Err("Estado de slot inválido"),
+                };
+                Ok(Command::ClusterSetSlot(slot, state))
+            },
+            ["CLUSTER", "GETKEYSINSLOT", slot, count] => {
+                let slot = slot.parse().map_err(|_| "Slot inválido")?;
+                let count = count.parse().map_err(|_| "Quantidade inválida")?;
+                Ok(Command::ClusterGetKeysInSlot(slot, count))
+            },
+            ["CLUSTER", "COUNTKEYSINSLOT", slot] => {
+                let slot = slot.parse().map_err(|_| "Slot inválido")?;
+                Ok(Command::ClusterCountKeysInSlot(slot))
+            },
+            ["ASKING"] => Ok(Command::Asking),
+            ["MIGRATE", slot, count] => {
+                let slot = slot.parse().map_err(|_| "Slot inválido")?;
+                let count = count.parse().map_err(|_| "Quantidade inválida")?;
+                Ok(Command::Migrate(slot, count))
+            },
+            ["RESTORE", _, _, ..] => parse_restore(input),
+            ["PING"] => Ok(Command::Ping),
             ["QUIT"] => Ok(Command::Quit),
             _ => Err("Comando inválido"),
         }
     }
 }
 
+fn to_strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+// Definições com espaços chegam como objeto JSON; sem ele, os pares são
+// `chave valor`. O JSON é tentado primeiro para que chaves com hash tag,
+// como `{user}:1`, não sejam confundidas com um objeto
+fn parse_mset(entries: &[&str]) -> Result<Command, &'static str> {
+    match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&entries.join(" ")) {
+        Ok(entries) if entries.is_empty() => Err("Entradas inválidas"),
+        Ok(entries) => entries
+            .into_iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(value) => Ok((key, value)),
+                _ => Err("Entradas inválidas"),
+            })
+            .collect::<Result<Vec<(String, String)>, _>>()
+            .map(Command::MSet),
+        Err(_) if entries.len().is_multiple_of(2) => Ok(Command::MSet(
+            entries.chunks(2).map(|pair| (pair[0].to_string(), pair[1].to_string())).collect(),
+        )),
+        Err(_) => Err("Entradas inválidas"),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct EvalRequest {
+    script: String,
+    #[serde(default)]
+    keys: Vec<String>,
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+// Aceita {"script": ..., "keys": [...], "args": [...]} ou só o código do
+// script, sem chaves nem argumentos
+fn parse_eval(input: &str) -> Result<Command, &'static str> {
+    match serde_json::from_str::<EvalRequest>(input) {
+        Ok(request) => Ok(Command::Eval(request.script, request.keys, request.args)),
+        Err(_) if input.starts_with('{') && serde_json::from_str::<serde_json::Value>(input).is_ok() => Err("Script inválido"),
+        Err(_) => Ok(Command::Eval(input.to_string(), Vec::new(), Vec::new())),
+    }
+}
+
+// ACKS <acks> <comando>: o restante da linha é o comando, preservado como
+// veio para não estragar argumentos com espaços (ex.: scripts)
+fn parse_acks(input: &str) -> Result<Command, &'static str> {
+    let input = input.trim_start().strip_prefix("ACKS").unwrap_or(input).trim_start();
+    let (acks, command) = input.split_once(char::is_whitespace).ok_or("Comando inválido")?;
+    let acks = acks.parse::<Acks>().map_err(|_| "Valor de acks inválido")?;
+
+    match Command::parse(command)? {
+        Command::Acks(..) => Err("Comando inválido"),
+        command => Ok(Command::Acks(acks, Box::new(command))),
+    }
+}
+
+// RESTORE <chave> <valor em JSON>: o JSON é o restante da linha, preservado
+// como veio para não alterar os espaços dos textos
+fn parse_restore(input: &str) -> Result<Command, &'static str> {
+    let input = input.trim_start().strip_prefix("RESTORE").unwrap_or(input).trim_start();
+    let (key, value) = input.split_once(char::is_whitespace).ok_or("Comando inválido")?;
+    let value = serde_json::from_str(value).map_err(|_| "Valor inválido")?;
+
+    Ok(Command::Restore(key.to_string(), value))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -48,6 +496,267 @@ mod tests {
         assert_eq!(command.execute(), "ADD key1 value1");
     }
 
+    #[test]
+    fn test_parse_search() {
+        let command = Command::parse("SEARCH rio OR mar").unwrap();
+        assert_eq!(command.execute(), "SEARCH rio OR mar");
+        assert!(Command::parse("SEARCH").is_err());
+    }
+
+    #[test]
+    fn test_parse_add_sense() {
+        let command = Command::parse(r#"ADDSENSE casa {"definition": "lugar de morar", "partOfSpeech": "substantivo"}"#).unwrap();
+        match &command {
+            Command::AddSense(key, sense) => {
+                assert_eq!(key, "casa");
+                assert_eq!(sense.definition, "lugar de morar");
+                assert_eq!(sense.part_of_speech, Some("substantivo".to_string()));
+            },
+            _ => panic!("Comando inesperado: {:?}", command),
+        }
+        assert_eq!(command.execute(), r#"ADDSENSE casa {"definition":"lugar de morar","partOfSpeech":"substantivo"}"#);
+        assert!(Command::parse("ADDSENSE casa lugar de morar").is_err());
+    }
+
+    #[test]
+    fn test_parse_del_sense() {
+        let command = Command::parse("DELSENSE casa 1").unwrap();
+        assert_eq!(command.execute(), "DELSENSE casa 1");
+        assert!(Command::parse("DELSENSE casa um").is_err());
+    }
+
+    #[test]
+    fn test_parse_map_commands() {
+        assert_eq!(Command::parse("HSET casa cor azul claro").unwrap().execute(), "HSET casa cor azul claro");
+        assert_eq!(Command::parse("HINCRBY casa visitas -2").unwrap().execute(), "HINCRBY casa visitas -2");
+        assert!(Command::parse("HSET casa cor").is_err());
+        assert!(Command::parse("HINCRBY casa visitas muitas").is_err());
+    }
+
+    #[test]
+    fn test_parse_list_commands() {
+        assert_eq!(Command::parse("LPUSH fila a b c").unwrap().execute(), "LPUSH fila a b c");
+        assert_eq!(Command::parse("LRANGE fila 0 -1").unwrap().execute(), "LRANGE fila 0 -1");
+        assert_eq!(Command::parse("BRPOP fila 5").unwrap().execute(), "BRPOP fila 5");
+        assert!(Command::parse("RPUSH fila").is_err());
+        assert!(Command::parse("BLPOP fila -1").is_err());
+    }
+
+    #[test]
+    fn test_parse_set_commands() {
+        assert_eq!(Command::parse("SADD tags rio mar").unwrap().execute(), "SADD tags rio mar");
+        assert_eq!(Command::parse("SINTER tags sinonimos").unwrap().execute(), "SINTER tags sinonimos");
+        assert!(Command::parse("SADD tags").is_err());
+        assert!(Command::parse("SUNION").is_err());
+    }
+
+    #[test]
+    fn test_parse_versioned_commands() {
+        assert_eq!(Command::parse("GET casa WITHVERSION").unwrap().execute(), "GET casa WITHVERSION");
+        assert_eq!(Command::parse("CAS casa 3 lugar de morar").unwrap().execute(), "CAS casa 3 lugar de morar");
+        assert!(Command::parse("CAS casa tres lugar").is_err());
+        assert!(Command::parse("CAS casa 3").is_err());
+    }
+
+    #[test]
+    fn test_parse_transaction_commands() {
+        assert_eq!(Command::parse("MULTI").unwrap().execute(), "MULTI");
+        assert_eq!(Command::parse("WATCH casa rio").unwrap().execute(), "WATCH casa rio");
+        assert_eq!(Command::parse("EXEC").unwrap().execute(), "EXEC");
+        assert!(Command::parse("WATCH").is_err());
+        assert!(Command::parse("EXEC agora").is_err());
+    }
+
+    #[test]
+    fn test_parse_batch_commands() {
+        assert_eq!(Command::parse("MGET casa rio").unwrap().execute(), "MGET casa rio");
+        assert_eq!(Command::parse("MDEL casa rio").unwrap().execute(), "MDEL casa rio");
+        assert_eq!(
+            Command::parse(r#"MSET {"casa": "lugar de morar", "rio": "curso de água"}"#).unwrap(),
+            Command::MSet(vec![
+                ("casa".to_string(), "lugar de morar".to_string()),
+                ("rio".to_string(), "curso de água".to_string()),
+            ])
+        );
+        assert_eq!(
+            Command::MSet(vec![("casa".to_string(), "lugar de morar".to_string())]).execute(),
+            r#"MSET {"casa":"lugar de morar"}"#
+        );
+        assert_eq!(Command::parse("MSET casa moradia rio riacho").unwrap().execute(), r#"MSET {"casa":"moradia","rio":"riacho"}"#);
+        assert!(Command::parse("MSET casa").is_err());
+        assert!(Command::parse("MGET").is_err());
+        assert!(Command::parse("MSET {}").is_err());
+        assert!(Command::parse(r#"MSET {"casa": 1}"#).is_err());
+        assert!(Command::parse("MSET").is_err());
+    }
+
+    #[test]
+    fn test_parse_batch_commands_with_hash_tags() {
+        assert_eq!(
+            Command::parse("MSET {user}:1 ana {user}:2 bia").unwrap(),
+            Command::MSet(vec![
+                ("{user}:1".to_string(), "ana".to_string()),
+                ("{user}:2".to_string(), "bia".to_string()),
+            ])
+        );
+        assert_eq!(Command::parse("MGET {user}:1 {user}:2").unwrap().keys(), vec!["{user}:1", "{user}:2"]);
+        assert_eq!(Command::parse("MDEL {user}:1 {user}:2").unwrap().keys(), vec!["{user}:1", "{user}:2"]);
+        // A forma serializada volta ao mesmo comando
+        let command = Command::MSet(vec![("{user}:1".to_string(), "ana maria".to_string())]);
+        assert_eq!(Command::parse(&command.execute()).unwrap(), command);
+        assert!(Command::parse("MSET {user}:1").is_err());
+    }
+
+    #[test]
+    fn test_parse_pubsub_commands() {
+        assert_eq!(Command::parse("SUBSCRIBE noticias alertas").unwrap().execute(), "SUBSCRIBE noticias alertas");
+        assert_eq!(Command::parse("PSUBSCRIBE noticias.*").unwrap().execute(), "PSUBSCRIBE noticias.*");
+        assert_eq!(Command::parse("UNSUBSCRIBE").unwrap().execute(), "UNSUBSCRIBE");
+        assert_eq!(Command::parse("PUBLISH noticias nova palavra").unwrap().execute(), "PUBLISH noticias nova palavra");
+        assert!(Command::parse("SUBSCRIBE").is_err());
+        assert!(Command::parse("PUBLISH noticias").is_err());
+    }
+
+    #[test]
+    fn test_parse_watch_stream_commands() {
+        assert_eq!(Command::parse("WATCHKEY casa rio").unwrap().execute(), "WATCHKEY casa rio");
+        assert_eq!(Command::parse("WATCHPREFIX verbete:").unwrap().execute(), "WATCHPREFIX verbete:");
+        assert_eq!(Command::parse("UNWATCHKEY").unwrap().execute(), "UNWATCHKEY");
+        assert_eq!(Command::parse("UNWATCHPREFIX verbete:").unwrap().execute(), "UNWATCHPREFIX verbete:");
+        assert!(Command::parse("WATCHKEY").is_err());
+        assert!(Command::parse("WATCHPREFIX").is_err());
+    }
+
+    #[test]
+    fn test_parse_script_commands() {
+        assert_eq!(
+            Command::parse(r#"EVAL {"script": "get(KEYS[0])", "keys": ["casa"]}"#).unwrap(),
+            Command::Eval("get(KEYS[0])".to_string(), vec!["casa".to_string()], Vec::new())
+        );
+        assert_eq!(Command::parse("EVAL let x = 1; x + 1").unwrap(), Command::Eval("let x = 1; x + 1".to_string(), Vec::new(), Vec::new()));
+        assert_eq!(
+            Command::Eval("get(KEYS[0])".to_string(), vec!["casa".to_string()], Vec::new()).execute(),
+            r#"EVAL {"args":[],"keys":["casa"],"script":"get(KEYS[0])"}"#
+        );
+        assert_eq!(
+            Command::parse("EVALSHA abc 1 casa 10").unwrap(),
+            Command::EvalSha("abc".to_string(), vec!["casa".to_string()], vec!["10".to_string()])
+        );
+        assert_eq!(Command::parse("EVALSHA abc 1 casa 10").unwrap().execute(), "EVALSHA abc 1 casa 10");
+        assert_eq!(Command::parse("SCRIPT LOAD get(\"casa\")").unwrap().execute(), "SCRIPT LOAD get(\"casa\")");
+        assert_eq!(Command::parse("SCRIPT EXISTS abc def").unwrap().execute(), "SCRIPT EXISTS abc def");
+        assert!(Command::parse(r#"EVAL {"keys": ["casa"]}"#).is_err());
+        assert!(Command::parse("EVALSHA abc 2 casa").is_err());
+        assert!(Command::parse("SCRIPT").is_err());
+    }
+
+    #[test]
+    fn test_parse_write_concern_commands() {
+        assert_eq!(
+            Command::parse("ACKS majority SET casa lugar de morar").unwrap(),
+            Command::Acks(Acks::Majority, Box::new(Command::Set("casa".to_string(), "lugar de morar".to_string())))
+        );
+        assert_eq!(Command::parse("ACKS 2 DEL casa").unwrap().execute(), "ACKS 2 DEL casa");
+        assert_eq!(Command::parse("ACKS 0 EXEC").unwrap(), Command::Acks(Acks::None, Box::new(Command::Exec)));
+        assert_eq!(Command::parse("WAIT 1 500").unwrap(), Command::Wait(1, 500));
+        assert!(Command::parse("ACKS algumas SET casa moradia").is_err());
+        assert!(Command::parse("ACKS all ACKS 1 DEL casa").is_err());
+        assert!(Command::parse("ACKS all").is_err());
+        assert!(Command::parse("WAIT 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_raft_commands() {
+        assert_eq!(
+            Command::parse("RAFTADD raft-node-4 127.0.0.1:13154").unwrap(),
+            Command::RaftAdd("raft-node-4".to_string(), "127.0.0.1:13154".to_string())
+        );
+        assert_eq!(Command::parse("RAFTREMOVE raft-node-2").unwrap().execute(), "RAFTREMOVE raft-node-2");
+        assert_eq!(Command::parse("RAFTSTATUS").unwrap(), Command::RaftStatus);
+        assert!(Command::parse("RAFTADD raft-node-4").is_err());
+    }
+
+    #[test]
+    fn test_parse_conflicts() {
+        assert_eq!(Command::parse("CONFLICTS").unwrap(), Command::Conflicts);
+        assert_eq!(Command::Conflicts.execute(), "CONFLICTS");
+        assert!(Command::parse("CONFLICTS casa").is_err());
+    }
+
+    #[test]
+    fn test_parse_crdt_commands() {
+        assert_eq!(Command::parse("CRDT INCRBY visitas -3").unwrap(), Command::CrdtIncrBy("visitas".to_string(), -3));
+        assert_eq!(
+            Command::parse("CRDT SADD cores azul verde").unwrap(),
+            Command::CrdtSAdd("cores".to_string(), vec!["azul".to_string(), "verde".to_string()])
+        );
+        assert_eq!(
+            Command::parse("CRDT HSET perfil nome Ana Maria").unwrap(),
+            Command::CrdtHSet("perfil".to_string(), "nome".to_string(), "Ana Maria".to_string())
+        );
+        assert_eq!(Command::parse("CRDT GET cores").unwrap(), Command::CrdtGet("cores".to_string()));
+        assert!(Command::parse("CRDT INCRBY visitas muitas").is_err());
+        assert!(Command::parse("CRDT SREM cores").is_err());
+
+        let command = Command::CrdtSet("titulo".to_string(), "Dicionário de bolso".to_string());
+        assert_eq!(Command::parse(&command.execute()).unwrap(), command);
+    }
+
+    #[test]
+    fn test_parse_cluster_commands() {
+        assert_eq!(Command::parse("CLUSTER KEYSLOT casa").unwrap(), Command::ClusterKeySlot("casa".to_string()));
+        assert_eq!(Command::parse("CLUSTER SLOTS").unwrap(), Command::ClusterSlots);
+        assert_eq!(Command::ClusterNodes.execute(), "CLUSTER NODES");
+        assert_eq!(Command::parse("CLUSTER STATUS").unwrap(), Command::ClusterStatus);
+        assert_eq!(Command::parse("REPLICAS").unwrap(), Command::ClusterStatus);
+        assert!(Command::parse("CLUSTER KEYSLOT").is_err());
+    }
+
+    #[test]
+    fn test_parse_slot_migration_commands() {
+        assert_eq!(
+            Command::parse("CLUSTER SETSLOT 42 MIGRATING shard-node-2").unwrap(),
+            Command::ClusterSetSlot(42, SlotState::Migrating("shard-node-2".to_string()))
+        );
+        assert_eq!(Command::parse("CLUSTER SETSLOT 42 STABLE").unwrap(), Command::ClusterSetSlot(42, SlotState::Stable));
+        assert!(Command::parse("CLUSTER SETSLOT 42 MOVING shard-node-2").is_err());
+        assert_eq!(Command::parse("CLUSTER GETKEYSINSLOT 42 10").unwrap(), Command::ClusterGetKeysInSlot(42, 10));
+        assert_eq!(Command::parse("MIGRATE 42 100").unwrap(), Command::Migrate(42, 100));
+        assert_eq!(Command::parse("ASKING").unwrap(), Command::Asking);
+
+        let command = Command::Restore("cores".to_string(), Value::List(vec!["azul  claro".to_string()].into()));
+        assert_eq!(Command::parse(&command.execute()).unwrap(), command);
+        assert!(Command::parse("RESTORE cores azul").is_err());
+    }
+
+    #[test]
+    fn test_command_keys() {
+        assert_eq!(Command::parse("HSET perfil nome Ana").unwrap().keys(), vec!["perfil"]);
+        assert_eq!(Command::parse("MSET a 1 b 2").unwrap().keys(), vec!["a", "b"]);
+        assert_eq!(Command::parse("ACKS 1 DEL casa").unwrap().keys(), vec!["casa"]);
+        assert_eq!(Command::parse(r#"EVAL {"script": "1", "keys": ["x"]}"#).unwrap().keys(), vec!["x"]);
+        assert!(Command::parse("PUBLISH canal oi").unwrap().keys().is_empty());
+        assert!(Command::parse("MULTI").unwrap().keys().is_empty());
+    }
+
+    #[test]
+    fn test_command_is_read_only() {
+        assert!(Command::parse("GET casa").unwrap().is_read_only());
+        assert!(Command::parse("CRDT GET visitas").unwrap().is_read_only());
+        assert!(!Command::parse("SET casa lar").unwrap().is_read_only());
+        assert!(!Command::parse("ACKS 1 GET casa").unwrap().is_read_only());
+        // As transações e o cache de scripts ficam no primário
+        for command in ["MULTI", "DISCARD", "WATCH casa", "UNWATCH", "SCRIPT LOAD get(\"casa\")", "SCRIPT EXISTS abc", "SCRIPT FLUSH"] {
+            assert!(!Command::parse(command).unwrap().is_read_only(), "{}", command);
+        }
+        assert_eq!(Command::parse("ROLE").unwrap(), Command::Role);
+        assert!(Command::parse("PING").unwrap().is_read_only());
+        assert_eq!(Command::parse("LAG").unwrap(), Command::Lag);
+        assert_eq!(Command::parse("METRICS").unwrap(), Command::Metrics);
+        // O PING com nome e versão é o heartbeat entre nós, só no barramento
+        assert!(Command::parse("PING replica-node-1 42").is_err());
+    }
+
     #[test]
     fn test_execute_delete() {
         let command = Command::Del("key1".to_string());
@@ -0,0 +1,63 @@
+use std::io::{self, Read};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Maior resposta aceita, para que um cabeçalho corrompido não reserve
+/// memória à toa.
+pub const MAX_FRAME: usize = 64 * 1024 * 1024;
+
+// "$" seguido do tamanho em decimal
+const MAX_HEADER: usize = 21;
+
+/// Resposta da porta dos clientes: `$<tamanho>\n` seguido do conteúdo, com
+/// o tamanho em bytes, para que respostas grandes ou com várias linhas
+/// cheguem inteiras. Os comandos vão no sentido contrário, um por linha.
+pub fn encode(payload: &str) -> String {
+    format!("${}\n{}", payload.len(), payload)
+}
+
+/// Lê uma resposta inteira, sem consumir o que vier depois dela.
+pub async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<String> {
+    let mut header = Vec::new();
+    loop {
+        match reader.read_u8().await? {
+            b'\n' => break,
+            byte if header.len() < MAX_HEADER => header.push(byte),
+            _ => return Err(invalid_header(&header)),
+        }
+    }
+
+    let mut payload = vec![0; payload_len(&header)?];
+    reader.read_exact(&mut payload).await?;
+    Ok(String::from_utf8_lossy(&payload).to_string())
+}
+
+/// Como `read`, para conexões síncronas.
+pub fn read_blocking<R: Read>(reader: &mut R) -> io::Result<String> {
+    let mut header = Vec::new();
+    let mut byte = [0];
+    loop {
+        reader.read_exact(&mut byte)?;
+        match byte[0] {
+            b'\n' => break,
+            byte if header.len() < MAX_HEADER => header.push(byte),
+            _ => return Err(invalid_header(&header)),
+        }
+    }
+
+    let mut payload = vec![0; payload_len(&header)?];
+    reader.read_exact(&mut payload)?;
+    Ok(String::from_utf8_lossy(&payload).to_string())
+}
+
+fn payload_len(header: &[u8]) -> io::Result<usize> {
+    std::str::from_utf8(header)
+        .ok()
+        .and_then(|header| header.strip_prefix('$'))
+        .and_then(|len| len.parse::<usize>().ok())
+        .filter(|len| *len <= MAX_FRAME)
+        .ok_or_else(|| invalid_header(header))
+}
+
+fn invalid_header(header: &[u8]) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Cabeçalho de resposta inválido: {}", String::from_utf8_lossy(header)))
+}
@@ -1,40 +1,119 @@
+use crate::replica::NodeIdentity;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Command {
-    Ping(String),
-    Pong(String),
+    /// Nó abrindo uma conexão com outro, com a sua identidade.
+    Hello(NodeIdentity),
+    /// Resposta a um `HELLO` aceito, com a identidade de quem aceitou.
+    Welcome(NodeIdentity),
+    /// Resposta a um `HELLO` recusado, com o motivo.
+    Denied(String),
+    /// Heartbeat: nome de quem envia e versão da última operação que ele
+    /// aplicou. A resposta é um `PONG` no mesmo formato.
+    Ping(String, u64),
+    Pong(String, u64),
+    /// Primário do termo pedindo para enviar o log de operações.
+    Sync(u64, String),
+    /// Candidato pedindo voto no termo, com a versão da sua última operação.
+    RequestVote(u64, String, u64),
+    /// Resposta a um pedido de voto: termo do eleitor e se o voto foi dado.
+    Vote(u64, bool),
+    /// Membro do Raft abrindo a conexão para enviar mensagens, com o
+    /// endereço em que recebe as respostas.
+    Raft(String, String),
 }
 
 impl Command {
     pub fn serialize(&self) -> String {
         match self {
-            Command::Ping(replica_name) => format!("PING {}", replica_name),
-            Command::Pong(replica_name) => format!("PONG {}", replica_name),
+            Command::Hello(identity) => format!("HELLO {}", serde_json::to_string(identity).unwrap()),
+            Command::Welcome(identity) => format!("WELCOME {}", serde_json::to_string(identity).unwrap()),
+            Command::Denied(reason) => format!("DENIED {}", reason),
+            Command::Ping(name, offset) => format!("PING {} {}", name, offset),
+            Command::Pong(name, offset) => format!("PONG {} {}", name, offset),
+            Command::Sync(term, leader) => format!("SYNC {} {}", term, leader),
+            Command::RequestVote(term, candidate, offset) => format!("REQUESTVOTE {} {} {}", term, candidate, offset),
+            Command::Vote(term, granted) => format!("VOTE {} {}", term, granted),
+            Command::Raft(name, addr) => format!("RAFT {} {}", name, addr),
         }
     }
 
 
     pub fn parse(input: &str) -> Result<Command, &'static str> {
-        let parts: Vec<&str> = input.trim().split_whitespace().collect();
+        // A identidade vai em JSON, que pode ter espaços
+        if let Some((command @ ("HELLO" | "WELCOME"), identity)) = input.split_once(' ') {
+            let identity = serde_json::from_str(identity).map_err(|_| "Identidade inválida")?;
+            return Ok(if command == "HELLO" { Command::Hello(identity) } else { Command::Welcome(identity) });
+        }
+
+        let parts: Vec<&str> = input.split_whitespace().collect();
         match parts.as_slice() {
-            ["PING", replica_name] => Ok(Command::Ping(replica_name.to_string())),
-            ["PONG", replica_name] => Ok(Command::Pong(replica_name.to_string())),
+            ["DENIED", reason @ ..] => Ok(Command::Denied(reason.join(" "))),
+            ["PING", name, offset] => Ok(Command::Ping(name.to_string(), parse_number(offset)?)),
+            ["PONG", name, offset] => Ok(Command::Pong(name.to_string(), parse_number(offset)?)),
+            ["SYNC", term, leader] => Ok(Command::Sync(parse_number(term)?, leader.to_string())),
+            ["REQUESTVOTE", term, candidate, offset] => Ok(Command::RequestVote(parse_number(term)?, candidate.to_string(), parse_number(offset)?)),
+            ["VOTE", term, granted] => Ok(Command::Vote(parse_number(term)?, granted.parse().map_err(|_| "Voto inválido")?)),
+            ["RAFT", name, addr] => Ok(Command::Raft(name.to_string(), addr.to_string())),
             _ => Err("Comando inválido"),
         }
     }
 }
 
+fn parse_number(value: &str) -> Result<u64, &'static str> {
+    value.parse().map_err(|_| "Número inválido")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::replica::Role;
+
     #[test]
     fn test_execute_ping() {
-        let command = Command::Ping("replica-node-1".into());
-        assert_eq!(command.serialize(), "PING replica-node-1");
+        let command = Command::Ping("replica-node-1".into(), 42);
+        assert_eq!(command.serialize(), "PING replica-node-1 42");
     }
 
     #[test]
     fn test_execute_pong() {
-        let command = Command::Pong("primary-node".into());
-        assert_eq!(command.serialize(), "PONG primary-node");
+        let command = Command::Pong("primary-node".into(), 7);
+        assert_eq!(command.serialize(), "PONG primary-node 7");
+        assert_eq!(Command::parse("PONG primary-node 7"), Ok(command));
+        assert!(Command::parse("PONG primary-node").is_err());
+    }
+
+    #[test]
+    fn test_parse_handshake() {
+        let identity = NodeIdentity {
+            name: "replica-node-1".into(),
+            role: Role::Replica,
+            database: Some("my-database".into()),
+            protocol: 1,
+            cluster_id: None,
+        };
+        let hello = Command::Hello(identity.clone());
+        assert_eq!(Command::parse(&hello.serialize()), Ok(hello));
+        assert_eq!(Command::parse(&Command::Welcome(identity.clone()).serialize()), Ok(Command::Welcome(identity)));
+        assert_eq!(Command::parse("DENIED Cluster diferente"), Ok(Command::Denied("Cluster diferente".into())));
+        assert!(Command::parse("HELLO replica-node-1").is_err());
+    }
+
+    #[test]
+    fn test_parse_election_commands() {
+        assert_eq!(Command::parse("SYNC 2 replica-node-1"), Ok(Command::Sync(2, "replica-node-1".into())));
+        assert_eq!(Command::RequestVote(3, "replica-node-2".into(), 17).serialize(), "REQUESTVOTE 3 replica-node-2 17");
+        assert_eq!(Command::parse("VOTE 3 true"), Ok(Command::Vote(3, true)));
+        assert!(Command::parse("SYNC dois replica-node-1").is_err());
+        assert!(Command::parse("VOTE 3 talvez").is_err());
+    }
+
+    #[test]
+    fn test_parse_raft_handshake() {
+        let command = Command::Raft("raft-node-1".into(), "127.0.0.1:13151".into());
+        assert_eq!(command.serialize(), "RAFT raft-node-1 127.0.0.1:13151");
+        assert_eq!(Command::parse("RAFT raft-node-1 127.0.0.1:13151"), Ok(command));
+        assert!(Command::parse("RAFT raft-node-1").is_err());
     }
 }
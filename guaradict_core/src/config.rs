@@ -2,10 +2,22 @@ use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fs::File;
 use std::io::BufReader;
+use std::time::Duration;
 use serde_yaml::{self, Value};
 use regex::Regex;
 
-use crate::{errors::ConfigFileError, replica::ReplicaStatus};
+use crate::{cluster::SlotRange, errors::ConfigFileError, replica::{Acks, ConflictResolution, Detector, MaxLag, MonitorSettings, ReplicaStatus}};
+
+/// Distância entre a porta dos clientes e a do barramento do cluster, quando
+/// o nó não define `busPort`.
+pub const BUS_PORT_OFFSET: u16 = 10000;
+
+/// Porta do barramento do cluster, por onde passam o handshake, os
+/// heartbeats, a replicação, o Raft e a cópia de slots entre os nós.
+pub fn bus_port(port: u16, bus_port: Option<u16>) -> u16 {
+    // A validação garante que a porta padrão não passa de 65535
+    bus_port.unwrap_or_else(|| port.wrapping_add(BUS_PORT_OFFSET))
+}
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -15,14 +27,35 @@ pub struct Config {
     pub ip: String,
     pub host: String,
     pub port: u16,
+    pub bus_port: Option<u16>,
     pub database: Option<String>,
+    // Nós com identificadores de cluster diferentes recusam a conexão entre si
+    pub cluster_id: Option<String>,
     pub journal: Journal,
     pub replicas: Option<Vec<Replica>>,
+    // Numa réplica: o primário que ela segue e as demais réplicas dele. No
+    // Raft, `peers` são os demais membros do cluster
+    pub primary: Option<Replica>,
+    pub peers: Option<Vec<Replica>>,
+    pub failover: Option<Failover>,
+    pub raft: Option<Raft>,
+    pub multi_primary: Option<MultiPrimary>,
+    pub cluster: Option<Cluster>,
+    pub search_index: Option<bool>,
+    pub script_timeout: Option<u64>,
+    pub write_concern: Option<WriteConcern>,
+    // Num primário: atraso máximo das réplicas que recebem leituras
+    pub max_lag: Option<MaxLag>,
+    pub monitor: Option<Monitor>,
 }
 
 impl Config {
+    pub fn bus_port(&self) -> u16 {
+        bus_port(self.port, self.bus_port)
+    }
+
     pub fn to_yaml_value(&self) -> Value {
-        serde_yaml::to_value(&self).unwrap()
+        serde_yaml::to_value(self).unwrap()
     }
 }
 
@@ -34,16 +67,119 @@ pub struct Replica {
     pub ip: String,
     pub host: String,
     pub port: u16,
+    pub bus_port: Option<u16>,
     pub database: Option<String>,
 }
 
 impl From<Replica> for ReplicaStatus {
     fn from(replica: Replica) -> Self {
         let addr = format!("{}:{}", replica.ip, replica.port).parse().ok();
-        ReplicaStatus::new(replica.name, false, addr)
+        let bus = format!("{}:{}", replica.ip, bus_port(replica.port, replica.bus_port)).parse().ok();
+        ReplicaStatus {
+            bus,
+            ..ReplicaStatus::new(replica.name, false, addr)
+        }
+    }
+}
+
+/// Heartbeats do primário às réplicas. Os tempos são em milissegundos:
+/// `interval` entre verificações, `pingTimeout` para o PONG e
+/// `connectTimeout` para a reconexão. Com `detector: fixed` a réplica cai
+/// depois de `failures` PINGs sem resposta; com `detector: phi` ela cai
+/// quando o phi do silêncio passa de `phiThreshold`. Uma réplica caída é
+/// reconectada com espera crescente, de `backoff` até `maxBackoff`.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Monitor {
+    pub interval: Option<u64>,
+    pub ping_timeout: Option<u64>,
+    pub connect_timeout: Option<u64>,
+    pub failures: Option<u32>,
+    pub detector: Option<Detector>,
+    pub phi_threshold: Option<f64>,
+    pub backoff: Option<u64>,
+    pub max_backoff: Option<u64>,
+}
+
+impl Monitor {
+    pub fn settings(&self) -> MonitorSettings {
+        let default = MonitorSettings::default();
+        MonitorSettings {
+            interval: self.interval.map_or(default.interval, Duration::from_millis),
+            ping_timeout: self.ping_timeout.map_or(default.ping_timeout, Duration::from_millis),
+            connect_timeout: self.connect_timeout.map_or(default.connect_timeout, Duration::from_millis),
+            failures: self.failures.unwrap_or(default.failures),
+            detector: self.detector.unwrap_or(default.detector),
+            phi_threshold: self.phi_threshold.unwrap_or(default.phi_threshold),
+            backoff: self.backoff.map_or(default.backoff, Duration::from_millis),
+            max_backoff: self.max_backoff.map_or(default.max_backoff, Duration::from_millis),
+        }
+    }
+}
+
+/// Failover automático de uma réplica. `timeout` é o tempo, em
+/// milissegundos, sem resposta do primário antes de iniciar uma eleição.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Failover {
+    pub timeout: Option<u64>,
+}
+
+/// Modo de consenso Raft: o nó e seus `peers` formam o cluster, as
+/// escritas só valem depois de gravadas na maioria e o líder é eleito
+/// automaticamente. `tick` é o intervalo do relógio do Raft em
+/// milissegundos; os prazos de eleição e de heartbeat contam em ticks.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Raft {
+    pub tick: Option<u64>,
+    pub election_ticks: Option<u32>,
+    pub heartbeat_ticks: Option<u32>,
+}
+
+/// Modo multi-primário: o nó aceita escritas e as troca com os primários
+/// listados em `replicas`. Escritas concorrentes na mesma chave são
+/// resolvidas por `conflictResolution` (padrão `lww`).
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiPrimary {
+    pub conflict_resolution: Option<ConflictResolution>,
+}
+
+/// Modo cluster: as chaves são divididas em slots e cada primário atende
+/// só os seus. `slots` são os deste nó e `nodes` os demais primários com os
+/// slots de cada um.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Cluster {
+    pub slots: Vec<SlotRange>,
+    pub nodes: Option<Vec<ClusterNode>>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterNode {
+    pub node_type: String,
+    pub name: String,
+    pub ip: String,
+    pub host: String,
+    pub port: u16,
+    pub bus_port: Option<u16>,
+    pub slots: Vec<SlotRange>,
+}
+
+impl ClusterNode {
+    pub fn bus_port(&self) -> u16 {
+        bus_port(self.port, self.bus_port)
     }
 }
 
+/// Confirmações exigidas das réplicas antes de responder a uma escrita.
+/// `timeout` em milissegundos.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct WriteConcern {
+    pub acks: Acks,
+    pub timeout: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct Journal {
     pub strategy: String,
@@ -63,10 +199,7 @@ pub fn parse_config_file(file_path: &str) -> Result<Config, ConfigFileError> {
 
 fn is_valid_ip(ip: &str) -> bool {
     // Verifica se a string pode ser convertida para um endereço IP
-    match ip.parse::<std::net::IpAddr>() {
-        Ok(_) => true,
-        Err(_) => false,
-    }
+    ip.parse::<std::net::IpAddr>().is_ok()
 }
 
 fn validate_database_name(database_name: &str) -> bool {
@@ -75,19 +208,31 @@ fn validate_database_name(database_name: &str) -> bool {
     regex.is_match(database_name)
 }
 
+fn validate_slots(node: &Value) -> Result<(), Box<dyn Error>> {
+    let slots = node
+        .get(Value::String("slots".into()))
+        .and_then(Value::as_sequence)
+        .ok_or("Chave obrigatória ausente: slots")?;
+    for range in slots {
+        let range = range.as_str().ok_or("Intervalo de slots inválido")?;
+        range.parse::<SlotRange>()?;
+    }
+    Ok(())
+}
+
 pub fn validate_config(config: &Value) -> Result<(), Box<dyn Error>> {
     let config = config.as_mapping().ok_or("Configuração YAML inválida")?;
 
     // Verifica se todas as chaves obrigatórias estão presentes
     let required_keys = ["nodeType", "name", "ip", "host", "port"];
     for key in &required_keys {
-        if !config.contains_key(&Value::String(key.to_string())) {
+        if !config.contains_key(Value::String(key.to_string())) {
             return Err(format!("Chave obrigatória ausente: {}", key).into());
         }
     }
 
     let node_type = config
-        .get(&Value::String("nodeType".into()))
+        .get(Value::String("nodeType".into()))
         .and_then(|value| value.as_str())
         .ok_or("Valor de nodeType inválido. Valores permitidos: 'primary', 'replica'")?;
 
@@ -99,7 +244,7 @@ pub fn validate_config(config: &Value) -> Result<(), Box<dyn Error>> {
 
     // Verifica se o valor do 'ip' é um IP válido
     let ip_str = config
-        .get(&Value::String("ip".into()))
+        .get(Value::String("ip".into()))
         .and_then(|value| value.as_str())
         .ok_or("Valor de IP inválido")?;
     if !is_valid_ip(ip_str) {
@@ -108,15 +253,29 @@ pub fn validate_config(config: &Value) -> Result<(), Box<dyn Error>> {
 
     // Verifica se o 'port' está dentro do intervalo válido
     let port = config
-        .get(&Value::String("port".to_string()))
+        .get(Value::String("port".to_string()))
         .and_then(|value| value.as_i64())
         .ok_or("Valor de porta inválido")?;
-    if port < 1 || port > 65535 {
+    if !(1..=65535).contains(&port) {
         return Err("Valor de porta inválido. A porta deve estar entre 1 e 65535".into());
     }
 
+    // Verifica a porta do barramento do cluster, que não pode ser a dos clientes
+    match config.get(Value::String("busPort".into())).filter(|bus_port| !bus_port.is_null()) {
+        Some(bus_port) => {
+            let bus_port = bus_port.as_i64().ok_or("Valor de busPort inválido")?;
+            if !(1..=65535).contains(&bus_port) || bus_port == port {
+                return Err("Valor de busPort inválido. A porta deve estar entre 1 e 65535 e ser diferente de port".into());
+            }
+        }
+        None if port + BUS_PORT_OFFSET as i64 > 65535 => {
+            return Err(format!("Sem busPort, a porta do barramento seria port + {}: defina busPort para portas acima de {}", BUS_PORT_OFFSET, 65535 - BUS_PORT_OFFSET).into());
+        }
+        None => {}
+    }
+
     // Verifica se o nome da base de dados está no formato correto, se presente
-    if let Some(database_name) = config.get(&Value::String("database".into())) {
+    if let Some(database_name) = config.get(Value::String("database".into())) {
         if !database_name.is_null() {
             let database_name_str = database_name
                 .as_str()
@@ -127,8 +286,124 @@ pub fn validate_config(config: &Value) -> Result<(), Box<dyn Error>> {
         }
     }
 
+    if let Some(cluster_id) = config.get(Value::String("clusterId".into())) {
+        if !cluster_id.is_null() && !cluster_id.as_str().is_some_and(validate_database_name) {
+            return Err("Formato de identificador de cluster inválido".into());
+        }
+    }
+
+    // Verifica o primário e as demais réplicas seguidas por uma réplica
+    if let Some(primary) = config.get(Value::String("primary".into())) {
+        if !primary.is_null() {
+            validate_config(primary)?;
+        }
+    }
+    if let Some(peers) = config.get(Value::String("peers".into())) {
+        if !peers.is_null() {
+            for peer in peers.as_sequence().ok_or("Formato de peers inválido")? {
+                validate_config(peer)?;
+            }
+        }
+    }
+
+    // Failover só faz sentido numa réplica que conhece seu primário
+    if let Some(failover) = config.get(Value::String("failover".into())) {
+        if !failover.is_null() && config.get(Value::String("primary".into())).is_none_or(Value::is_null) {
+            return Err("O campo 'failover' exige o campo 'primary'".into());
+        }
+    }
+
+    // No Raft não há primário fixo: o líder é eleito entre os peers
+    if let Some(raft) = config.get(Value::String("raft".into())) {
+        if !raft.is_null() {
+            if config.get(Value::String("failover".into())).is_some_and(|failover| !failover.is_null()) {
+                return Err("Os campos 'raft' e 'failover' não podem ser usados juntos".into());
+            }
+            if config.get(Value::String("peers".into())).is_none_or(Value::is_null) {
+                return Err("O campo 'raft' exige o campo 'peers'".into());
+            }
+        }
+    }
+
+    if let Some(multi_primary) = config.get(Value::String("multiPrimary".into())) {
+        if !multi_primary.is_null() {
+            if config.get(Value::String("nodeType".into())).and_then(Value::as_str) != Some("primary") {
+                return Err("O campo 'multiPrimary' só é permitido em nós do tipo 'primary'".into());
+            }
+            if config.get(Value::String("raft".into())).is_some_and(|raft| !raft.is_null()) {
+                return Err("Os campos 'raft' e 'multiPrimary' não podem ser usados juntos".into());
+            }
+        }
+    }
+
+    // No cluster cada primário atende só os seus slots
+    if let Some(cluster) = config.get(Value::String("cluster".into())) {
+        if !cluster.is_null() {
+            if config.get(Value::String("nodeType".into())).and_then(Value::as_str) != Some("primary") {
+                return Err("O campo 'cluster' só é permitido em nós do tipo 'primary'".into());
+            }
+            for mode in ["raft", "multiPrimary"] {
+                if config.get(Value::String(mode.into())).is_some_and(|value| !value.is_null()) {
+                    return Err(format!("Os campos 'cluster' e '{}' não podem ser usados juntos", mode).into());
+                }
+            }
+            validate_slots(cluster)?;
+            if let Some(nodes) = cluster.get(Value::String("nodes".into())) {
+                if !nodes.is_null() {
+                    for node in nodes.as_sequence().ok_or("Formato de nodes do cluster inválido")? {
+                        validate_config(node)?;
+                        validate_slots(node)?;
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(max_lag) = config.get(Value::String("maxLag".into())) {
+        if !max_lag.is_null() {
+            let max_lag = max_lag.as_mapping().ok_or("Configuração de maxLag inválida")?;
+            for (limit, value) in max_lag {
+                if !matches!(limit.as_str(), Some("operations" | "bytes" | "seconds")) {
+                    return Err("Limite de maxLag inválido. Valores permitidos: 'operations', 'bytes', 'seconds'".into());
+                }
+                if !value.is_null() && !value.as_f64().is_some_and(|value| value >= 0.0) {
+                    return Err("Valor de maxLag inválido. Deve ser um número não negativo".into());
+                }
+            }
+        }
+    }
+
+    if let Some(monitor) = config.get(Value::String("monitor".into())) {
+        if !monitor.is_null() {
+            let monitor = monitor.as_mapping().ok_or("Configuração de monitor inválida")?;
+            for (field, value) in monitor {
+                if value.is_null() {
+                    continue;
+                }
+                match field.as_str() {
+                    Some("interval" | "pingTimeout" | "connectTimeout" | "failures" | "backoff" | "maxBackoff") => {
+                        if value.as_u64().is_none_or(|value| value == 0) {
+                            return Err(format!("Valor de '{}' do monitor inválido. Deve ser um inteiro positivo", field.as_str().unwrap()).into());
+                        }
+                    }
+                    Some("detector") => {
+                        if !matches!(value.as_str(), Some("fixed" | "phi")) {
+                            return Err("Detector do monitor inválido. Valores permitidos: 'fixed', 'phi'".into());
+                        }
+                    }
+                    Some("phiThreshold") => {
+                        if !value.as_f64().is_some_and(|value| value > 0.0) {
+                            return Err("Valor de phiThreshold inválido. Deve ser um número positivo".into());
+                        }
+                    }
+                    _ => return Err("Campo de monitor inválido. Valores permitidos: 'interval', 'pingTimeout', 'connectTimeout', 'failures', 'detector', 'phiThreshold', 'backoff', 'maxBackoff'".into()),
+                }
+            }
+        }
+    }
+
     // Verifica as réplicas e o journal, se presentes
-    let replicas = config.get(&Value::String("replicas".into()));
+    let replicas = config.get(Value::String("replicas".into()));
 
     if let Some(replicas) = replicas {
         if !replicas.is_null() {
@@ -136,7 +411,7 @@ pub fn validate_config(config: &Value) -> Result<(), Box<dyn Error>> {
                 .as_sequence()
                 .ok_or("Formato de réplicas inválido")?;
             for replica in replicas_array {
-                if replica.get(&Value::String("journal".into())).is_some() {
+                if replica.get(Value::String("journal".into())).is_some() {
                     return Err("O campo 'journal' não é permitido dentro do campo 'replicas'".into());
                 }
                 validate_config(replica)?;
@@ -145,16 +420,16 @@ pub fn validate_config(config: &Value) -> Result<(), Box<dyn Error>> {
 
         // Se não houver "replicas", verifica se "journal" está presente e valida suas configurações
         let journal = config
-            .get(&Value::String("journal".into()))
+            .get(Value::String("journal".into()))
             .ok_or("Chave obrigatória ausente: journal")?;
         let journal_mapping = journal
             .as_mapping()
             .ok_or("Configuração de journal inválida")?;
         let strategy = journal_mapping
-            .get(&Value::String("strategy".into()))
+            .get(Value::String("strategy".into()))
             .ok_or("Estratégia de journal ausente")?;
         let size = journal_mapping
-            .get(&Value::String("size".into()))
+            .get(Value::String("size".into()))
             .ok_or("Tamanho de journal ausente")?;
 
         let strategy_str = strategy
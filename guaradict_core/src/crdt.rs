@@ -0,0 +1,268 @@
+use std::collections::{BTreeMap, BTreeSet};
+use serde::{Deserialize, Serialize};
+
+use crate::replica::HlcTimestamp;
+
+/// Contador que aceita incrementos e decrementos concorrentes em nós
+/// diferentes: cada nó soma os seus em separado e o valor é a diferença
+/// entre os totais.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct PnCounter {
+    increments: BTreeMap<String, u64>,
+    decrements: BTreeMap<String, u64>,
+}
+
+impl PnCounter {
+    pub fn value(&self) -> i64 {
+        let total = |counts: &BTreeMap<String, u64>| counts.values().map(|count| *count as i128).sum::<i128>();
+        (total(&self.increments) - total(&self.decrements)).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+
+    /// Soma `by` na parcela do nó, retornando o delta a replicar.
+    pub fn increment(&mut self, node: &str, by: i64) -> PnCounter {
+        let counts = if by >= 0 { &mut self.increments } else { &mut self.decrements };
+        let count = counts.entry(node.to_string()).or_default();
+        *count = count.saturating_add(by.unsigned_abs());
+
+        let mut delta = PnCounter::default();
+        let delta_counts = if by >= 0 { &mut delta.increments } else { &mut delta.decrements };
+        delta_counts.insert(node.to_string(), *count);
+        delta
+    }
+
+    pub fn merge(&mut self, other: &PnCounter) -> bool {
+        merge_max(&mut self.increments, &other.increments) | merge_max(&mut self.decrements, &other.decrements)
+    }
+}
+
+fn merge_max(counts: &mut BTreeMap<String, u64>, other: &BTreeMap<String, u64>) -> bool {
+    let mut changed = false;
+    for (node, count) in other {
+        let current = counts.entry(node.clone()).or_default();
+        if count > current {
+            *current = *count;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Conjunto em que uma adição concorrente a uma remoção vence. Cada adição
+/// recebe uma marca única (o instante do relógio híbrido do nó) e a remoção
+/// apaga só as marcas que o nó já tinha visto. As marcas removidas ficam
+/// guardadas para que uma adição antiga não reapareça.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct OrSet {
+    entries: BTreeMap<String, BTreeSet<HlcTimestamp>>,
+    removed: BTreeSet<HlcTimestamp>,
+}
+
+impl OrSet {
+    pub fn contains(&self, member: &str) -> bool {
+        self.entries.contains_key(member)
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = &String> {
+        self.entries.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn add(&mut self, member: &str, dot: HlcTimestamp) -> OrSet {
+        self.entries.entry(member.to_string()).or_default().insert(dot.clone());
+
+        OrSet {
+            entries: BTreeMap::from([(member.to_string(), BTreeSet::from([dot]))]),
+            removed: BTreeSet::new(),
+        }
+    }
+
+    /// Remove o membro, retornando o delta se ele estava no conjunto.
+    pub fn remove(&mut self, member: &str) -> Option<OrSet> {
+        let dots = self.entries.remove(member)?;
+        self.removed.extend(dots.iter().cloned());
+
+        Some(OrSet {
+            entries: BTreeMap::new(),
+            removed: dots,
+        })
+    }
+
+    pub fn merge(&mut self, other: &OrSet) -> bool {
+        let mut changed = false;
+
+        for dot in &other.removed {
+            changed |= self.removed.insert(dot.clone());
+        }
+        for (member, dots) in &other.entries {
+            for dot in dots.iter().filter(|dot| !self.removed.contains(dot)) {
+                changed |= self.entries.entry(member.clone()).or_default().insert(dot.clone());
+            }
+        }
+
+        let removed = &self.removed;
+        for dots in self.entries.values_mut() {
+            dots.retain(|dot| !removed.contains(dot));
+        }
+        self.entries.retain(|_, dots| !dots.is_empty());
+
+        changed
+    }
+
+    fn latest(&self) -> Option<&HlcTimestamp> {
+        self.entries.values().flatten().chain(&self.removed).max()
+    }
+}
+
+/// Registrador de um único valor em que vence a escrita de maior instante.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct LwwRegister {
+    value: Option<String>,
+    timestamp: Option<HlcTimestamp>,
+}
+
+impl LwwRegister {
+    pub fn value(&self) -> Option<&String> {
+        self.value.as_ref()
+    }
+
+    pub fn set(&mut self, value: String, timestamp: HlcTimestamp) -> LwwRegister {
+        self.value = Some(value);
+        self.timestamp = Some(timestamp);
+        self.clone()
+    }
+
+    pub fn merge(&mut self, other: &LwwRegister) -> bool {
+        if other.timestamp > self.timestamp {
+            *self = other.clone();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Mapa de campos de texto: os campos presentes formam um `OrSet`, então
+/// gravar um campo vence uma remoção concorrente, e cada valor é um
+/// `LwwRegister`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct OrMap {
+    fields: OrSet,
+    values: BTreeMap<String, LwwRegister>,
+}
+
+impl OrMap {
+    pub fn get(&self, field: &str) -> Option<&String> {
+        self.fields
+            .contains(field)
+            .then(|| self.values.get(field).and_then(LwwRegister::value))
+            .flatten()
+    }
+
+    pub fn entries(&self) -> BTreeMap<&String, &String> {
+        self.fields
+            .members()
+            .filter_map(|field| self.get(field).map(|value| (field, value)))
+            .collect()
+    }
+
+    pub fn set(&mut self, field: &str, value: String, timestamp: HlcTimestamp) -> OrMap {
+        let fields = self.fields.add(field, timestamp.clone());
+        let register = self.values.entry(field.to_string()).or_default().set(value, timestamp);
+
+        OrMap {
+            fields,
+            values: BTreeMap::from([(field.to_string(), register)]),
+        }
+    }
+
+    pub fn remove(&mut self, field: &str) -> Option<OrMap> {
+        self.fields.remove(field).map(|fields| OrMap {
+            fields,
+            values: BTreeMap::new(),
+        })
+    }
+
+    pub fn merge(&mut self, other: &OrMap) -> bool {
+        let mut changed = self.fields.merge(&other.fields);
+        for (field, register) in &other.values {
+            changed |= self.values.entry(field.clone()).or_default().merge(register);
+        }
+        changed
+    }
+
+    fn latest(&self) -> Option<&HlcTimestamp> {
+        self.fields.latest()
+    }
+}
+
+/// Valor replicado como CRDT: as alterações viajam no log como deltas, que
+/// cada nó funde ao seu estado. A fusão é comutativa, associativa e
+/// idempotente, então os nós convergem sem perder escritas concorrentes,
+/// em qualquer ordem de chegada.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Crdt {
+    Counter(PnCounter),
+    Set(OrSet),
+    Register(LwwRegister),
+    Map(OrMap),
+}
+
+impl Crdt {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Crdt::Counter(_) => "pncounter",
+            Crdt::Set(_) => "orset",
+            Crdt::Register(_) => "lwwregister",
+            Crdt::Map(_) => "ormap",
+        }
+    }
+
+    /// Funde um delta (ou o estado de outro nó) a este valor. Retorna se
+    /// houve alteração; deltas de outro tipo são ignorados.
+    pub fn merge(&mut self, other: &Crdt) -> bool {
+        match (self, other) {
+            (Crdt::Counter(counter), Crdt::Counter(other)) => counter.merge(other),
+            (Crdt::Set(set), Crdt::Set(other)) => set.merge(other),
+            (Crdt::Register(register), Crdt::Register(other)) => register.merge(other),
+            (Crdt::Map(map), Crdt::Map(other)) => map.merge(other),
+            _ => false,
+        }
+    }
+
+    /// Maior instante do relógio híbrido presente no valor.
+    pub fn latest(&self) -> Option<&HlcTimestamp> {
+        match self {
+            Crdt::Counter(_) => None,
+            Crdt::Set(set) => set.latest(),
+            Crdt::Register(register) => register.timestamp.as_ref(),
+            Crdt::Map(map) => map.latest(),
+        }
+    }
+
+    /// Valor visível, no mesmo formato das respostas de leitura.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Crdt::Counter(counter) => serde_json::Value::from(counter.value()),
+            Crdt::Set(set) => serde_json::to_value(set.members().collect::<Vec<&String>>()).unwrap(),
+            Crdt::Register(register) => serde_json::to_value(register.value()).unwrap(),
+            Crdt::Map(map) => serde_json::to_value(map.entries()).unwrap(),
+        }
+    }
+
+    pub fn searchable_text(&self) -> String {
+        match self {
+            Crdt::Counter(_) => String::new(),
+            Crdt::Set(set) => set.members().map(String::as_str).collect::<Vec<&str>>().join("\n"),
+            Crdt::Register(register) => register.value().cloned().unwrap_or_default(),
+            Crdt::Map(map) => map.entries().into_values().map(String::as_str).collect::<Vec<&str>>().join("\n"),
+        }
+    }
+}
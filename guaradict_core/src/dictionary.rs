@@ -1,30 +1,805 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::cluster::key_slot;
+use crate::crdt::{Crdt, LwwRegister, OrMap, OrSet, PnCounter};
+use crate::errors::DictionaryError;
+use crate::replica::{HybridClock, LogOperator, Operation, OperationKind, OperationValue};
+use crate::search::{SearchHit, SearchIndex, SearchQuery};
+use crate::value::{Entry, ListSide, Sense, Value};
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Dictionary {
-    pub entries: HashMap<String, String>,
+    pub entries: HashMap<String, Value>,
+    index: Option<SearchIndex>,
+    // Versão de cada chave: a revisão do dicionário em que ela foi alterada
+    // pela última vez. Cresce a cada mutação e nunca se repete, mesmo se a
+    // chave for removida e recriada.
+    versions: HashMap<String, u64>,
+    revision: u64,
+    // Operações ainda não enviadas para o log de replicação
+    changes: LogOperator,
+    // Identifica as escritas deste nó nos valores CRDT
+    clock: HybridClock,
+}
+
+/// Ponto de retorno para desfazer um grupo de escritas sem copiar o
+/// dicionário: guarda a revisão, quantas operações estavam pendentes e o
+/// valor e a versão anteriores de cada chave marcada com `remember`.
+#[derive(Debug)]
+pub struct Savepoint {
+    revision: u64,
+    operations: usize,
+    keys: HashMap<String, (Option<Value>, u64)>,
+}
+
+impl Savepoint {
+    /// Guarda o estado da chave antes da primeira escrita nela.
+    pub fn remember(&mut self, dictionary: &Dictionary, key: &str) {
+        if !self.keys.contains_key(key) {
+            let previous = (dictionary.get(key).cloned(), dictionary.version(key));
+            self.keys.insert(key.to_string(), previous);
+        }
+    }
+}
+
+impl Default for Dictionary {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Dictionary {
     pub fn new() -> Self {
         Self {
             entries: HashMap::new(),
+            index: None,
+            versions: HashMap::new(),
+            revision: 0,
+            changes: LogOperator::new(),
+            clock: HybridClock::new("local"),
+        }
+    }
+
+    pub fn with_search_index() -> Self {
+        Self {
+            index: Some(SearchIndex::new()),
+            ..Self::new()
         }
     }
 
+    /// Nome do nó nas escritas em valores CRDT. Precisa ser único entre os
+    /// nós que escrevem nas mesmas chaves.
+    pub fn set_node(&mut self, node: &str) {
+        self.clock = HybridClock::new(node);
+    }
+
+    pub fn node(&self) -> &str {
+        self.clock.node()
+    }
+
     pub fn add_entry(&mut self, word: String, definition: String) {
-        self.entries.insert(word, definition);
+        self.put(word, Value::Text(definition));
     }
 
     pub fn remove_entry(&mut self, word: &str) {
-        self.entries.remove(word);
+        if self.entries.remove(word).is_some() {
+            self.changes.delete(word);
+            self.commit(word);
+        }
+    }
+
+    /// Substitui o valor da palavra inteiro, ou a remove com `None`. Usado
+    /// para aplicar o estado vencedor de uma escrita de outro primário.
+    pub fn restore(&mut self, word: &str, value: Option<Value>) {
+        match value {
+            Some(value) => self.put(word.to_string(), value),
+            None => self.remove_entry(word),
+        }
     }
 
     pub fn get_definition(&self, word: &str) -> Option<&String> {
+        self.entries.get(word).and_then(Value::definition)
+    }
+
+    pub fn get_definitions(&self, words: &[String]) -> Vec<Option<&String>> {
+        words.iter().map(|word| self.get_definition(word)).collect()
+    }
+
+    /// Grava várias entradas; as operações ficam juntas no log e são
+    /// replicadas como um único lote.
+    pub fn add_entries(&mut self, entries: Vec<(String, String)>) {
+        for (word, definition) in entries {
+            self.add_entry(word, definition);
+        }
+    }
+
+    /// Remove várias entradas e retorna quantas existiam.
+    pub fn remove_entries(&mut self, words: &[String]) -> usize {
+        words
+            .iter()
+            .filter(|word| {
+                let exists = self.entries.contains_key(word.as_str());
+                self.remove_entry(word);
+                exists
+            })
+            .count()
+    }
+
+    pub fn get(&self, word: &str) -> Option<&Value> {
         self.entries.get(word)
     }
 
+    /// Versão atual da chave; 0 se a chave não existe.
+    pub fn version(&self, key: &str) -> u64 {
+        self.versions.get(key).copied().unwrap_or(0)
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Grava a definição somente se a versão atual da chave for `expected`
+    /// (0 exige que a chave não exista). Retorna a nova versão.
+    pub fn compare_and_set(&mut self, word: &str, expected: u64, definition: String) -> Result<u64, DictionaryError> {
+        let current = self.version(word);
+        if current != expected {
+            return Err(DictionaryError::VersionConflict { expected, current });
+        }
+
+        self.add_entry(word.to_string(), definition);
+        Ok(self.version(word))
+    }
+
+    pub fn get_entry(&self, word: &str) -> Result<Option<Entry>, DictionaryError> {
+        match self.entries.get(word) {
+            Some(value) => value.to_entry().map(Some).ok_or_else(|| DictionaryError::WrongType(word.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Acrescenta uma acepção ao verbete, convertendo um texto simples em
+    /// verbete se necessário. Retorna o número de acepções.
+    pub fn add_sense(&mut self, word: &str, sense: Sense) -> Result<usize, DictionaryError> {
+        let mut entry = self.get_entry(word)?.unwrap_or_default();
+        entry.senses.push(sense);
+        let senses = entry.senses.len();
+
+        self.put(word.to_string(), Value::Entry(entry));
+        Ok(senses)
+    }
+
+    /// Remove a acepção na posição `position`; o verbete é removido junto com
+    /// a última acepção.
+    pub fn remove_sense(&mut self, word: &str, position: usize) -> Result<usize, DictionaryError> {
+        let mut entry = self
+            .get_entry(word)?
+            .ok_or_else(|| DictionaryError::KeyNotFound(word.to_string()))?;
+
+        if position >= entry.senses.len() {
+            return Err(DictionaryError::SenseNotFound(position));
+        }
+
+        entry.senses.remove(position);
+        let senses = entry.senses.len();
+
+        if senses == 0 {
+            self.remove_entry(word);
+        } else {
+            self.put(word.to_string(), Value::Entry(entry));
+        }
+
+        Ok(senses)
+    }
+
+    pub fn map_get_all(&self, key: &str) -> Result<Option<&HashMap<String, String>>, DictionaryError> {
+        match self.entries.get(key) {
+            Some(Value::Map(map)) => Ok(Some(map)),
+            Some(_) => Err(DictionaryError::WrongType(key.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn map_get(&self, key: &str, field: &str) -> Result<Option<&String>, DictionaryError> {
+        Ok(self.map_get_all(key)?.and_then(|map| map.get(field)))
+    }
+
+    /// Define um campo do mapa, criando o mapa se necessário. Retorna `true`
+    /// se o campo não existia.
+    pub fn map_set(&mut self, key: &str, field: &str, value: String) -> Result<bool, DictionaryError> {
+        let map = self.map_mut(key)?;
+        let prev_value = map.insert(field.to_string(), value.clone());
+        let created = prev_value.is_none();
+
+        self.changes.map_set(key, field, value, prev_value);
+        self.commit(key);
+        Ok(created)
+    }
+
+    /// Remove um campo do mapa; o mapa é removido junto com o último campo.
+    pub fn map_delete(&mut self, key: &str, field: &str) -> Result<bool, DictionaryError> {
+        let Some(Value::Map(map)) = self.entries.get_mut(key) else {
+            self.map_get_all(key)?;
+            return Ok(false);
+        };
+
+        let Some(prev_value) = map.remove(field) else {
+            return Ok(false);
+        };
+        if map.is_empty() {
+            self.entries.remove(key);
+        }
+
+        self.changes.map_delete(key, field, Some(prev_value));
+        self.commit(key);
+        Ok(true)
+    }
+
+    /// Incrementa um campo numérico do mapa. O log recebe o valor resultante,
+    /// e não o incremento, para que a replicação seja determinística.
+    pub fn map_increment(&mut self, key: &str, field: &str, by: i64) -> Result<i64, DictionaryError> {
+        let current = match self.map_get(key, field)? {
+            Some(value) => value
+                .parse::<i64>()
+                .map_err(|_| DictionaryError::NotAnInteger(field.to_string()))?,
+            None => 0,
+        };
+        let value = current
+            .checked_add(by)
+            .ok_or_else(|| DictionaryError::NotAnInteger(field.to_string()))?;
+
+        self.map_set(key, field, value.to_string())?;
+        Ok(value)
+    }
+
+    pub fn list_len(&self, key: &str) -> Result<usize, DictionaryError> {
+        match self.entries.get(key) {
+            Some(Value::List(list)) => Ok(list.len()),
+            Some(_) => Err(DictionaryError::WrongType(key.to_string())),
+            None => Ok(0),
+        }
+    }
+
+    /// Elementos entre `start` e `stop` (inclusive). Índices negativos contam
+    /// a partir do fim da lista, como no Redis.
+    pub fn list_range(&self, key: &str, start: i64, stop: i64) -> Result<Vec<String>, DictionaryError> {
+        let list = match self.entries.get(key) {
+            Some(Value::List(list)) => list,
+            Some(_) => return Err(DictionaryError::WrongType(key.to_string())),
+            None => return Ok(Vec::new()),
+        };
+
+        let len = list.len() as i64;
+        let start = if start < 0 { (len + start).max(0) } else { start };
+        let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+        if start > stop {
+            return Ok(Vec::new());
+        }
+
+        Ok(list.range(start as usize..=stop as usize).cloned().collect())
+    }
+
+    /// Insere os valores na extremidade indicada, um a um. Retorna o tamanho
+    /// da lista.
+    pub fn list_push(&mut self, key: &str, side: ListSide, values: Vec<String>) -> Result<usize, DictionaryError> {
+        let list = match self
+            .entries
+            .entry(key.to_string())
+            .or_insert_with(|| Value::List(VecDeque::new()))
+        {
+            Value::List(list) => list,
+            _ => return Err(DictionaryError::WrongType(key.to_string())),
+        };
+
+        for value in &values {
+            match side {
+                ListSide::Left => list.push_front(value.to_string()),
+                ListSide::Right => list.push_back(value.to_string()),
+            }
+        }
+        let len = list.len();
+
+        let kind = match side {
+            ListSide::Left => OperationKind::ListPushLeft,
+            ListSide::Right => OperationKind::ListPushRight,
+        };
+        self.changes.list_push(key, kind, values);
+        self.commit(key);
+        Ok(len)
+    }
+
+    /// Remove um valor da extremidade indicada; a lista é removida junto com o
+    /// último valor.
+    pub fn list_pop(&mut self, key: &str, side: ListSide) -> Result<Option<String>, DictionaryError> {
+        let list = match self.entries.get_mut(key) {
+            Some(Value::List(list)) => list,
+            Some(_) => return Err(DictionaryError::WrongType(key.to_string())),
+            None => return Ok(None),
+        };
+
+        let value = match side {
+            ListSide::Left => list.pop_front(),
+            ListSide::Right => list.pop_back(),
+        };
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        if list.is_empty() {
+            self.entries.remove(key);
+        }
+
+        let kind = match side {
+            ListSide::Left => OperationKind::ListPopLeft,
+            ListSide::Right => OperationKind::ListPopRight,
+        };
+        self.changes.list_pop(key, kind, value.as_str());
+        self.commit(key);
+        Ok(Some(value))
+    }
+
+    pub fn set_members(&self, key: &str) -> Result<Option<&HashSet<String>>, DictionaryError> {
+        match self.entries.get(key) {
+            Some(Value::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(DictionaryError::WrongType(key.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_is_member(&self, key: &str, member: &str) -> Result<bool, DictionaryError> {
+        Ok(self.set_members(key)?.is_some_and(|set| set.contains(member)))
+    }
+
+    pub fn set_len(&self, key: &str) -> Result<usize, DictionaryError> {
+        Ok(self.set_members(key)?.map_or(0, HashSet::len))
+    }
+
+    /// Adiciona os membros ao conjunto. Retorna quantos não existiam; só esses
+    /// vão para o log.
+    pub fn set_add(&mut self, key: &str, members: Vec<String>) -> Result<usize, DictionaryError> {
+        let set = match self
+            .entries
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Set(HashSet::new()))
+        {
+            Value::Set(set) => set,
+            _ => return Err(DictionaryError::WrongType(key.to_string())),
+        };
+
+        let added = members
+            .into_iter()
+            .filter(|member| set.insert(member.to_string()))
+            .collect::<Vec<String>>();
+        if set.is_empty() {
+            self.entries.remove(key);
+        }
+        if added.is_empty() {
+            return Ok(0);
+        }
+
+        let count = added.len();
+        self.changes.set_add(key, added);
+        self.commit(key);
+        Ok(count)
+    }
+
+    /// Remove os membros do conjunto; o conjunto é removido junto com o último
+    /// membro. Retorna quantos foram removidos.
+    pub fn set_remove(&mut self, key: &str, members: Vec<String>) -> Result<usize, DictionaryError> {
+        let set = match self.entries.get_mut(key) {
+            Some(Value::Set(set)) => set,
+            Some(_) => return Err(DictionaryError::WrongType(key.to_string())),
+            None => return Ok(0),
+        };
+
+        let removed = members
+            .into_iter()
+            .filter(|member| set.remove(member))
+            .collect::<Vec<String>>();
+        if set.is_empty() {
+            self.entries.remove(key);
+        }
+        if removed.is_empty() {
+            return Ok(0);
+        }
+
+        let count = removed.len();
+        self.changes.set_remove(key, removed);
+        self.commit(key);
+        Ok(count)
+    }
+
+    pub fn set_union(&self, keys: &[String]) -> Result<HashSet<String>, DictionaryError> {
+        let mut result = HashSet::new();
+        for key in keys {
+            if let Some(set) = self.set_members(key)? {
+                result.extend(set.iter().cloned());
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn set_intersection(&self, keys: &[String]) -> Result<HashSet<String>, DictionaryError> {
+        let mut result: Option<HashSet<String>> = None;
+        for key in keys {
+            let set = self.set_members(key)?.cloned().unwrap_or_default();
+            result = Some(match result {
+                Some(result) => result.intersection(&set).cloned().collect(),
+                None => set,
+            });
+        }
+        Ok(result.unwrap_or_default())
+    }
+
+    /// Membros do primeiro conjunto que não estão em nenhum dos demais.
+    pub fn set_difference(&self, keys: &[String]) -> Result<HashSet<String>, DictionaryError> {
+        let Some((first, others)) = keys.split_first() else {
+            return Ok(HashSet::new());
+        };
+
+        let mut result = self.set_members(first)?.cloned().unwrap_or_default();
+        for key in others {
+            if let Some(set) = self.set_members(key)? {
+                result.retain(|member| !set.contains(member));
+            }
+        }
+        Ok(result)
+    }
+
     pub fn len(&self) -> usize {
         self.entries.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Até `count` chaves do slot, em ordem alfabética. Percorre todas as
+    /// chaves: serve à migração de slots, não a consultas frequentes.
+    pub fn keys_in_slot(&self, slot: u16, count: usize) -> Vec<String> {
+        let mut keys = self.entries.keys().filter(|key| key_slot(key) == slot).cloned().collect::<Vec<String>>();
+        keys.sort();
+        keys.truncate(count);
+        keys
+    }
+
+    pub fn count_keys_in_slot(&self, slot: u16) -> usize {
+        self.entries.keys().filter(|key| key_slot(key) == slot).count()
+    }
+
+    pub fn savepoint(&self) -> Savepoint {
+        Savepoint {
+            revision: self.revision,
+            operations: self.changes.operations.len(),
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Volta as chaves marcadas no `savepoint` ao estado anterior e descarta
+    /// as operações registradas depois dele, que não chegam a ser replicadas.
+    pub fn rollback(&mut self, savepoint: Savepoint) {
+        for (key, (value, version)) in savepoint.keys {
+            match value {
+                Some(value) => {
+                    self.entries.insert(key.clone(), value);
+                    self.versions.insert(key.clone(), version);
+                }
+                None => {
+                    self.entries.remove(&key);
+                    self.versions.remove(&key);
+                }
+            }
+            self.reindex(&key);
+        }
+        self.changes.operations.truncate(savepoint.operations);
+        self.revision = savepoint.revision;
+    }
+
+    /// Retira as operações acumuladas desde a última chamada, para envio ao
+    /// log de replicação.
+    pub fn take_operations(&mut self) -> Vec<Operation> {
+        std::mem::take(&mut self.changes.operations)
+    }
+
+    pub fn has_search_index(&self) -> bool {
+        self.index.is_some()
+    }
+
+    pub fn enable_search_index(&mut self) {
+        if self.index.is_none() {
+            self.index = Some(SearchIndex::new());
+            self.rebuild_search_index();
+        }
+    }
+
+    pub fn disable_search_index(&mut self) {
+        self.index = None;
+    }
+
+    /// Reconstrói o índice de busca a partir das entradas atuais.
+    pub fn rebuild_search_index(&mut self) {
+        if let Some(index) = &mut self.index {
+            index.clear();
+            for (word, value) in &self.entries {
+                index.insert(word, &value.searchable_text());
+            }
+        }
+    }
+
+    pub fn search(&self, query: &str) -> Result<Vec<SearchHit>, DictionaryError> {
+        let index = self.index.as_ref().ok_or(DictionaryError::SearchIndexDisabled)?;
+        let query = SearchQuery::parse(query).map_err(|e| DictionaryError::InvalidQuery(e.to_string()))?;
+
+        Ok(index.search(&query))
+    }
+
+    pub fn crdt_get(&self, key: &str) -> Result<Option<&Crdt>, DictionaryError> {
+        match self.entries.get(key) {
+            Some(Value::Crdt(crdt)) => Ok(Some(crdt)),
+            Some(_) => Err(DictionaryError::WrongType(key.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Soma `by` ao contador PN da chave, criando-o se preciso. Retorna o
+    /// novo valor.
+    pub fn crdt_increment(&mut self, key: &str, by: i64) -> Result<i64, DictionaryError> {
+        let node = self.clock.node().to_string();
+        let Crdt::Counter(counter) = self.crdt_entry(key, || Crdt::Counter(PnCounter::default()))? else {
+            return Err(DictionaryError::WrongType(key.to_string()));
+        };
+
+        let delta = counter.increment(&node, by);
+        let value = counter.value();
+        self.record_crdt(key, Crdt::Counter(delta));
+        Ok(value)
+    }
+
+    /// Adiciona membros ao OR-set da chave. Retorna quantos não estavam nele.
+    pub fn crdt_set_add(&mut self, key: &str, members: Vec<String>) -> Result<usize, DictionaryError> {
+        let dots = members.iter().map(|_| self.clock.now()).collect::<Vec<_>>();
+        let Crdt::Set(set) = self.crdt_entry(key, || Crdt::Set(OrSet::default()))? else {
+            return Err(DictionaryError::WrongType(key.to_string()));
+        };
+
+        let mut delta = OrSet::default();
+        let mut added = 0;
+        for (member, dot) in members.iter().zip(dots) {
+            added += usize::from(!set.contains(member));
+            delta.merge(&set.add(member, dot));
+        }
+        self.record_crdt(key, Crdt::Set(delta));
+        Ok(added)
+    }
+
+    /// Remove membros do OR-set da chave. Retorna quantos estavam nele.
+    pub fn crdt_set_remove(&mut self, key: &str, members: Vec<String>) -> Result<usize, DictionaryError> {
+        let Some(Crdt::Set(set)) = self.crdt_get_mut(key, |crdt| matches!(crdt, Crdt::Set(_)))? else {
+            return Ok(0);
+        };
+
+        let mut delta = OrSet::default();
+        let mut removed = 0;
+        for member in &members {
+            if let Some(member_delta) = set.remove(member) {
+                delta.merge(&member_delta);
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            self.record_crdt(key, Crdt::Set(delta));
+        }
+        Ok(removed)
+    }
+
+    /// Grava o valor do registrador LWW da chave.
+    pub fn crdt_register_set(&mut self, key: &str, value: String) -> Result<(), DictionaryError> {
+        let timestamp = self.clock.now();
+        let Crdt::Register(register) = self.crdt_entry(key, || Crdt::Register(LwwRegister::default()))? else {
+            return Err(DictionaryError::WrongType(key.to_string()));
+        };
+
+        let delta = register.set(value, timestamp);
+        self.record_crdt(key, Crdt::Register(delta));
+        Ok(())
+    }
+
+    /// Grava um campo do OR-map da chave.
+    pub fn crdt_map_set(&mut self, key: &str, field: &str, value: String) -> Result<(), DictionaryError> {
+        let timestamp = self.clock.now();
+        let Crdt::Map(map) = self.crdt_entry(key, || Crdt::Map(OrMap::default()))? else {
+            return Err(DictionaryError::WrongType(key.to_string()));
+        };
+
+        let delta = map.set(field, value, timestamp);
+        self.record_crdt(key, Crdt::Map(delta));
+        Ok(())
+    }
+
+    /// Remove um campo do OR-map da chave. Retorna se o campo existia.
+    pub fn crdt_map_delete(&mut self, key: &str, field: &str) -> Result<bool, DictionaryError> {
+        let Some(Crdt::Map(map)) = self.crdt_get_mut(key, |crdt| matches!(crdt, Crdt::Map(_)))? else {
+            return Ok(false);
+        };
+
+        match map.remove(field) {
+            Some(delta) => {
+                self.record_crdt(key, Crdt::Map(delta));
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Funde um delta CRDT recebido de outro nó ao valor da chave. Retorna
+    /// se o valor mudou; só então a operação vai para o log, o que evita
+    /// reenviar deltas já incorporados.
+    pub fn crdt_merge(&mut self, key: &str, delta: &Crdt) -> bool {
+        if let Some(timestamp) = delta.latest() {
+            self.clock.observe(timestamp);
+        }
+
+        let changed = match self.entries.get_mut(key) {
+            Some(Value::Crdt(crdt)) => crdt.merge(delta),
+            Some(_) => false,
+            None => {
+                self.entries.insert(key.to_string(), Value::Crdt(delta.clone()));
+                true
+            }
+        };
+
+        if changed {
+            self.record_crdt(key, delta.clone());
+        }
+        changed
+    }
+
+    fn crdt_entry<F>(&mut self, key: &str, default: F) -> Result<&mut Crdt, DictionaryError>
+    where
+        F: FnOnce() -> Crdt,
+    {
+        match self.entries.entry(key.to_string()).or_insert_with(|| Value::Crdt(default())) {
+            Value::Crdt(crdt) => Ok(crdt),
+            _ => Err(DictionaryError::WrongType(key.to_string())),
+        }
+    }
+
+    fn crdt_get_mut<F>(&mut self, key: &str, is_type: F) -> Result<Option<&mut Crdt>, DictionaryError>
+    where
+        F: FnOnce(&Crdt) -> bool,
+    {
+        match self.entries.get_mut(key) {
+            Some(Value::Crdt(crdt)) if is_type(crdt) => Ok(Some(crdt)),
+            Some(_) => Err(DictionaryError::WrongType(key.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    fn record_crdt(&mut self, key: &str, delta: Crdt) {
+        self.changes.crdt_merge(key, delta);
+        self.commit(key);
+    }
+
+    /// Aplica uma operação do log ao dicionário.
+    pub fn apply(&mut self, operation: &Operation) {
+        let word = operation.key.to_string();
+
+        // Mantém as versões iguais às do nó que gerou a operação
+        if operation.version > 0 {
+            self.revision = operation.version - 1;
+        }
+
+        match operation.kind {
+            OperationKind::Insert | OperationKind::Update => {
+                if let Some(value) = operation.current_value.as_ref().and_then(|value| Value::try_from(value).ok()) {
+                    self.put(word, value);
+                }
+            }
+            OperationKind::Delete => self.remove_entry(&word),
+            OperationKind::MapSet => {
+                if let (Some(field), Some(OperationValue::StringValue(value))) = (&operation.field, &operation.current_value) {
+                    let _ = self.map_set(&word, &field.to_string(), value.to_string());
+                }
+            }
+            OperationKind::MapDelete => {
+                if let Some(field) = &operation.field {
+                    let _ = self.map_delete(&word, &field.to_string());
+                }
+            }
+            OperationKind::ListPushLeft | OperationKind::ListPushRight => {
+                if let Some(Ok(Value::List(values))) = operation.current_value.as_ref().map(Value::try_from) {
+                    let side = match operation.kind {
+                        OperationKind::ListPushLeft => ListSide::Left,
+                        _ => ListSide::Right,
+                    };
+                    let _ = self.list_push(&word, side, values.into());
+                }
+            }
+            OperationKind::ListPopLeft => {
+                let _ = self.list_pop(&word, ListSide::Left);
+            }
+            OperationKind::ListPopRight => {
+                let _ = self.list_pop(&word, ListSide::Right);
+            }
+            OperationKind::SetAdd => {
+                if let Some(Ok(Value::List(members))) = operation.current_value.as_ref().map(Value::try_from) {
+                    let _ = self.set_add(&word, members.into());
+                }
+            }
+            OperationKind::SetRemove => {
+                if let Some(Ok(Value::List(members))) = operation.prev_value.as_ref().map(Value::try_from) {
+                    let _ = self.set_remove(&word, members.into());
+                }
+            }
+            OperationKind::CrdtMerge => {
+                if let Some(OperationValue::CrdtValue(delta)) = &operation.current_value {
+                    self.crdt_merge(&word, delta);
+                }
+            }
+        }
+
+        self.revision = self.revision.max(operation.version);
+    }
+
+    /// Reaplica as operações do journal e reconstrói o índice de busca uma
+    /// única vez no final, em vez de atualizá-lo a cada operação.
+    pub fn replay<'a, I>(&mut self, operations: I)
+    where
+        I: IntoIterator<Item = &'a Operation>,
+    {
+        let index = self.index.take();
+
+        for operation in operations {
+            self.apply(operation);
+        }
+
+        self.changes.operations.clear();
+        self.index = index;
+        self.rebuild_search_index();
+    }
+
+    fn put(&mut self, word: String, value: Value) {
+        let current_value = OperationValue::from(&value);
+        match self.entries.insert(word.clone(), value) {
+            Some(prev_value) => self.changes.update(word.as_str(), current_value, Some(OperationValue::from(&prev_value))),
+            None => self.changes.insert(word.as_str(), current_value),
+        }
+
+        self.commit(&word);
+    }
+
+    // Finaliza uma mutação da chave: atualiza o índice de busca e carimba a
+    // operação recém registrada com a nova versão da chave
+    fn commit(&mut self, key: &str) {
+        self.reindex(key);
+
+        self.revision += 1;
+        if self.entries.contains_key(key) {
+            self.versions.insert(key.to_string(), self.revision);
+        } else {
+            self.versions.remove(key);
+        }
+        if let Some(operation) = self.changes.operations.last_mut() {
+            operation.version = self.revision;
+        }
+    }
+
+    fn map_mut(&mut self, key: &str) -> Result<&mut HashMap<String, String>, DictionaryError> {
+        match self
+            .entries
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Map(HashMap::new()))
+        {
+            Value::Map(map) => Ok(map),
+            _ => Err(DictionaryError::WrongType(key.to_string())),
+        }
+    }
+
+    // Atualiza o índice de busca com o valor atual da chave
+    fn reindex(&mut self, word: &str) {
+        if let Some(index) = &mut self.index {
+            match self.entries.get(word) {
+                Some(value) => index.insert(word, &value.searchable_text()),
+                None => index.remove(word),
+            }
+        }
+    }
 }
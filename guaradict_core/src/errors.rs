@@ -49,3 +49,177 @@ impl fmt::Display for ConfigError {
 }
 
 impl Error for ConfigError {}
+
+#[derive(Debug, PartialEq)]
+pub enum DictionaryError {
+    KeyNotFound(String),
+    SenseNotFound(usize),
+    WrongType(String),
+    NotAnInteger(String),
+    VersionConflict { expected: u64, current: u64 },
+    SearchIndexDisabled,
+    InvalidQuery(String),
+}
+
+impl fmt::Display for DictionaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DictionaryError::KeyNotFound(key) => write!(f, "Chave não encontrada: {}", key),
+            DictionaryError::SenseNotFound(position) => write!(f, "Acepção não encontrada: {}", position),
+            DictionaryError::WrongType(key) => write!(f, "Operação contra uma chave com o tipo de valor errado: {}", key),
+            DictionaryError::NotAnInteger(field) => write!(f, "O valor não é um inteiro ou está fora do intervalo: {}", field),
+            DictionaryError::VersionConflict { expected, current } => write!(f, "Conflito de versão: esperada {}, atual {}", expected, current),
+            DictionaryError::SearchIndexDisabled => write!(f, "Índice de busca desabilitado"),
+            DictionaryError::InvalidQuery(msg) => write!(f, "Consulta inválida: {}", msg),
+        }
+    }
+}
+
+impl Error for DictionaryError {}
+
+#[derive(Debug, PartialEq)]
+pub enum TransactionError {
+    NotStarted(&'static str),
+    Nested,
+    WatchInsideMulti,
+    Failed,
+    WatchedKeyChanged,
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::NotStarted(command) => write!(f, "{} sem MULTI", command),
+            TransactionError::Nested => write!(f, "MULTI não pode ser aninhado"),
+            TransactionError::WatchInsideMulti => write!(f, "WATCH não é permitido dentro de MULTI"),
+            TransactionError::Failed => write!(f, "Transação descartada por erros anteriores"),
+            TransactionError::WatchedKeyChanged => write!(f, "Transação abortada: chave observada foi alterada"),
+        }
+    }
+}
+
+impl Error for TransactionError {}
+
+#[derive(Debug, PartialEq)]
+pub enum ScriptError {
+    Compile(String),
+    Runtime(String),
+    Timeout(std::time::Duration),
+    NotFound(String),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Compile(msg) => write!(f, "Erro de compilação do script: {}", msg),
+            ScriptError::Runtime(msg) => write!(f, "Erro de execução do script: {}", msg),
+            ScriptError::Timeout(limit) => write!(f, "Script interrompido após exceder o limite de {} ms", limit.as_millis()),
+            ScriptError::NotFound(hash) => write!(f, "Script não encontrado: {}", hash),
+        }
+    }
+}
+
+impl Error for ScriptError {}
+
+#[derive(Debug, PartialEq)]
+pub enum RaftError {
+    NotLeader(Option<String>),
+    MembershipChangePending,
+    UnknownMember(String),
+    AlreadyMember(String),
+}
+
+impl fmt::Display for RaftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RaftError::NotLeader(Some(leader)) => write!(f, "O nó não é o líder; o líder atual é {}", leader),
+            RaftError::NotLeader(None) => write!(f, "O nó não é o líder e nenhum líder é conhecido"),
+            RaftError::MembershipChangePending => write!(f, "Já existe uma alteração de membros pendente"),
+            RaftError::UnknownMember(member) => write!(f, "Membro desconhecido: {}", member),
+            RaftError::AlreadyMember(member) => write!(f, "{} já é membro do cluster", member),
+        }
+    }
+}
+
+impl Error for RaftError {}
+
+#[derive(Debug, PartialEq)]
+pub enum FailoverError {
+    NotPrimary(Option<String>),
+}
+
+impl fmt::Display for FailoverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailoverError::NotPrimary(Some(primary)) => write!(f, "O nó é uma réplica; o primário atual é {}", primary),
+            FailoverError::NotPrimary(None) => write!(f, "O nó é uma réplica e nenhum primário é conhecido"),
+        }
+    }
+}
+
+impl Error for FailoverError {}
+
+#[derive(Debug, PartialEq)]
+pub enum ClusterError {
+    Moved { slot: u16, addr: String },
+    Ask { slot: u16, addr: String },
+    CrossSlot,
+    SlotUnassigned(u16),
+    SlotOverlap { slot: u16, first: String, second: String },
+    InvalidSlotRange(String),
+    UnknownNode(String),
+    SlotNotOwned(u16),
+    NotMigrating(u16),
+    MigrationFailed(String),
+    InvalidTopology(String),
+}
+
+// MOVED, ASK, CROSSSLOT e CLUSTERDOWN iniciam a resposta para que os clientes
+// reconheçam o erro sem depender do texto
+impl fmt::Display for ClusterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClusterError::Moved { slot, addr } => write!(f, "MOVED {} {}", slot, addr),
+            ClusterError::Ask { slot, addr } => write!(f, "ASK {} {}", slot, addr),
+            ClusterError::CrossSlot => write!(f, "CROSSSLOT As chaves do comando pertencem a slots diferentes"),
+            ClusterError::SlotUnassigned(slot) => write!(f, "CLUSTERDOWN O slot {} não está atribuído a nenhum nó", slot),
+            ClusterError::SlotOverlap { slot, first, second } => write!(f, "O slot {} está atribuído a {} e a {}", slot, first, second),
+            ClusterError::InvalidSlotRange(range) => write!(f, "Intervalo de slots inválido: {}", range),
+            ClusterError::UnknownNode(node) => write!(f, "Nó desconhecido no cluster: {}", node),
+            ClusterError::SlotNotOwned(slot) => write!(f, "O slot {} não pertence ao nó esperado", slot),
+            ClusterError::NotMigrating(slot) => write!(f, "O slot {} não está em migração", slot),
+            ClusterError::MigrationFailed(msg) => write!(f, "Falha na migração: {}", msg),
+            ClusterError::InvalidTopology(response) => write!(f, "Topologia inválida: {}", response),
+        }
+    }
+}
+
+impl Error for ClusterError {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum HandshakeError {
+    ProtocolMismatch { local: u32, remote: u32 },
+    DatabaseMismatch { local: Option<String>, remote: Option<String> },
+    ClusterMismatch { local: Option<String>, remote: Option<String> },
+    UnexpectedNode { expected: String, actual: String },
+    Required,
+    Denied(String),
+    InvalidResponse(String),
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = |value: &Option<String>| value.clone().unwrap_or_else(|| "nenhum".to_string());
+        match self {
+            HandshakeError::ProtocolMismatch { local, remote } => write!(f, "Versão de protocolo incompatível: {} (local) e {} (remoto)", local, remote),
+            HandshakeError::DatabaseMismatch { local, remote } => write!(f, "Base de dados diferente: {} (local) e {} (remoto)", name(local), name(remote)),
+            HandshakeError::ClusterMismatch { local, remote } => write!(f, "Cluster diferente: {} (local) e {} (remoto)", name(local), name(remote)),
+            HandshakeError::UnexpectedNode { expected, actual } => write!(f, "Esperava o nó {}, mas respondeu {}", expected, actual),
+            HandshakeError::Required => write!(f, "Conexão sem identificação: envie HELLO antes dos comandos entre nós"),
+            HandshakeError::Denied(reason) => write!(f, "Conexão recusada: {}", reason),
+            HandshakeError::InvalidResponse(response) => write!(f, "Resposta inválida ao HELLO: {}", response),
+        }
+    }
+}
+
+impl Error for HandshakeError {}
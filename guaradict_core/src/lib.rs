@@ -2,6 +2,26 @@ pub mod config;
 pub mod commands;
 pub mod errors;
 pub mod replica;
+pub mod cluster;
+pub mod metrics;
 
 mod dictionary;
 pub use dictionary::*;
+
+mod search;
+pub use search::*;
+
+mod value;
+pub use value::*;
+
+mod crdt;
+pub use crdt::*;
+
+mod transaction;
+pub use transaction::*;
+
+mod pubsub;
+pub use pubsub::*;
+
+mod script;
+pub use script::*;
@@ -0,0 +1,41 @@
+use std::fmt::Write;
+
+/// Métricas no formato de texto do Prometheus, respondidas pelo METRICS.
+/// Cada métrica é declarada com `gauge` e seguida dos seus valores.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    output: String,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str) -> &mut Self {
+        writeln!(self.output, "# HELP {} {}", name, help).unwrap();
+        writeln!(self.output, "# TYPE {} gauge", name).unwrap();
+        self
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        self.output.push_str(name);
+        if !labels.is_empty() {
+            let labels = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+                .collect::<Vec<String>>();
+            write!(self.output, "{{{}}}", labels.join(",")).unwrap();
+        }
+        writeln!(self.output, " {}", value).unwrap();
+        self
+    }
+
+    pub fn render(&self) -> String {
+        self.output.clone()
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
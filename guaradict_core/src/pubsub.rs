@@ -0,0 +1,268 @@
+use std::collections::{BTreeSet, HashMap};
+use serde::Serialize;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::replica::OperationKind;
+
+/// Mensagem enviada a uma conexão inscrita: confirmações de inscrição, as
+/// publicações recebidas e as alterações nas chaves observadas. Cada mensagem
+/// vai pela conexão como uma linha JSON.
+#[derive(Serialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PubSubMessage {
+    Subscribe { channel: String, count: usize },
+    Unsubscribe { channel: String, count: usize },
+    PSubscribe { pattern: String, count: usize },
+    PUnsubscribe { pattern: String, count: usize },
+    WatchKey { key: String, count: usize },
+    UnwatchKey { key: String, count: usize },
+    WatchPrefix { prefix: String, count: usize },
+    UnwatchPrefix { prefix: String, count: usize },
+    Message { channel: String, data: String },
+    PMessage { pattern: String, channel: String, data: String },
+    /// Alteração em uma chave; `value` é o valor da chave depois da
+    /// alteração, ou `null` se ela foi removida.
+    Change { kind: OperationKind, key: String, value: serde_json::Value, version: u64 },
+}
+
+impl PubSubMessage {
+    pub fn to_line(&self) -> String {
+        format!("{}\n", serde_json::to_string(self).unwrap())
+    }
+}
+
+#[derive(Debug)]
+struct Subscriber {
+    sender: UnboundedSender<PubSubMessage>,
+    channels: BTreeSet<String>,
+    patterns: BTreeSet<String>,
+    keys: BTreeSet<String>,
+    prefixes: BTreeSet<String>,
+}
+
+impl Subscriber {
+    fn count(&self) -> usize {
+        self.channels.len() + self.patterns.len() + self.keys.len() + self.prefixes.len()
+    }
+
+    fn watches(&self, key: &str) -> bool {
+        self.keys.contains(key) || self.prefixes.iter().any(|prefix| key.starts_with(prefix.as_str()))
+    }
+}
+
+/// Registro de inscrições em canais, padrões de canais, chaves e prefixos
+/// de chaves.
+#[derive(Debug, Default)]
+pub struct PubSub {
+    subscribers: HashMap<u64, Subscriber>,
+    next_id: u64,
+}
+
+impl PubSub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra uma conexão que pode receber mensagens e retorna seu id.
+    pub fn register(&mut self, sender: UnboundedSender<PubSubMessage>) -> u64 {
+        self.next_id += 1;
+        self.subscribers.insert(self.next_id, Subscriber {
+            sender,
+            channels: BTreeSet::new(),
+            patterns: BTreeSet::new(),
+            keys: BTreeSet::new(),
+            prefixes: BTreeSet::new(),
+        });
+        self.next_id
+    }
+
+    pub fn unregister(&mut self, id: u64) {
+        self.subscribers.remove(&id);
+    }
+
+    /// Quantidade de inscrições da conexão.
+    pub fn subscription_count(&self, id: u64) -> usize {
+        self.subscribers.get(&id).map_or(0, Subscriber::count)
+    }
+
+    pub fn subscribe(&mut self, id: u64, channels: Vec<String>) -> Vec<PubSubMessage> {
+        self.add(id, channels, |subscriber| &mut subscriber.channels, |channel, count| PubSubMessage::Subscribe { channel, count })
+    }
+
+    /// Cancela as inscrições nos canais; sem canais, cancela todas.
+    pub fn unsubscribe(&mut self, id: u64, channels: Vec<String>) -> Vec<PubSubMessage> {
+        self.remove(id, channels, |subscriber| &mut subscriber.channels, |channel, count| PubSubMessage::Unsubscribe { channel, count })
+    }
+
+    pub fn psubscribe(&mut self, id: u64, patterns: Vec<String>) -> Vec<PubSubMessage> {
+        self.add(id, patterns, |subscriber| &mut subscriber.patterns, |pattern, count| PubSubMessage::PSubscribe { pattern, count })
+    }
+
+    /// Cancela as inscrições nos padrões; sem padrões, cancela todas.
+    pub fn punsubscribe(&mut self, id: u64, patterns: Vec<String>) -> Vec<PubSubMessage> {
+        self.remove(id, patterns, |subscriber| &mut subscriber.patterns, |pattern, count| PubSubMessage::PUnsubscribe { pattern, count })
+    }
+
+    pub fn watch_keys(&mut self, id: u64, keys: Vec<String>) -> Vec<PubSubMessage> {
+        self.add(id, keys, |subscriber| &mut subscriber.keys, |key, count| PubSubMessage::WatchKey { key, count })
+    }
+
+    /// Deixa de observar as chaves; sem chaves, deixa de observar todas.
+    pub fn unwatch_keys(&mut self, id: u64, keys: Vec<String>) -> Vec<PubSubMessage> {
+        self.remove(id, keys, |subscriber| &mut subscriber.keys, |key, count| PubSubMessage::UnwatchKey { key, count })
+    }
+
+    pub fn watch_prefixes(&mut self, id: u64, prefixes: Vec<String>) -> Vec<PubSubMessage> {
+        self.add(id, prefixes, |subscriber| &mut subscriber.prefixes, |prefix, count| PubSubMessage::WatchPrefix { prefix, count })
+    }
+
+    /// Deixa de observar os prefixos; sem prefixos, deixa de observar todos.
+    pub fn unwatch_prefixes(&mut self, id: u64, prefixes: Vec<String>) -> Vec<PubSubMessage> {
+        self.remove(id, prefixes, |subscriber| &mut subscriber.prefixes, |prefix, count| PubSubMessage::UnwatchPrefix { prefix, count })
+    }
+
+    /// Entrega a mensagem aos inscritos no canal e nos padrões que o
+    /// reconhecem, retornando quantas entregas foram feitas.
+    pub fn publish(&self, channel: &str, data: &str) -> usize {
+        let mut delivered = 0;
+
+        for subscriber in self.subscribers.values() {
+            if subscriber.channels.contains(channel) {
+                let message = PubSubMessage::Message {
+                    channel: channel.to_string(),
+                    data: data.to_string(),
+                };
+                if subscriber.sender.send(message).is_ok() {
+                    delivered += 1;
+                }
+            }
+
+            for pattern in subscriber.patterns.iter().filter(|pattern| pattern_matches(pattern, channel)) {
+                let message = PubSubMessage::PMessage {
+                    pattern: pattern.to_string(),
+                    channel: channel.to_string(),
+                    data: data.to_string(),
+                };
+                if subscriber.sender.send(message).is_ok() {
+                    delivered += 1;
+                }
+            }
+        }
+
+        delivered
+    }
+
+    /// Indica se alguma conexão observa chaves, para evitar montar as
+    /// notificações de alteração quando ninguém vai recebê-las.
+    pub fn has_watchers(&self) -> bool {
+        self.subscribers.values().any(|subscriber| !subscriber.keys.is_empty() || !subscriber.prefixes.is_empty())
+    }
+
+    /// Notifica uma alteração às conexões que observam a chave ou um prefixo
+    /// dela. Cada conexão recebe a alteração uma única vez.
+    pub fn notify_change(&self, kind: OperationKind, key: &str, value: serde_json::Value, version: u64) -> usize {
+        self.subscribers
+            .values()
+            .filter(|subscriber| subscriber.watches(key))
+            .filter(|subscriber| {
+                let message = PubSubMessage::Change {
+                    kind: kind.clone(),
+                    key: key.to_string(),
+                    value: value.clone(),
+                    version,
+                };
+                subscriber.sender.send(message).is_ok()
+            })
+            .count()
+    }
+
+    fn add<S, C>(&mut self, id: u64, targets: Vec<String>, select: S, confirm: C) -> Vec<PubSubMessage>
+    where
+        S: Fn(&mut Subscriber) -> &mut BTreeSet<String>,
+        C: Fn(String, usize) -> PubSubMessage,
+    {
+        let Some(subscriber) = self.subscribers.get_mut(&id) else {
+            return Vec::new();
+        };
+
+        targets
+            .into_iter()
+            .map(|target| {
+                select(subscriber).insert(target.clone());
+                confirm(target, subscriber.count())
+            })
+            .collect()
+    }
+
+    fn remove<S, C>(&mut self, id: u64, targets: Vec<String>, select: S, confirm: C) -> Vec<PubSubMessage>
+    where
+        S: Fn(&mut Subscriber) -> &mut BTreeSet<String>,
+        C: Fn(String, usize) -> PubSubMessage,
+    {
+        let Some(subscriber) = self.subscribers.get_mut(&id) else {
+            return Vec::new();
+        };
+
+        let targets = if targets.is_empty() {
+            select(subscriber).iter().cloned().collect()
+        } else {
+            targets
+        };
+
+        targets
+            .into_iter()
+            .map(|target| {
+                select(subscriber).remove(&target);
+                confirm(target, subscriber.count())
+            })
+            .collect()
+    }
+}
+
+/// Compara um canal com um padrão glob: `*` reconhece qualquer sequência,
+/// `?` um único caractere e `\` escapa o caractere seguinte.
+pub fn pattern_matches(pattern: &str, channel: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let channel: Vec<char> = channel.chars().collect();
+
+    let (mut p, mut c) = (0, 0);
+    // Posição do último `*` no padrão e do canal quando ele foi encontrado
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while c < channel.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, c));
+                p += 1;
+                continue;
+            }
+            Some('?') => {
+                p += 1;
+                c += 1;
+                continue;
+            }
+            Some('\\') if pattern.get(p + 1) == Some(&channel[c]) => {
+                p += 2;
+                c += 1;
+                continue;
+            }
+            Some(expected) if *expected != '\\' && *expected == channel[c] => {
+                p += 1;
+                c += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        match backtrack {
+            Some((star, matched)) => {
+                p = star + 1;
+                c = matched + 1;
+                backtrack = Some((star, matched + 1));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&token| token == '*')
+}
@@ -0,0 +1,34 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use tokio::time::Duration;
+
+/// Espera entre tentativas de reconexão. Dobra a cada falha, a partir de
+/// `base`, até `max`, e é sorteada entre a metade e o valor cheio para que
+/// as tentativas a réplicas diferentes não caiam no mesmo instante.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self { base, max, attempt: 0 }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.base.saturating_mul(2u32.saturating_pow(self.attempt)).min(self.max);
+        self.attempt = self.attempt.saturating_add(1);
+
+        let half = delay / 2;
+        let random = RandomState::new().build_hasher().finish();
+        let jitter = random % ((delay - half).as_millis() as u64 + 1);
+        half + Duration::from_millis(jitter)
+    }
+
+    /// Volta à espera inicial, depois de uma conexão bem-sucedida.
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use tokio::time::{Duration, Instant};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Primary,
+    Replica,
+}
+
+/// Estado de eleição do nó, no estilo do Raft: cada eleição abre um novo
+/// termo, cada nó vota uma única vez por termo e só há um primário por
+/// termo. Um nó só vota em candidatos com log pelo menos tão atualizado
+/// quanto o seu e quando ele mesmo perdeu o contato com o primário.
+#[derive(Debug)]
+pub struct Election {
+    name: String,
+    role: Role,
+    term: u64,
+    voted_for: Option<String>,
+    leader: Option<String>,
+    last_contact: Instant,
+    // Tempo sem contato com o primário a partir do qual ele é dado como morto
+    timeout: Duration,
+}
+
+impl Election {
+    pub fn new(name: String, role: Role, leader: Option<String>, timeout: Duration) -> Self {
+        let leader = match role {
+            Role::Primary => Some(name.clone()),
+            Role::Replica => leader,
+        };
+
+        Self {
+            name,
+            role,
+            term: 0,
+            voted_for: None,
+            leader,
+            last_contact: Instant::now(),
+            timeout,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn term(&self) -> u64 {
+        self.term
+    }
+
+    pub fn leader(&self) -> Option<&str> {
+        self.leader.as_deref()
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Indica se o nó ainda é o primário eleito no termo.
+    pub fn is_leader(&self, term: u64) -> bool {
+        self.role == Role::Primary && self.term == term
+    }
+
+    /// Registra contato com o primário.
+    pub fn touch(&mut self) {
+        self.last_contact = Instant::now();
+    }
+
+    /// Indica se o primário deu sinal de vida dentro do tempo limite.
+    pub fn leader_alive(&self) -> bool {
+        self.last_contact.elapsed() < self.timeout
+    }
+
+    /// Abre um novo termo com o nó como candidato, votando em si mesmo.
+    pub fn start_election(&mut self) -> u64 {
+        self.term += 1;
+        self.voted_for = Some(self.name.clone());
+        self.leader = None;
+        self.term
+    }
+
+    /// Decide o voto para um candidato; `offset` é a versão da última
+    /// operação aplicada por este nó.
+    pub fn request_vote(&mut self, term: u64, candidate: &str, candidate_offset: u64, offset: u64) -> bool {
+        // Um primário ativo não apoia a própria substituição
+        if self.role == Role::Primary || term < self.term {
+            return false;
+        }
+
+        self.observe_term(term);
+
+        if self.leader_alive() || candidate_offset < offset {
+            return false;
+        }
+
+        match &self.voted_for {
+            Some(voted_for) if voted_for != candidate => false,
+            _ => {
+                self.voted_for = Some(candidate.to_string());
+                // Dá tempo ao candidato antes de começar outra eleição
+                self.touch();
+                true
+            }
+        }
+    }
+
+    /// Acompanha um termo mais novo visto em outro nó.
+    pub fn observe_term(&mut self, term: u64) {
+        if term > self.term {
+            self.term = term;
+            self.voted_for = None;
+        }
+    }
+
+    /// Assume o papel de primário se o nó ainda é candidato no termo.
+    pub fn become_primary(&mut self, term: u64) -> bool {
+        if self.term != term || self.role != Role::Replica || self.voted_for.as_deref() != Some(self.name.as_str()) {
+            return false;
+        }
+
+        self.role = Role::Primary;
+        self.leader = Some(self.name.clone());
+        true
+    }
+
+    /// Aceita `leader` como primário no termo, a menos que o termo esteja
+    /// desatualizado. Um primário que vê um termo mais novo passa a réplica.
+    pub fn accept_leader(&mut self, term: u64, leader: &str) -> bool {
+        if term < self.term || (term == self.term && self.role == Role::Primary && leader != self.name) {
+            return false;
+        }
+
+        self.observe_term(term);
+        self.role = if leader == self.name { Role::Primary } else { Role::Replica };
+        self.leader = Some(leader.to_string());
+        self.touch();
+        true
+    }
+}
+
+/// Estado de eleição compartilhado entre o servidor, a replicação e o
+/// monitor de failover.
+pub type SharedElection = std::sync::Arc<std::sync::Mutex<Election>>;
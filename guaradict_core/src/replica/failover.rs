@@ -0,0 +1,175 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{watch, Mutex};
+use tokio::time::{Duration, Instant};
+
+use super::{read_line, LocalIdentity, LogOperator, ReplicaStatus, ReplicationAcks, ReplicationServer, Role, SharedElection};
+use crate::commands::server;
+
+/// Monitora o primário a partir de uma réplica. Quando o primário fica sem
+/// responder além do tempo limite, a réplica se candidata: abre um novo
+/// termo e pede votos a todos os nós conhecidos. Com a maioria do cluster, é
+/// promovida a primário e passa a enviar o log de operações aos demais, que
+/// reconhecem o novo primário pelo termo do `SYNC`.
+///
+/// Como a maioria é contada sobre o cluster inteiro, incluindo o primário
+/// que caiu, um primário com uma única réplica não tem failover: isso evita
+/// dois primários quando a rede se divide.
+pub struct FailoverMonitor {
+    // Endereço no barramento dos demais nós do cluster: o primário
+    // configurado e as outras réplicas
+    nodes: HashMap<String, ReplicaStatus>,
+    election: SharedElection,
+    identity: LocalIdentity,
+    operations_log: Arc<Mutex<LogOperator>>,
+    offset: watch::Receiver<u64>,
+    acks: Arc<ReplicationAcks>,
+}
+
+impl FailoverMonitor {
+    pub fn new(nodes: Vec<ReplicaStatus>, election: SharedElection, identity: LocalIdentity, operations_log: Arc<Mutex<LogOperator>>, offset: watch::Receiver<u64>, acks: Arc<ReplicationAcks>) -> Self {
+        let nodes = nodes
+            .into_iter()
+            .filter(|node| node.bus.is_some())
+            .map(|node| (node.name.clone(), node))
+            .collect();
+
+        Self {
+            nodes,
+            election,
+            identity,
+            operations_log,
+            offset,
+            acks,
+        }
+    }
+
+    /// Votos necessários para vencer uma eleição.
+    pub fn majority(&self) -> usize {
+        // Metade dos nós mais um, contando este
+        self.nodes.len().div_ceil(2) + 1
+    }
+
+    pub async fn start(&self) {
+        let timeout = self.election.lock().unwrap().timeout();
+        let mut interval = tokio::time::interval((timeout / 4).max(Duration::from_millis(100)));
+        let mut deadline = Instant::now() + election_timeout(timeout);
+
+        loop {
+            interval.tick().await;
+
+            let leader = {
+                let election = self.election.lock().unwrap();
+                if election.role() == Role::Primary {
+                    continue;
+                }
+                election.leader().map(str::to_string)
+            };
+
+            let addr = leader.as_ref().and_then(|leader| self.nodes.get(leader).and_then(|node| node.bus).map(|addr| (leader, addr)));
+            if let Some((leader, addr)) = addr {
+                let ping = server::Command::Ping(self.identity.identity().name, *self.offset.borrow());
+                if self.ping(leader, addr, &ping, timeout / 4).await {
+                    self.election.lock().unwrap().touch();
+                }
+            }
+
+            if self.election.lock().unwrap().leader_alive() {
+                deadline = Instant::now() + election_timeout(timeout);
+            } else if Instant::now() >= deadline {
+                println!("Primário {} sem resposta, iniciando eleição", leader.as_deref().unwrap_or("desconhecido"));
+                self.run_election(timeout / 2).await;
+                // Um novo prazo aleatório evita que as réplicas empatem de novo
+                deadline = Instant::now() + election_timeout(timeout);
+            }
+        }
+    }
+
+    async fn run_election(&self, request_timeout: Duration) {
+        let (name, term) = {
+            let mut election = self.election.lock().unwrap();
+            let term = election.start_election();
+            (election.name().to_string(), term)
+        };
+        let offset = *self.offset.borrow();
+        let request = server::Command::RequestVote(term, name.clone(), offset);
+
+        let requests = self
+            .nodes
+            .values()
+            .filter_map(|node| node.bus.map(|addr| (node.name.clone(), addr)))
+            .map(|(node, addr)| {
+                let (identity, request) = (self.identity.clone(), request.clone());
+                tokio::spawn(async move { request_vote(&identity, &node, addr, &request, request_timeout).await })
+            })
+            .collect::<Vec<_>>();
+
+        let mut votes = 1;
+        for request in requests {
+            match request.await {
+                Ok(Some((_, true))) => votes += 1,
+                Ok(Some((voter_term, false))) => self.election.lock().unwrap().observe_term(voter_term),
+                _ => {}
+            }
+        }
+
+        println!("Eleição do termo {}: {} de {} votos necessários", term, votes, self.majority());
+        if votes < self.majority() || !self.election.lock().unwrap().become_primary(term) {
+            return;
+        }
+
+        println!("{} promovido a primário no termo {}", name, term);
+        self.acks.set_replica_count(self.nodes.len());
+
+        let nodes = self.nodes.values().cloned().collect();
+        ReplicationServer::new(nodes, self.operations_log.clone(), self.offset.clone(), self.acks.clone(), self.election.clone(), self.identity.clone())
+            .start()
+            .await;
+    }
+
+    // O primário só conta como vivo se quem responde é ele mesmo
+    async fn ping(&self, leader: &str, addr: SocketAddr, ping: &server::Command, timeout: Duration) -> bool {
+        let exchange = async {
+            let (mut stream, _) = self.identity.connect(addr, leader).await?;
+            stream.write_all(format!("{}\n", ping.serialize()).as_bytes()).await?;
+            read_line(&mut stream).await
+        };
+
+        match tokio::time::timeout(timeout, exchange).await {
+            Ok(Ok(line)) => matches!(server::Command::parse(&line), Ok(server::Command::Pong(name, _)) if name == leader),
+            Ok(Err(e)) => {
+                println!("Falha no PING ao primário {}: {}", leader, e);
+                false
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+// Prazo de eleição entre uma e duas vezes o tempo limite, sorteado para que
+// as réplicas não se candidatem todas ao mesmo tempo
+fn election_timeout(timeout: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    let jitter = random % (timeout.as_millis() as u64 + 1);
+    timeout + Duration::from_millis(jitter)
+}
+
+async fn request_vote(identity: &LocalIdentity, node: &str, addr: SocketAddr, request: &server::Command, timeout: Duration) -> Option<(u64, bool)> {
+    let exchange = async {
+        let (mut stream, _) = identity.connect(addr, node).await?;
+        stream.write_all(format!("{}\n", request.serialize()).as_bytes()).await?;
+        read_line(&mut stream).await
+    };
+
+    match tokio::time::timeout(timeout, exchange).await {
+        Ok(Ok(line)) => match server::Command::parse(&line) {
+            Ok(server::Command::Vote(term, granted)) => Some((term, granted)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
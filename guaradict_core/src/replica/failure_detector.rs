@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use tokio::time::{Duration, Instant};
+
+/// Como o monitor decide que uma réplica caiu. `Fixed` derruba a conexão
+/// depois de `failures` heartbeats seguidos sem resposta; `Phi` usa um
+/// `PhiAccrualDetector` e derruba quando o phi passa do limite, contando as
+/// falhas como no `Fixed` enquanto o detector não tem histórico.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Detector {
+    #[default]
+    Fixed,
+    Phi,
+}
+
+/// Parâmetros do monitor de réplicas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonitorSettings {
+    pub interval: Duration,
+    pub ping_timeout: Duration,
+    pub connect_timeout: Duration,
+    pub failures: u32,
+    pub detector: Detector,
+    pub phi_threshold: f64,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for MonitorSettings {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            ping_timeout: Duration::from_secs(1),
+            connect_timeout: Duration::from_secs(3),
+            failures: 3,
+            detector: Detector::Fixed,
+            phi_threshold: 8.0,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+// Intervalos guardados por réplica
+const PHI_WINDOW: usize = 100;
+// Intervalos necessários para que o phi tenha base
+const PHI_MIN_SAMPLES: usize = 3;
+
+/// Detector de falhas phi-accrual. Guarda os intervalos entre os heartbeats
+/// respondidos e, a partir da média e do desvio deles, calcula o phi: quanto
+/// mais improvável o silêncio atual, maior o valor. Um phi de 8 equivale a
+/// uma chance de 10⁻⁸ de o nó ainda estar vivo. Assim o limite se adapta à
+/// variação observada em cada rede em vez de contar falhas fixas.
+#[derive(Debug, Clone)]
+pub struct PhiAccrualDetector {
+    // Intervalos em milissegundos
+    intervals: VecDeque<f64>,
+    last: Option<Instant>,
+    // Desvio mínimo, para que uma rede muito regular não dispare o phi
+    // com qualquer atraso
+    min_std_dev: f64,
+}
+
+impl PhiAccrualDetector {
+    pub fn new(min_std_dev: Duration) -> Self {
+        Self {
+            intervals: VecDeque::with_capacity(PHI_WINDOW),
+            last: None,
+            min_std_dev: min_std_dev.as_secs_f64() * 1000.0,
+        }
+    }
+
+    pub fn heartbeat(&mut self, now: Instant) {
+        if let Some(last) = self.last {
+            if self.intervals.len() == PHI_WINDOW {
+                self.intervals.pop_front();
+            }
+            self.intervals.push_back(now.duration_since(last).as_secs_f64() * 1000.0);
+        }
+        self.last = Some(now);
+    }
+
+    /// Se já há intervalos suficientes para o phi. Antes disso quem usa o
+    /// detector precisa de outro critério, como a contagem fixa de falhas.
+    pub fn has_history(&self) -> bool {
+        self.intervals.len() >= PHI_MIN_SAMPLES
+    }
+
+    /// Phi do silêncio desde o último heartbeat. Zero enquanto não houver
+    /// intervalos observados.
+    pub fn phi(&self, now: Instant) -> f64 {
+        let Some(last) = self.last else {
+            return 0.0;
+        };
+        if self.intervals.is_empty() {
+            return 0.0;
+        }
+
+        let count = self.intervals.len() as f64;
+        let mean = self.intervals.iter().sum::<f64>() / count;
+        let variance = self.intervals.iter().map(|interval| (interval - mean).powi(2)).sum::<f64>() / count;
+        let std_dev = variance.sqrt().max(self.min_std_dev);
+
+        // Aproximação logística da distribuição normal acumulada
+        let elapsed = now.duration_since(last).as_secs_f64() * 1000.0;
+        let y = (elapsed - mean) / std_dev;
+        let e = (-y * (1.5976 + 0.070566 * y * y)).exp();
+        let phi = if elapsed > mean {
+            -(e / (1.0 + e)).log10()
+        } else {
+            -(1.0 - 1.0 / (1.0 + e)).log10()
+        };
+
+        phi.max(0.0)
+    }
+
+    pub fn is_available(&self, now: Instant, threshold: f64) -> bool {
+        self.phi(now) < threshold
+    }
+}
@@ -0,0 +1,136 @@
+use std::net::SocketAddr;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::{Role, SharedElection};
+use crate::commands::server;
+use crate::errors::HandshakeError;
+
+/// Versão do protocolo entre nós. Nós com versões diferentes recusam a
+/// conexão um do outro.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+// Limite da linha de resposta ao HELLO
+const MAX_LINE: usize = 4096;
+
+/// Identidade que um nó apresenta ao abrir uma conexão com outro (`HELLO`)
+/// e ao aceitá-la (`WELCOME`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeIdentity {
+    pub name: String,
+    pub role: Role,
+    pub database: Option<String>,
+    pub protocol: u32,
+    pub cluster_id: Option<String>,
+}
+
+impl NodeIdentity {
+    /// Verifica se `peer` pode conversar com este nó: mesmo protocolo, mesma
+    /// base de dados e mesmo cluster.
+    pub fn check(&self, peer: &NodeIdentity) -> Result<(), HandshakeError> {
+        if peer.protocol != self.protocol {
+            return Err(HandshakeError::ProtocolMismatch { local: self.protocol, remote: peer.protocol });
+        }
+        if peer.database != self.database {
+            return Err(HandshakeError::DatabaseMismatch { local: self.database.clone(), remote: peer.database.clone() });
+        }
+        if peer.cluster_id != self.cluster_id {
+            return Err(HandshakeError::ClusterMismatch { local: self.cluster_id.clone(), remote: peer.cluster_id.clone() });
+        }
+        Ok(())
+    }
+}
+
+/// Identidade deste nó. O nome e o papel vêm da eleição, já que uma réplica
+/// pode ser promovida a primário.
+#[derive(Debug, Clone)]
+pub struct LocalIdentity {
+    database: Option<String>,
+    cluster_id: Option<String>,
+    election: SharedElection,
+}
+
+impl LocalIdentity {
+    pub fn new(database: Option<String>, cluster_id: Option<String>, election: SharedElection) -> Self {
+        Self {
+            database,
+            cluster_id,
+            election,
+        }
+    }
+
+    pub fn identity(&self) -> NodeIdentity {
+        let election = self.election.lock().unwrap();
+        NodeIdentity {
+            name: election.name().to_string(),
+            role: election.role(),
+            database: self.database.clone(),
+            protocol: PROTOCOL_VERSION,
+            cluster_id: self.cluster_id.clone(),
+        }
+    }
+
+    /// Resposta ao `HELLO` de outro nó: `WELCOME` com a identidade deste ou
+    /// `DENIED` com o motivo da recusa.
+    pub fn accept(&self, peer: &NodeIdentity) -> Result<server::Command, server::Command> {
+        let identity = self.identity();
+        match identity.check(peer) {
+            Ok(()) => Ok(server::Command::Welcome(identity)),
+            Err(e) => Err(server::Command::Denied(e.to_string())),
+        }
+    }
+
+    /// Abre uma conexão com o nó `expected` em `addr` e troca as
+    /// identidades. A conexão volta pronta para os comandos entre nós, junto
+    /// com a identidade do outro lado.
+    pub async fn connect(&self, addr: SocketAddr, expected: &str) -> std::io::Result<(TcpStream, NodeIdentity)> {
+        let mut stream = TcpStream::connect(addr).await?;
+        let peer = self.handshake(&mut stream, expected).await?;
+        Ok((stream, peer))
+    }
+
+    /// Apresenta este nó pela conexão e confere a resposta.
+    pub async fn handshake(&self, stream: &mut TcpStream, expected: &str) -> std::io::Result<NodeIdentity> {
+        let identity = self.identity();
+        stream.write_all(format!("{}\n", server::Command::Hello(identity.clone()).serialize()).as_bytes()).await?;
+
+        let line = read_line(stream).await?;
+        let peer = match server::Command::parse(&line) {
+            Ok(server::Command::Welcome(peer)) => peer,
+            Ok(server::Command::Denied(reason)) => return Err(std::io::Error::other(HandshakeError::Denied(reason))),
+            _ => return Err(std::io::Error::other(HandshakeError::InvalidResponse(line))),
+        };
+
+        if peer.name != expected {
+            return Err(std::io::Error::other(HandshakeError::UnexpectedNode {
+                expected: expected.to_string(),
+                actual: peer.name,
+            }));
+        }
+        identity.check(&peer).map_err(std::io::Error::other)?;
+
+        Ok(peer)
+    }
+}
+
+/// Lê uma linha byte a byte, sem consumir o que vier depois dela na
+/// conexão.
+pub async fn read_line<S: AsyncRead + Unpin>(stream: &mut S) -> std::io::Result<String> {
+    read_line_up_to(stream, MAX_LINE).await
+}
+
+/// Como `read_line`, com outro limite. Depois do HELLO as linhas do
+/// barramento podem trazer valores inteiros, como no RESTORE.
+pub async fn read_line_up_to<S: AsyncRead + Unpin>(stream: &mut S, max: usize) -> std::io::Result<String> {
+    let mut line = Vec::new();
+    loop {
+        match stream.read_u8().await? {
+            b'\n' => break,
+            byte if line.len() < max => line.push(byte),
+            _ => return Err(std::io::Error::other("Linha longa demais")),
+        }
+    }
+    Ok(String::from_utf8_lossy(&line).trim().to_string())
+}
@@ -1,21 +1,38 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::time::Instant;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use super::{ReplicationLag, WriteStamp};
+use crate::crdt::Crdt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum OperationKind {
     Insert,
     Update,
     Delete,
+    MapSet,
+    MapDelete,
+    ListPushLeft,
+    ListPushRight,
+    ListPopLeft,
+    ListPopRight,
+    SetAdd,
+    SetRemove,
+    CrdtMerge,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum OperationValue {
     NumericValue(i32),
     StringValue(String),
     BooleanValue(bool),
-    MapValue(HashMap<OperationKey, OperationValue>),
+    MapValue(#[serde(serialize_with = "serialize_pairs", deserialize_with = "deserialize_pairs")] HashMap<OperationKey, OperationValue>),
     VecValue(Vec<OperationValue>),
+    CrdtValue(Crdt),
+    // Membros de um conjunto inteiro, em ordem, para não voltar como lista
+    SetValue(Vec<String>),
 }
 
 impl OperationValue {
@@ -86,7 +103,7 @@ impl From<bool> for OperationValue {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum OperationKey {
     NumericKey(i32),
     StringKey(String),
@@ -112,6 +129,15 @@ impl OperationKey {
     }
 }
 
+impl fmt::Display for OperationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperationKey::NumericKey(num) => write!(f, "{}", num),
+            OperationKey::StringKey(str) => write!(f, "{}", str),
+        }
+    }
+}
+
 impl From<i32> for OperationKey {
     fn from(value: i32) -> Self {
         OperationKey::NumericKey(value)
@@ -130,27 +156,97 @@ impl<'a> From<&'a str> for OperationKey {
     }
 }
 
-#[derive(Debug, Clone)]
+// Em JSON as chaves de um objeto precisam ser strings, então os mapas vão
+// como uma lista de pares
+fn serialize_pairs<S: Serializer>(map: &HashMap<OperationKey, OperationValue>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(map.iter())
+}
+
+fn deserialize_pairs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<OperationKey, OperationValue>, D::Error> {
+    let pairs = Vec::<(OperationKey, OperationValue)>::deserialize(deserializer)?;
+    Ok(pairs.into_iter().collect())
+}
+
+/// Uma alteração no dicionário. Serializável para ser enviada às réplicas;
+/// o instante da operação é local e não vai junto.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Operation {
+    #[serde(skip, default = "Instant::now")]
     pub time: Instant,
     pub kind: OperationKind,
     pub key: OperationKey,
+    pub field: Option<OperationKey>,
     pub current_value: Option<OperationValue>,
     pub prev_value: Option<OperationValue>,
+    // Versão da chave após a operação; 0 quando não versionada
+    pub version: u64,
+    // Lote ao qual a operação pertence; operações do mesmo lote (ex.: uma
+    // transação) devem ser aplicadas juntas. 0 enquanto fora do log.
+    pub batch: u64,
+    // Carimbo da escrita em modo multi-primário
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stamp: Option<WriteStamp>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LogOperator {
     pub operations: Vec<Operation>,
+    pub batches: u64,
+}
+
+impl Default for LogOperator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LogOperator {
     pub fn new() -> Self {
         Self {
             operations: Vec::new(),
+            batches: 0,
+        }
+    }
+
+    /// Acrescenta as operações ao log como um único lote. Retorna o
+    /// identificador do lote.
+    pub fn append_batch(&mut self, operations: Vec<Operation>) -> u64 {
+        self.batches += 1;
+        let batch = self.batches;
+
+        self.operations.extend(operations.into_iter().map(|operation| Operation { batch, ..operation }));
+        batch
+    }
+
+    /// Lotes com as operações de versão maior que `offset`. As versões do log
+    /// são crescentes, então basta achar a primeira operação ainda não vista.
+    pub fn batches_after(&self, offset: u64) -> Vec<Vec<Operation>> {
+        let start = self.operations.partition_point(|operation| operation.version <= offset);
+        self.operations[start..]
+            .chunk_by(|a, b| a.batch == b.batch)
+            .map(<[Operation]>::to_vec)
+            .collect()
+    }
+
+    /// Atraso de uma réplica que já aplicou até a versão `offset`: as
+    /// operações que faltam, o tamanho delas serializadas e a idade da mais
+    /// antiga.
+    pub fn lag(&self, offset: u64) -> ReplicationLag {
+        let start = self.operations.partition_point(|operation| operation.version <= offset);
+        let pending = &self.operations[start..];
+
+        ReplicationLag {
+            operations: pending.len() as u64,
+            bytes: pending.iter().map(|operation| serde_json::to_vec(operation).map_or(0, |bytes| bytes.len() as u64)).sum(),
+            seconds: pending.first().map_or(0.0, |operation| operation.time.elapsed().as_secs_f64()),
         }
     }
 
+    /// Lotes do log, na ordem em que foram acrescentados.
+    pub fn iter_batches(&self) -> impl Iterator<Item = &[Operation]> {
+        self.operations.chunk_by(|a, b| a.batch == b.batch)
+    }
+
     pub fn insert<K, V>(&mut self, key: K, current_value: V)
     where
         K: Into<OperationKey>,
@@ -163,8 +259,12 @@ impl LogOperator {
             time: Instant::now(),
             kind: OperationKind::Insert,
             key,
+            field: None,
             current_value,
             prev_value: None,
+            version: 0,
+            batch: 0,
+            stamp: None,
         });
     }
 
@@ -181,8 +281,12 @@ impl LogOperator {
             time: Instant::now(),
             kind: OperationKind::Update,
             key,
+            field: None,
             current_value,
             prev_value,
+            version: 0,
+            batch: 0,
+            stamp: None,
         });
     }
 
@@ -196,8 +300,140 @@ impl LogOperator {
             time: Instant::now(),
             kind: OperationKind::Delete,
             key,
+            field: None,
+            current_value: None,
+            prev_value: None,
+            version: 0,
+            batch: 0,
+            stamp: None,
+        });
+    }
+
+    pub fn map_set<K, F, V>(&mut self, key: K, field: F, current_value: V, prev_value: Option<V>)
+    where
+        K: Into<OperationKey>,
+        F: Into<OperationKey>,
+        V: Into<OperationValue>,
+    {
+        self.operations.push(Operation {
+            time: Instant::now(),
+            kind: OperationKind::MapSet,
+            key: key.into(),
+            field: Some(field.into()),
+            current_value: Some(current_value.into()),
+            prev_value: prev_value.map(Into::into),
+            version: 0,
+            batch: 0,
+            stamp: None,
+        });
+    }
+
+    pub fn map_delete<K, F, V>(&mut self, key: K, field: F, prev_value: Option<V>)
+    where
+        K: Into<OperationKey>,
+        F: Into<OperationKey>,
+        V: Into<OperationValue>,
+    {
+        self.operations.push(Operation {
+            time: Instant::now(),
+            kind: OperationKind::MapDelete,
+            key: key.into(),
+            field: Some(field.into()),
             current_value: None,
-            prev_value: None
+            prev_value: prev_value.map(Into::into),
+            version: 0,
+            batch: 0,
+            stamp: None,
+        });
+    }
+
+    pub fn list_push<K, V>(&mut self, key: K, kind: OperationKind, values: Vec<V>)
+    where
+        K: Into<OperationKey>,
+        V: Into<OperationValue>,
+    {
+        self.operations.push(Operation {
+            time: Instant::now(),
+            kind,
+            key: key.into(),
+            field: None,
+            current_value: Some(values.into()),
+            prev_value: None,
+            version: 0,
+            batch: 0,
+            stamp: None,
+        });
+    }
+
+    pub fn list_pop<K, V>(&mut self, key: K, kind: OperationKind, prev_value: V)
+    where
+        K: Into<OperationKey>,
+        V: Into<OperationValue>,
+    {
+        self.operations.push(Operation {
+            time: Instant::now(),
+            kind,
+            key: key.into(),
+            field: None,
+            current_value: None,
+            prev_value: Some(prev_value.into()),
+            version: 0,
+            batch: 0,
+            stamp: None,
+        });
+    }
+
+    pub fn set_add<K, V>(&mut self, key: K, members: Vec<V>)
+    where
+        K: Into<OperationKey>,
+        V: Into<OperationValue>,
+    {
+        self.operations.push(Operation {
+            time: Instant::now(),
+            kind: OperationKind::SetAdd,
+            key: key.into(),
+            field: None,
+            current_value: Some(members.into()),
+            prev_value: None,
+            version: 0,
+            batch: 0,
+            stamp: None,
+        });
+    }
+
+    pub fn set_remove<K, V>(&mut self, key: K, members: Vec<V>)
+    where
+        K: Into<OperationKey>,
+        V: Into<OperationValue>,
+    {
+        self.operations.push(Operation {
+            time: Instant::now(),
+            kind: OperationKind::SetRemove,
+            key: key.into(),
+            field: None,
+            current_value: None,
+            prev_value: Some(members.into()),
+            version: 0,
+            batch: 0,
+            stamp: None,
+        });
+    }
+
+    /// Delta de um valor CRDT, fundido ao estado da chave em cada nó.
+    pub fn crdt_merge<K>(&mut self, key: K, delta: Crdt)
+    where
+        K: Into<OperationKey>,
+    {
+        self.operations.push(Operation {
+            time: Instant::now(),
+            kind: OperationKind::CrdtMerge,
+            key: key.into(),
+            field: None,
+            current_value: Some(OperationValue::CrdtValue(delta)),
+            prev_value: None,
+            version: 0,
+            batch: 0,
+            stamp: None,
         });
     }
 }
@@ -4,8 +4,35 @@ pub use replica_status::*;
 mod log_operator;
 pub use log_operator::*;
 
+mod handshake;
+pub use handshake::*;
+
+mod backoff;
+pub use backoff::*;
+
+mod failure_detector;
+pub use failure_detector::*;
+
 mod monitor;
 pub use monitor::*;
 
 mod synchronizer;
 pub use synchronizer::*;
+
+mod replication;
+pub use replication::*;
+
+mod election;
+pub use election::*;
+
+mod failover;
+pub use failover::*;
+
+mod raft;
+pub use raft::*;
+
+mod raft_server;
+pub use raft_server::*;
+
+mod multi_primary;
+pub use multi_primary::*;
@@ -2,110 +2,203 @@ use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpStream;
-use tokio::sync::Mutex;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{broadcast, watch, Mutex};
+use tokio::io::AsyncWriteExt;
+use tokio::task::JoinSet;
 use tokio::time::{Duration, Instant};
 
-use super::ReplicaStatus;
+use super::{read_line, Backoff, Detector, LocalIdentity, LogOperator, MonitorSettings, NodeIdentity, PhiAccrualDetector, ReplicaEvent, ReplicaState, ReplicaStatus};
+use crate::commands::server;
 
+/// Acompanha as réplicas do primário. Cada réplica recebe uma conexão
+/// identificada com HELLO, pela qual o monitor manda um PING com o nome e a
+/// versão do log deste nó e espera o PONG da própria réplica com a versão
+/// que ela já aplicou, de onde sai o atraso dela em relação ao log. Os
+/// prazos e o critério de queda vêm de `MonitorSettings`.
+///
+/// Cada réplica tem a sua própria tarefa, que é dona da conexão e reconecta
+/// com `Backoff` quando ela cai; o mapa compartilhado só é travado para
+/// gravar o resultado, nunca durante a rede, para que uma réplica
+/// inacessível não atrase as demais. As mudanças de estado são publicadas
+/// como `ReplicaEvent`.
 pub struct ReplicaMonitorServer {
     replicas: Arc<Mutex<HashMap<String, ReplicaStatus>>>,
+    identity: LocalIdentity,
+    operations_log: Arc<Mutex<LogOperator>>,
+    offset: watch::Receiver<u64>,
+    settings: MonitorSettings,
+    events: broadcast::Sender<ReplicaEvent>,
 }
 
 impl ReplicaMonitorServer {
-    pub fn new(replicas: HashMap<String, ReplicaStatus>) -> Self {
+    pub fn new(replicas: HashMap<String, ReplicaStatus>, identity: LocalIdentity, operations_log: Arc<Mutex<LogOperator>>, offset: watch::Receiver<u64>, settings: MonitorSettings) -> Self {
+        let (events, _) = broadcast::channel(64);
         Self {
             replicas: Arc::new(Mutex::new(replicas)),
+            identity,
+            operations_log,
+            offset,
+            settings,
+            events,
         }
     }
 
-    async fn connect_with_timeout(&self, addr: &SocketAddr, timeout_duration: Duration) -> Result<TcpStream, std::io::Error> {
-        tokio::time::timeout(timeout_duration, TcpStream::connect(addr)).await.unwrap()
+    /// Estado das réplicas, atualizado a cada verificação.
+    pub fn replicas(&self) -> Arc<Mutex<HashMap<String, ReplicaStatus>>> {
+        Arc::clone(&self.replicas)
     }
 
-    async fn heartbeat(&self, stream: &mut TcpStream, timeout_duration: Duration) -> Result<Duration, std::io::Error> {
+    /// Mudanças de estado das réplicas a partir deste momento.
+    pub fn subscribe(&self) -> broadcast::Receiver<ReplicaEvent> {
+        self.events.subscribe()
+    }
+
+    async fn connect_with_timeout(&self, addr: &SocketAddr, name: &str) -> Result<(TcpStream, NodeIdentity), std::io::Error> {
+        tokio::time::timeout(self.settings.connect_timeout, self.identity.connect(*addr, name)).await?
+    }
+
+    // Retorna o tempo de resposta e a versão informada pela réplica
+    async fn heartbeat(&self, stream: &mut TcpStream, name: &str) -> Result<(Duration, u64), std::io::Error> {
         let start_time = Instant::now();
-        stream.write_all(b"PING\n").await?;
-        let mut buf = [0; 5];
-        tokio::time::timeout(timeout_duration, stream.read_exact(&mut buf)).await??;
-        if &buf == b"PONG\n" {
-            Ok(start_time.elapsed())
-        } else {
-            println!("RESPOSTA NAO FOI PONG: ({})", String::from_utf8_lossy(&buf[..5]));
-            Err(std::io::Error::new(std::io::ErrorKind::Other, "Resposta inválida"))
+        let ping = server::Command::Ping(self.identity.identity().name, *self.offset.borrow());
+        stream.write_all(format!("{}\n", ping.serialize()).as_bytes()).await?;
+
+        let line = tokio::time::timeout(self.settings.ping_timeout, read_line(stream)).await??;
+        match server::Command::parse(&line) {
+            Ok(server::Command::Pong(replica, offset)) if replica == name => Ok((start_time.elapsed(), offset)),
+            Ok(server::Command::Pong(replica, _)) => Err(std::io::Error::other(format!("PONG de {}, esperado de {}", replica, name))),
+            _ => Err(std::io::Error::other(format!("Resposta inválida ao PING: {}", line))),
+        }
+    }
+
+    // Atualiza o estado da réplica e publica a mudança, se houver
+    async fn update(&self, name: &str, state: ReplicaState, f: impl FnOnce(&mut ReplicaStatus)) {
+        let from = {
+            let mut replicas = self.replicas.lock().await;
+            let Some(replica) = replicas.get_mut(name) else {
+                return;
+            };
+            f(replica);
+            let from = replica.state;
+            replica.state = state;
+            replica.ready = state == ReplicaState::Ready;
+            from
+        };
+
+        if from != state {
+            // Sem inscritos o evento é descartado
+            let _ = self.events.send(ReplicaEvent { name: name.to_string(), from, to: state });
+        }
+    }
+
+    // Se a réplica que acabou de falhar um PING deve ser considerada caída
+    fn is_down(&self, detector: &PhiAccrualDetector, failures: u32) -> bool {
+        match self.settings.detector {
+            Detector::Phi if detector.has_history() => detector.phi(Instant::now()) >= self.settings.phi_threshold,
+            _ => failures >= self.settings.failures,
         }
     }
 
-    async fn check_heartbeat(&self) {
-        let replicas = Arc::clone(&self.replicas);
-        let mut replicas = replicas.lock().await;
+    // PING pela conexão, ou por uma nova se a anterior foi descartada
+    async fn ping(&self, stream: Option<TcpStream>, name: &str, addr: &SocketAddr) -> Result<(TcpStream, Duration, u64), std::io::Error> {
+        let mut stream = match stream {
+            Some(stream) => stream,
+            None => self.connect_with_timeout(addr, name).await?.0,
+        };
+        let (ping_time, offset) = self.heartbeat(&mut stream, name).await?;
+        Ok((stream, ping_time, offset))
+    }
+
+    // Manda PINGs pela conexão até a réplica ser considerada caída. Depois
+    // de uma falha a conexão é descartada, para que um PONG atrasado não
+    // seja lido como a resposta do PING seguinte, e o próximo PING vai por
+    // uma conexão nova
+    async fn check_heartbeat(&self, name: &str, addr: &SocketAddr, stream: TcpStream) {
+        let mut interval = tokio::time::interval(self.settings.interval);
+        // O desvio mínimo é o próprio prazo do PONG: um atraso dentro dele
+        // não deve parecer uma falha
+        let mut detector = PhiAccrualDetector::new(self.settings.ping_timeout);
+        detector.heartbeat(Instant::now());
+        let mut failures = 0;
+        let mut stream = Some(stream);
 
-        for (_, replica) in replicas.iter_mut() {
-            if let Some(stream) = &mut replica.stream {
-                let mut locked_stream = stream.lock().await;
-                match self.heartbeat(&mut *locked_stream, Duration::from_secs(1)).await {
-                    Ok(ping_time) => {
+        loop {
+            interval.tick().await;
+            match self.ping(stream.take(), name, addr).await {
+                Ok((current, ping_time, offset)) => {
+                    stream = Some(current);
+                    detector.heartbeat(Instant::now());
+                    failures = 0;
+                    let lag = self.operations_log.lock().await.lag(offset);
+                    self.update(name, ReplicaState::Ready, |replica| {
                         replica.ping = ping_time;
-                        replica.ready = true;
+                        replica.offset = offset;
+                        replica.lag = lag;
                         replica.failures = 0;
-                        println!("Sucesso no PING");
+                    })
+                    .await;
+                }
+                Err(e) => {
+                    println!("Erro no PING a {}: {}", name, e);
+                    failures += 1;
+                    if self.is_down(&detector, failures) {
+                        return;
                     }
-                    Err(e) => {
+                    self.update(name, ReplicaState::Suspect, |replica| {
                         replica.ping = Duration::default();
-                        replica.ready = false;
-                        replica.failures += 1;
-                        if replica.failures >= 3 {
-                            drop(locked_stream);
-                            replica.stream = None;
-                        }
-                        println!("Erro no PING: {}", e);
-                    }
+                        replica.failures = failures;
+                    })
+                    .await;
                 }
             }
         }
-
-        drop(replicas);
     }
 
-    async fn check_reconnect(&self) {
-        let replicas = Arc::clone(&self.replicas);
-        let mut replicas = replicas.lock().await;
-
-        for (_, replica) in replicas.iter_mut() {
-            if let Some(addr) = &replica.addr {
-                if replica.stream.is_none() {
-                    match self.connect_with_timeout(addr, Duration::from_secs(3)).await {
-                        Ok(stream) => {
-                            replica.stream = Some(Arc::new(Mutex::new(stream)));
-                            replica.ping = Duration::default();
-                            replica.ready = true;
-                            replica.failures = 0;
-                            println!("Sucesso na reconexão");
-                        }
-                        Err(e) => {
-                            replica.ping = Duration::default();
-                            replica.ready = false;
-                            replica.failures += 1;
-                            if replica.failures >= 3 {
-                                replica.stream = None;
-                            }
-                            println!("Erro ao reconectar: {}", e);
-                        }
-                    }
+    // Mantém a conexão com uma réplica, reconectando enquanto o monitor rodar
+    async fn watch(&self, name: String, addr: SocketAddr) {
+        let mut backoff = Backoff::new(self.settings.backoff, self.settings.max_backoff);
+
+        loop {
+            self.update(&name, ReplicaState::Connecting, |_| {}).await;
+            match self.connect_with_timeout(&addr, &name).await {
+                Ok((stream, peer)) => {
+                    backoff.reset();
+                    self.update(&name, ReplicaState::Connecting, |replica| replica.role = Some(peer.role)).await;
+                    self.check_heartbeat(&name, &addr, stream).await;
+                    self.update(&name, ReplicaState::Down, |replica| {
+                        replica.ping = Duration::default();
+                        replica.failures += 1;
+                    })
+                    .await;
+                }
+                Err(e) => {
+                    println!("Erro ao conectar a {}: {}", name, e);
+                    self.update(&name, ReplicaState::Down, |replica| {
+                        replica.ping = Duration::default();
+                        replica.failures += 1;
+                    })
+                    .await;
                 }
             }
+            tokio::time::sleep(backoff.next_delay()).await;
         }
-
-        drop(replicas);
     }
 
-    pub async fn start(&self) {
-        let mut interval = tokio::time::interval(Duration::from_secs(5));
+    pub async fn start(self) {
+        let monitor = Arc::new(self);
+        let replicas = monitor
+            .replicas
+            .lock()
+            .await
+            .iter()
+            .filter_map(|(name, replica)| replica.bus.map(|bus| (name.clone(), bus)))
+            .collect::<Vec<(String, SocketAddr)>>();
 
-        loop {
-            interval.tick().await;
-            self.check_heartbeat().await;
-            self.check_reconnect().await;
+        let mut tasks = JoinSet::new();
+        for (name, addr) in replicas {
+            let monitor = Arc::clone(&monitor);
+            tasks.spawn(async move { monitor.watch(name, addr).await });
         }
+        while tasks.join_next().await.is_some() {}
     }
 }
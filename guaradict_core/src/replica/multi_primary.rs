@@ -0,0 +1,346 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use super::{Operation, OperationKind, OperationValue};
+use crate::value::Value;
+use crate::Dictionary;
+
+// Conflitos guardados para consulta pelo comando CONFLICTS
+const MAX_CONFLICTS: usize = 1000;
+
+/// Instante de um relógio lógico híbrido: o tempo físico em milissegundos,
+/// um contador para eventos no mesmo milissegundo e o nó de origem, que
+/// desempata escritas simultâneas. A ordem é total e igual em todos os nós.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HlcTimestamp {
+    pub wall: u64,
+    pub logical: u32,
+    pub node: String,
+}
+
+/// Relógio lógico híbrido. Segue o relógio do sistema, mas nunca volta
+/// atrás e fica sempre à frente dos instantes recebidos de outros nós, de
+/// modo que uma escrita feita depois de ver outra tem instante maior mesmo
+/// com relógios dessincronizados.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HybridClock {
+    node: String,
+    wall: u64,
+    logical: u32,
+}
+
+impl HybridClock {
+    pub fn new(node: &str) -> Self {
+        Self {
+            node: node.to_string(),
+            wall: 0,
+            logical: 0,
+        }
+    }
+
+    pub fn node(&self) -> &str {
+        &self.node
+    }
+
+    pub fn now(&mut self) -> HlcTimestamp {
+        self.now_at(physical_time())
+    }
+
+    /// Instante para um evento local, dado o tempo físico em milissegundos.
+    pub fn now_at(&mut self, physical: u64) -> HlcTimestamp {
+        if physical > self.wall {
+            self.wall = physical;
+            self.logical = 0;
+        } else {
+            self.logical += 1;
+        }
+        self.timestamp()
+    }
+
+    pub fn observe(&mut self, remote: &HlcTimestamp) {
+        self.observe_at(remote, physical_time());
+    }
+
+    /// Avança o relógio ao receber o instante de outro nó.
+    pub fn observe_at(&mut self, remote: &HlcTimestamp, physical: u64) {
+        let wall = physical.max(self.wall).max(remote.wall);
+        self.logical = if wall == self.wall && wall == remote.wall {
+            self.logical.max(remote.logical) + 1
+        } else if wall == self.wall {
+            self.logical + 1
+        } else if wall == remote.wall {
+            remote.logical + 1
+        } else {
+            0
+        };
+        self.wall = wall;
+    }
+
+    fn timestamp(&self) -> HlcTimestamp {
+        HlcTimestamp {
+            wall: self.wall,
+            logical: self.logical,
+            node: self.node.clone(),
+        }
+    }
+}
+
+fn physical_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_millis() as u64)
+}
+
+/// Relação causal entre dois vetores de versão.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Causality {
+    Before,
+    After,
+    Equal,
+    Concurrent,
+}
+
+/// Vetor de versão de uma chave: quantas escritas de cada primário ela já
+/// incorporou. Escritas cujos vetores não se comparam são concorrentes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(transparent)]
+pub struct VersionVector(BTreeMap<String, u64>);
+
+impl VersionVector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, node: &str) -> u64 {
+        self.0.get(node).copied().unwrap_or(0)
+    }
+
+    pub fn increment(&mut self, node: &str) {
+        *self.0.entry(node.to_string()).or_default() += 1;
+    }
+
+    pub fn merge(&mut self, other: &VersionVector) {
+        for (node, count) in &other.0 {
+            let current = self.0.entry(node.clone()).or_default();
+            *current = (*current).max(*count);
+        }
+    }
+
+    /// Posição deste vetor em relação a `other`.
+    pub fn compare(&self, other: &VersionVector) -> Causality {
+        let nodes = self.0.keys().chain(other.0.keys());
+        let (mut less, mut greater) = (false, false);
+        for node in nodes {
+            match self.get(node).cmp(&other.get(node)) {
+                Ordering::Less => less = true,
+                Ordering::Greater => greater = true,
+                Ordering::Equal => {}
+            }
+        }
+
+        match (less, greater) {
+            (false, false) => Causality::Equal,
+            (true, false) => Causality::Before,
+            (false, true) => Causality::After,
+            (true, true) => Causality::Concurrent,
+        }
+    }
+}
+
+/// Como resolver escritas concorrentes na mesma chave em primários
+/// diferentes. Em ambos os casos vence o maior instante do relógio híbrido;
+/// com `VectorClock` as escritas concorrentes são detectadas pelos vetores
+/// de versão e registradas como conflito.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictResolution {
+    #[default]
+    #[serde(rename = "lww")]
+    LastWriterWins,
+    #[serde(rename = "vector")]
+    VectorClock,
+}
+
+/// Carimbo de uma escrita em modo multi-primário: quando e onde ela foi
+/// feita e o valor da chave depois dela (`None` quando removida). O valor
+/// inteiro vai junto para que todos os nós cheguem ao mesmo estado
+/// qualquer que seja a ordem de chegada.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WriteStamp {
+    pub timestamp: HlcTimestamp,
+    pub vector: VersionVector,
+    pub state: Option<Value>,
+}
+
+/// Escritas concorrentes detectadas numa chave.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub key: String,
+    pub local: HlcTimestamp,
+    pub remote: HlcTimestamp,
+    pub winner: String,
+}
+
+#[derive(Debug, Clone)]
+struct KeyStamp {
+    timestamp: HlcTimestamp,
+    vector: VersionVector,
+}
+
+/// Resolve as escritas recebidas de outros primários. Guarda, por chave, o
+/// carimbo da escrita vigente, inclusive de chaves removidas, para decidir
+/// se uma escrita recebida é nova, repetida ou concorrente.
+#[derive(Debug)]
+pub struct ConflictResolver {
+    node: String,
+    resolution: ConflictResolution,
+    clock: HybridClock,
+    keys: HashMap<String, KeyStamp>,
+    conflicts: VecDeque<Conflict>,
+}
+
+impl ConflictResolver {
+    pub fn new(node: &str, resolution: ConflictResolution) -> Self {
+        Self::with_clock(HybridClock::new(node), resolution)
+    }
+
+    pub fn with_clock(clock: HybridClock, resolution: ConflictResolution) -> Self {
+        Self {
+            node: clock.node.clone(),
+            resolution,
+            clock,
+            keys: HashMap::new(),
+            conflicts: VecDeque::new(),
+        }
+    }
+
+    pub fn resolution(&self) -> ConflictResolution {
+        self.resolution
+    }
+
+    /// Conflitos detectados, do mais antigo ao mais recente.
+    pub fn conflicts(&self) -> impl Iterator<Item = &Conflict> {
+        self.conflicts.iter()
+    }
+
+    /// Carimba as operações de uma escrita local com o estado atual de cada
+    /// chave no dicionário. Deltas CRDT não precisam de carimbo: convergem
+    /// pela fusão.
+    pub fn stamp(&mut self, operations: &mut [Operation], dictionary: &Dictionary) {
+        let unstamped = operations
+            .iter_mut()
+            .filter(|operation| operation.stamp.is_none() && operation.kind != OperationKind::CrdtMerge);
+        for operation in unstamped {
+            let key = operation.key.to_string();
+            let timestamp = self.clock.now();
+            let vector = {
+                let stamp = self.keys.entry(key.clone()).or_insert_with(|| KeyStamp {
+                    timestamp: timestamp.clone(),
+                    vector: VersionVector::new(),
+                });
+                stamp.timestamp = timestamp.clone();
+                stamp.vector.increment(&self.node);
+                stamp.vector.clone()
+            };
+
+            operation.stamp = Some(WriteStamp {
+                timestamp,
+                vector,
+                state: dictionary.get(&key).cloned(),
+            });
+        }
+    }
+
+    /// Aplica ao dicionário as escritas de outro primário que vencem as
+    /// locais. Retorna as operações geradas, já carimbadas com a escrita
+    /// vencedora, para seguirem no log deste nó; escritas repetidas ou
+    /// perdedoras não geram operações, o que encerra o ciclo entre primários.
+    pub fn merge(&mut self, operations: &[Operation], dictionary: &mut Dictionary) -> Vec<Operation> {
+        let mut merged = Vec::new();
+
+        for operation in operations {
+            if let Some(OperationValue::CrdtValue(delta)) = &operation.current_value {
+                // Só deltas que trazem algo novo seguem adiante
+                if dictionary.crdt_merge(&operation.key.to_string(), delta) {
+                    merged.extend(dictionary.take_operations());
+                }
+                continue;
+            }
+
+            let Some(remote) = &operation.stamp else {
+                println!("Operação sem carimbo de multi-primário ignorada: {}", operation.key);
+                continue;
+            };
+
+            let key = operation.key.to_string();
+            let Some(stamp) = self.resolve(&key, remote) else {
+                continue;
+            };
+
+            dictionary.restore(&key, stamp.state.clone());
+            merged.extend(dictionary.take_operations().into_iter().map(|operation| Operation {
+                stamp: Some(stamp.clone()),
+                ..operation
+            }));
+        }
+
+        merged
+    }
+
+    /// Decide uma escrita recebida na chave, retornando o carimbo a aplicar
+    /// quando ela vence.
+    pub fn resolve(&mut self, key: &str, remote: &WriteStamp) -> Option<WriteStamp> {
+        self.clock.observe(&remote.timestamp);
+
+        let Some(local) = self.keys.get_mut(key) else {
+            self.keys.insert(key.to_string(), KeyStamp {
+                timestamp: remote.timestamp.clone(),
+                vector: remote.vector.clone(),
+            });
+            return Some(remote.clone());
+        };
+
+        let causality = match self.resolution {
+            ConflictResolution::LastWriterWins => match remote.timestamp.cmp(&local.timestamp) {
+                Ordering::Greater => Causality::After,
+                _ => Causality::Before,
+            },
+            ConflictResolution::VectorClock => remote.vector.compare(&local.vector),
+        };
+
+        match causality {
+            Causality::Before | Causality::Equal => None,
+            Causality::After => {
+                local.timestamp = remote.timestamp.clone();
+                local.vector.merge(&remote.vector);
+                Some(remote.clone())
+            }
+            Causality::Concurrent => {
+                let remote_wins = remote.timestamp > local.timestamp;
+                let winner = if remote_wins { &remote.timestamp } else { &local.timestamp };
+                let conflict = Conflict {
+                    key: key.to_string(),
+                    local: local.timestamp.clone(),
+                    remote: remote.timestamp.clone(),
+                    winner: winner.node.clone(),
+                };
+                println!("Conflito na chave {}: vence a escrita de {}", key, conflict.winner);
+
+                local.vector.merge(&remote.vector);
+                if remote_wins {
+                    local.timestamp = remote.timestamp.clone();
+                }
+                let vector = local.vector.clone();
+
+                if self.conflicts.len() == MAX_CONFLICTS {
+                    self.conflicts.pop_front();
+                }
+                self.conflicts.push_back(conflict);
+
+                remote_wins.then(|| WriteStamp {
+                    vector,
+                    ..remote.clone()
+                })
+            }
+        }
+    }
+}
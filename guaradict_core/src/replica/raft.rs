@@ -0,0 +1,496 @@
+use std::collections::{BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
+
+use super::Operation;
+use crate::errors::RaftError;
+
+// Máximo de entradas enviadas num único AppendEntries
+const MAX_ENTRIES_PER_APPEND: usize = 64;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RaftRole {
+    Follower,
+    Candidate,
+    Leader,
+}
+
+/// Conteúdo de uma entrada do log do Raft.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RaftPayload {
+    /// Um lote do `LogOperator`: as operações de um comando, transação ou
+    /// script, aplicadas juntas.
+    Operations { operations: Vec<Operation> },
+    /// Nova composição do cluster. Vale assim que entra no log.
+    Membership { members: Vec<String> },
+    /// Entrada vazia que o líder grava ao assumir, para confirmar as
+    /// entradas de termos anteriores.
+    Noop,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RaftEntry {
+    pub term: u64,
+    pub index: u64,
+    pub payload: RaftPayload,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RaftMessage {
+    #[serde(rename_all = "camelCase")]
+    RequestVote { term: u64, last_log_index: u64, last_log_term: u64 },
+    Vote { term: u64, granted: bool },
+    #[serde(rename_all = "camelCase")]
+    AppendEntries { term: u64, prev_log_index: u64, prev_log_term: u64, entries: Vec<RaftEntry>, leader_commit: u64 },
+    #[serde(rename_all = "camelCase")]
+    AppendResponse { term: u64, success: bool, match_index: u64 },
+}
+
+impl RaftMessage {
+    pub fn term(&self) -> u64 {
+        match self {
+            RaftMessage::RequestVote { term, .. }
+            | RaftMessage::Vote { term, .. }
+            | RaftMessage::AppendEntries { term, .. }
+            | RaftMessage::AppendResponse { term, .. } => *term,
+        }
+    }
+
+    pub fn to_line(&self) -> String {
+        format!("{}\n", serde_json::to_string(self).unwrap())
+    }
+
+    pub fn from_line(line: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(line.trim())
+    }
+}
+
+/// Mensagem de saída, endereçada a outro nó.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaftEnvelope {
+    pub to: String,
+    pub message: RaftMessage,
+}
+
+/// Um nó do Raft como máquina de estados: não faz E/S nem conta tempo.
+/// Quem o usa chama `tick` a intervalos regulares, entrega as mensagens
+/// recebidas com `step`, envia as de `take_messages` e aplica ao dicionário
+/// as entradas de `take_committed`. Isso permite testar um cluster inteiro
+/// num só processo, controlando a rede.
+///
+/// As alterações de membros são feitas um nó por vez, e só uma pode estar
+/// pendente (não confirmada) de cada vez.
+#[derive(Debug)]
+pub struct RaftNode {
+    id: String,
+    initial_members: BTreeSet<String>,
+    members: BTreeSet<String>,
+    role: RaftRole,
+    term: u64,
+    voted_for: Option<String>,
+    leader: Option<String>,
+    log: Vec<RaftEntry>,
+    commit_index: u64,
+    last_applied: u64,
+    votes: BTreeSet<String>,
+    next_index: HashMap<String, u64>,
+    match_index: HashMap<String, u64>,
+    // Ticks desde o último contato do líder (ou desde o último heartbeat enviado)
+    elapsed: u32,
+    election_ticks: u32,
+    election_timeout: u32,
+    heartbeat_ticks: u32,
+    rng: u64,
+    messages: Vec<RaftEnvelope>,
+}
+
+impl RaftNode {
+    /// Cria um nó seguidor. `members` inclui o próprio nó; `election_ticks`
+    /// é o mínimo de ticks sem líder antes de uma eleição (o prazo real é
+    /// sorteado entre uma e duas vezes esse valor).
+    pub fn new(id: &str, members: &[String], election_ticks: u32, heartbeat_ticks: u32) -> Self {
+        let members: BTreeSet<String> = members.iter().cloned().collect();
+        // Semente fixa por nó: os prazos variam entre os nós, mas cada
+        // execução com os mesmos nós se repete igual
+        let rng = id.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3)) | 1;
+
+        let mut node = Self {
+            id: id.to_string(),
+            initial_members: members.clone(),
+            members,
+            role: RaftRole::Follower,
+            term: 0,
+            voted_for: None,
+            leader: None,
+            log: Vec::new(),
+            commit_index: 0,
+            last_applied: 0,
+            votes: BTreeSet::new(),
+            next_index: HashMap::new(),
+            match_index: HashMap::new(),
+            elapsed: 0,
+            election_ticks: election_ticks.max(1),
+            election_timeout: 0,
+            heartbeat_ticks: heartbeat_ticks.max(1),
+            rng,
+            messages: Vec::new(),
+        };
+        node.reset_election_timeout();
+        node
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn role(&self) -> RaftRole {
+        self.role
+    }
+
+    pub fn term(&self) -> u64 {
+        self.term
+    }
+
+    pub fn leader(&self) -> Option<&str> {
+        self.leader.as_deref()
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.role == RaftRole::Leader
+    }
+
+    pub fn members(&self) -> &BTreeSet<String> {
+        &self.members
+    }
+
+    pub fn commit_index(&self) -> u64 {
+        self.commit_index
+    }
+
+    pub fn last_index(&self) -> u64 {
+        self.log.len() as u64
+    }
+
+    pub fn entries(&self) -> &[RaftEntry] {
+        &self.log
+    }
+
+    /// Operações de todas as entradas confirmadas, em ordem.
+    pub fn committed_operations(&self) -> impl Iterator<Item = &Operation> {
+        self.log[..self.commit_index as usize]
+            .iter()
+            .filter_map(|entry| match &entry.payload {
+                RaftPayload::Operations { operations } => Some(operations),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    pub fn tick(&mut self) {
+        self.elapsed += 1;
+
+        if self.role == RaftRole::Leader {
+            if self.elapsed >= self.heartbeat_ticks {
+                self.elapsed = 0;
+                self.broadcast_append();
+            }
+        } else if self.elapsed >= self.election_timeout && self.members.contains(&self.id) {
+            self.campaign();
+        }
+    }
+
+    /// Propõe um lote de operações ao cluster, retornando o índice da entrada.
+    pub fn propose(&mut self, operations: Vec<Operation>) -> Result<u64, RaftError> {
+        self.append(RaftPayload::Operations { operations })
+    }
+
+    pub fn add_member(&mut self, member: &str) -> Result<u64, RaftError> {
+        if self.members.contains(member) {
+            return Err(RaftError::AlreadyMember(member.to_string()));
+        }
+        self.change_membership(|members| {
+            members.insert(member.to_string());
+        })
+    }
+
+    pub fn remove_member(&mut self, member: &str) -> Result<u64, RaftError> {
+        if !self.members.contains(member) {
+            return Err(RaftError::UnknownMember(member.to_string()));
+        }
+        self.change_membership(|members| {
+            members.remove(member);
+        })
+    }
+
+    /// Mensagens a enviar desde a última chamada.
+    pub fn take_messages(&mut self) -> Vec<RaftEnvelope> {
+        std::mem::take(&mut self.messages)
+    }
+
+    /// Entradas confirmadas ainda não entregues para aplicação.
+    pub fn take_committed(&mut self) -> Vec<RaftEntry> {
+        let entries = self.log[self.last_applied as usize..self.commit_index as usize].to_vec();
+        self.last_applied = self.commit_index;
+        entries
+    }
+
+    pub fn step(&mut self, from: &str, message: RaftMessage) {
+        if message.term() > self.term {
+            self.become_follower(message.term());
+        }
+
+        match message {
+            RaftMessage::RequestVote { term, last_log_index, last_log_term } => {
+                let up_to_date = (last_log_term, last_log_index) >= (self.last_term(), self.last_index());
+                let granted = term == self.term
+                    && up_to_date
+                    && self.voted_for.as_deref().is_none_or(|voted_for| voted_for == from);
+
+                if granted {
+                    self.voted_for = Some(from.to_string());
+                    self.elapsed = 0;
+                }
+                self.send(from, RaftMessage::Vote { term: self.term, granted });
+            }
+            RaftMessage::Vote { term, granted } => {
+                if self.role == RaftRole::Candidate && term == self.term && granted {
+                    self.votes.insert(from.to_string());
+                    if self.has_quorum(&self.votes) {
+                        self.become_leader();
+                    }
+                }
+            }
+            RaftMessage::AppendEntries { term, prev_log_index, prev_log_term, entries, leader_commit } => {
+                if term < self.term {
+                    self.send(from, RaftMessage::AppendResponse { term: self.term, success: false, match_index: 0 });
+                    return;
+                }
+
+                self.role = RaftRole::Follower;
+                self.leader = Some(from.to_string());
+                self.elapsed = 0;
+
+                if prev_log_index > self.last_index() || self.term_at(prev_log_index) != prev_log_term {
+                    // Indica ao líder de onde recomeçar
+                    let match_index = self.last_index().min(prev_log_index.saturating_sub(1));
+                    self.send(from, RaftMessage::AppendResponse { term: self.term, success: false, match_index });
+                    return;
+                }
+
+                let last_new = prev_log_index + entries.len() as u64;
+                for entry in entries {
+                    if entry.index <= self.last_index() {
+                        if self.term_at(entry.index) == entry.term {
+                            continue;
+                        }
+                        // Conflito: descarta daqui em diante, inclusive
+                        self.log.truncate(entry.index as usize - 1);
+                    }
+                    self.log.push(entry);
+                }
+                self.refresh_members();
+
+                if leader_commit > self.commit_index {
+                    self.commit_index = leader_commit.min(last_new);
+                }
+                self.send(from, RaftMessage::AppendResponse { term: self.term, success: true, match_index: last_new });
+            }
+            RaftMessage::AppendResponse { term, success, match_index } => {
+                if self.role != RaftRole::Leader || term != self.term {
+                    return;
+                }
+
+                if success {
+                    let matched = self.match_index.entry(from.to_string()).or_default();
+                    *matched = (*matched).max(match_index);
+                    self.next_index.insert(from.to_string(), *matched + 1);
+                    self.advance_commit();
+                    // Ainda há entradas a enviar a esse nó
+                    if match_index < self.last_index() {
+                        self.send_append(from);
+                    }
+                } else {
+                    let next = self.next_index.get(from).copied().unwrap_or(1);
+                    self.next_index.insert(from.to_string(), (match_index + 1).min(next.saturating_sub(1)).max(1));
+                    self.send_append(from);
+                }
+            }
+        }
+    }
+
+    fn append(&mut self, payload: RaftPayload) -> Result<u64, RaftError> {
+        if self.role != RaftRole::Leader {
+            return Err(RaftError::NotLeader(self.leader.clone()));
+        }
+
+        let index = self.last_index() + 1;
+        self.log.push(RaftEntry { term: self.term, index, payload });
+        self.refresh_members();
+        self.broadcast_append();
+        // Num cluster de um nó a entrada já está confirmada
+        self.advance_commit();
+        Ok(index)
+    }
+
+    fn change_membership<F>(&mut self, change: F) -> Result<u64, RaftError>
+    where
+        F: FnOnce(&mut BTreeSet<String>),
+    {
+        if self.role != RaftRole::Leader {
+            return Err(RaftError::NotLeader(self.leader.clone()));
+        }
+
+        let pending = self.log[self.commit_index as usize..]
+            .iter()
+            .any(|entry| matches!(entry.payload, RaftPayload::Membership { .. }));
+        if pending {
+            return Err(RaftError::MembershipChangePending);
+        }
+
+        let mut members = self.members.clone();
+        change(&mut members);
+        let next = self.last_index() + 1;
+        for member in &members {
+            self.next_index.entry(member.clone()).or_insert(next);
+        }
+
+        self.append(RaftPayload::Membership { members: members.into_iter().collect() })
+    }
+
+    fn campaign(&mut self) {
+        self.term += 1;
+        self.role = RaftRole::Candidate;
+        self.voted_for = Some(self.id.clone());
+        self.leader = None;
+        self.votes = BTreeSet::from([self.id.clone()]);
+        self.elapsed = 0;
+        self.reset_election_timeout();
+
+        if self.has_quorum(&self.votes) {
+            self.become_leader();
+            return;
+        }
+
+        let request = RaftMessage::RequestVote {
+            term: self.term,
+            last_log_index: self.last_index(),
+            last_log_term: self.last_term(),
+        };
+        for peer in self.peers() {
+            self.send(&peer, request.clone());
+        }
+    }
+
+    fn become_follower(&mut self, term: u64) {
+        self.term = term;
+        self.role = RaftRole::Follower;
+        self.voted_for = None;
+        self.leader = None;
+        self.elapsed = 0;
+        self.reset_election_timeout();
+    }
+
+    fn become_leader(&mut self) {
+        self.role = RaftRole::Leader;
+        self.leader = Some(self.id.clone());
+        self.elapsed = 0;
+        let next = self.last_index() + 1;
+        self.next_index = self.peers().into_iter().map(|peer| (peer, next)).collect();
+        self.match_index.clear();
+
+        // Entradas de termos anteriores só são confirmadas junto com uma do termo atual
+        let _ = self.append(RaftPayload::Noop);
+    }
+
+    fn advance_commit(&mut self) {
+        for index in (self.commit_index + 1..=self.last_index()).rev() {
+            if self.term_at(index) != self.term {
+                break;
+            }
+
+            let replicated = self
+                .members
+                .iter()
+                .filter(|member| **member == self.id || self.match_index.get(*member).is_some_and(|matched| *matched >= index))
+                .cloned()
+                .collect::<BTreeSet<String>>();
+            if self.has_quorum(&replicated) {
+                self.commit_index = index;
+                break;
+            }
+        }
+
+        // Um líder removido do cluster sai depois de confirmar a remoção
+        if !self.members.contains(&self.id) && self.commit_index == self.last_index() {
+            self.role = RaftRole::Follower;
+            self.leader = None;
+        }
+    }
+
+    fn broadcast_append(&mut self) {
+        for peer in self.peers() {
+            self.send_append(&peer);
+        }
+    }
+
+    fn send_append(&mut self, peer: &str) {
+        let next = self.next_index.get(peer).copied().unwrap_or(self.last_index() + 1).max(1);
+        let prev_log_index = next - 1;
+        let entries = self.log[prev_log_index as usize..].iter().take(MAX_ENTRIES_PER_APPEND).cloned().collect();
+
+        let message = RaftMessage::AppendEntries {
+            term: self.term,
+            prev_log_index,
+            prev_log_term: self.term_at(prev_log_index),
+            entries,
+            leader_commit: self.commit_index,
+        };
+        self.send(peer, message);
+    }
+
+    fn send(&mut self, to: &str, message: RaftMessage) {
+        self.messages.push(RaftEnvelope { to: to.to_string(), message });
+    }
+
+    fn peers(&self) -> Vec<String> {
+        self.members.iter().filter(|member| **member != self.id).cloned().collect()
+    }
+
+    fn has_quorum(&self, nodes: &BTreeSet<String>) -> bool {
+        nodes.iter().filter(|node| self.members.contains(*node)).count() > self.members.len() / 2
+    }
+
+    // A composição vigente é a da última alteração no log, confirmada ou não
+    fn refresh_members(&mut self) {
+        self.members = self
+            .log
+            .iter()
+            .rev()
+            .find_map(|entry| match &entry.payload {
+                RaftPayload::Membership { members } => Some(members.iter().cloned().collect()),
+                _ => None,
+            })
+            .unwrap_or_else(|| self.initial_members.clone());
+    }
+
+    fn term_at(&self, index: u64) -> u64 {
+        match index {
+            0 => 0,
+            index => self.log.get(index as usize - 1).map_or(0, |entry| entry.term),
+        }
+    }
+
+    fn last_term(&self) -> u64 {
+        self.term_at(self.last_index())
+    }
+
+    fn reset_election_timeout(&mut self) {
+        // xorshift64
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.election_timeout = self.election_ticks + (self.rng % self.election_ticks as u64) as u32;
+    }
+}
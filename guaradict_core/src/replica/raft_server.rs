@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, watch};
+use tokio::time::Duration;
+
+use super::{LocalIdentity, Operation, RaftEntry, RaftMessage, RaftNode, RaftPayload};
+use crate::commands::server;
+use crate::errors::RaftError;
+
+// Intervalo entre tentativas de conexão com um membro
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Liga um `RaftNode` à rede. Cada membro recebe as mensagens deste nó por
+/// uma conexão própria, identificada com `HELLO`, aberta com `RAFT <nome>
+/// <endereço>` e seguida de uma mensagem JSON por linha; as respostas voltam
+/// pela conexão que o outro membro abre para este. Mensagens enviadas com o membro desconectado são
+/// descartadas: o Raft reenvia o que faltar.
+///
+/// As entradas confirmadas saem, em ordem, pelo canal retornado em `new`.
+///
+/// O termo, o voto e o log ficam só em memória: um nó reiniciado volta sem
+/// log e recebe tudo do líder. Enquanto não houver journal, reiniciar a
+/// maioria dos nós ao mesmo tempo pode levar a dois líderes no mesmo termo.
+#[derive(Clone)]
+pub struct RaftServer {
+    name: String,
+    addr: String,
+    identity: LocalIdentity,
+    node: Arc<Mutex<RaftNode>>,
+    peers: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<RaftMessage>>>>,
+    committed: mpsc::UnboundedSender<RaftEntry>,
+    commit_index: Arc<watch::Sender<u64>>,
+}
+
+impl RaftServer {
+    /// `addr` é o endereço em que este nó recebe conexões, informado aos
+    /// demais membros, e `identity` é apresentada a eles ao conectar.
+    pub fn new(node: RaftNode, addr: String, identity: LocalIdentity) -> (Self, mpsc::UnboundedReceiver<RaftEntry>) {
+        let (committed, entries) = mpsc::unbounded_channel();
+        let server = Self {
+            name: node.id().to_string(),
+            addr,
+            identity,
+            node: Arc::new(Mutex::new(node)),
+            peers: Arc::new(Mutex::new(HashMap::new())),
+            committed,
+            commit_index: Arc::new(watch::Sender::new(0)),
+        };
+
+        (server, entries)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.node.lock().unwrap().is_leader()
+    }
+
+    pub fn leader(&self) -> Option<String> {
+        self.node.lock().unwrap().leader().map(str::to_string)
+    }
+
+    /// Estado do nó em JSON, para o comando RAFTSTATUS.
+    pub fn status(&self) -> serde_json::Value {
+        let node = self.node.lock().unwrap();
+        serde_json::json!({
+            "name": node.id(),
+            "role": node.role(),
+            "term": node.term(),
+            "leader": node.leader(),
+            "commitIndex": node.commit_index(),
+            "lastIndex": node.last_index(),
+            "members": node.members(),
+        })
+    }
+
+    /// Termo da entrada confirmada no índice, se já foi confirmada.
+    pub fn committed_term(&self, index: u64) -> Option<u64> {
+        let node = self.node.lock().unwrap();
+        let entry = node.entries().get(index.checked_sub(1)? as usize)?;
+        (index <= node.commit_index()).then_some(entry.term)
+    }
+
+    /// Operações das entradas confirmadas até o índice `until`, para
+    /// reconstruir o dicionário.
+    pub fn committed_operations(&self, until: u64) -> Vec<Operation> {
+        let node = self.node.lock().unwrap();
+        node.entries()[..until.min(node.commit_index()) as usize]
+            .iter()
+            .filter_map(|entry| match &entry.payload {
+                RaftPayload::Operations { operations } => Some(operations),
+                _ => None,
+            })
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Registra o endereço de um membro e abre a conexão de saída para ele.
+    pub fn connect(&self, name: &str, addr: SocketAddr) {
+        let mut peers = self.peers.lock().unwrap();
+        if name == self.name || peers.get(name).is_some_and(|peer| !peer.is_closed()) {
+            return;
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        peers.insert(name.to_string(), tx);
+
+        let (identity, name) = (self.identity.clone(), name.to_string());
+        let handshake = server::Command::Raft(self.name.clone(), self.addr.clone());
+        tokio::spawn(async move {
+            send_to(&identity, &name, addr, &handshake, rx).await;
+        });
+    }
+
+    pub fn start(&self, tick: Duration) {
+        let server = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tick);
+            loop {
+                interval.tick().await;
+                server.node.lock().unwrap().tick();
+                server.flush();
+            }
+        });
+    }
+
+    /// Lê as mensagens de um membro até a conexão fechar. `addr` é o
+    /// endereço informado por ele, usado para responder.
+    pub async fn receive(&self, from: &str, addr: &str, socket: TcpStream) -> std::io::Result<()> {
+        if let Ok(addr) = addr.parse() {
+            self.connect(from, addr);
+        }
+
+        let mut lines = BufReader::new(socket).lines();
+        while let Some(line) = lines.next_line().await? {
+            let message = RaftMessage::from_line(&line).map_err(std::io::Error::other)?;
+            self.node.lock().unwrap().step(from, message);
+            self.flush();
+        }
+
+        Ok(())
+    }
+
+    /// Propõe um lote de operações, retornando o índice e o termo da entrada.
+    pub fn propose(&self, operations: Vec<Operation>) -> Result<(u64, u64), RaftError> {
+        let result = {
+            let mut node = self.node.lock().unwrap();
+            node.propose(operations).map(|index| (index, node.term()))
+        };
+        self.flush();
+        result
+    }
+
+    pub fn add_member(&self, name: &str, addr: SocketAddr) -> Result<u64, RaftError> {
+        self.connect(name, addr);
+        let result = self.node.lock().unwrap().add_member(name);
+        self.flush();
+        result
+    }
+
+    pub fn remove_member(&self, name: &str) -> Result<u64, RaftError> {
+        let result = self.node.lock().unwrap().remove_member(name);
+        self.flush();
+        result
+    }
+
+    /// Espera a entrada do índice ser confirmada no termo em que foi
+    /// proposta. Retorna falso se o tempo acabar ou se a entrada foi
+    /// substituída por outro líder.
+    pub async fn wait_commit(&self, index: u64, term: u64, timeout: Duration) -> bool {
+        let mut commit_index = self.commit_index.subscribe();
+        let _ = tokio::time::timeout(timeout, commit_index.wait_for(|commit_index| *commit_index >= index)).await;
+
+        self.committed_term(index) == Some(term)
+    }
+
+    // Envia as mensagens pendentes do nó e entrega as entradas confirmadas.
+    // O nó fica travado até o fim para que as entradas saiam em ordem
+    fn flush(&self) {
+        let mut node = self.node.lock().unwrap();
+
+        let peers = self.peers.lock().unwrap();
+        for envelope in node.take_messages() {
+            if let Some(peer) = peers.get(&envelope.to) {
+                let _ = peer.send(envelope.message);
+            }
+        }
+        drop(peers);
+
+        for entry in node.take_committed() {
+            let _ = self.committed.send(entry);
+        }
+        self.commit_index.send_if_modified(|current| {
+            let changed = node.commit_index() > *current;
+            *current = (*current).max(node.commit_index());
+            changed
+        });
+    }
+}
+
+async fn send_to(identity: &LocalIdentity, name: &str, addr: SocketAddr, handshake: &server::Command, mut messages: mpsc::UnboundedReceiver<RaftMessage>) {
+    loop {
+        match identity.connect(addr, name).await {
+            Ok((mut stream, _)) => {
+                println!("Conectado ao membro {} do Raft", name);
+                let result = async {
+                    stream.write_all(format!("{}\n", handshake.serialize()).as_bytes()).await?;
+                    while let Some(message) = messages.recv().await {
+                        stream.write_all(message.to_line().as_bytes()).await?;
+                    }
+                    Ok::<(), std::io::Error>(())
+                };
+                match result.await {
+                    // O servidor foi encerrado
+                    Ok(()) => return,
+                    Err(e) => println!("Conexão com o membro {} do Raft interrompida: {}", name, e),
+                }
+            }
+            Err(e) => println!("Falha ao conectar ao membro {} do Raft: {}", name, e),
+        }
+
+        tokio::time::sleep(RECONNECT_INTERVAL).await;
+        // Descarta o que acumulou sem conexão: o líder reenvia o necessário
+        while messages.try_recv().is_ok() {}
+    }
+}
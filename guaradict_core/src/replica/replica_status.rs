@@ -1,20 +1,30 @@
+use std::fmt;
 use std::net::SocketAddr;
-use std::sync::Arc;
 use serde::{Deserialize, Serialize};
-use tokio::net::TcpStream;
-use tokio::sync::Mutex;
 use tokio::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::Role;
+
+/// Estado de um nó visto pelo monitor do primário, exposto pelo
+/// CLUSTER STATUS. O ping é serializado em milissegundos.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ReplicaStatus {
     pub name: String,
+    /// Papel informado pelo nó no handshake; vazio antes da primeira conexão.
+    pub role: Option<Role>,
     #[serde(with = "duration_serde")]
     pub ping: Duration,
     pub ready: bool,
+    #[serde(default)]
+    pub state: ReplicaState,
     pub addr: Option<SocketAddr>,
+    /// Endereço do barramento do cluster, por onde os demais nós se conectam.
+    pub bus: Option<SocketAddr>,
     pub failures: u32,
-    #[serde(skip)]
-    pub(crate) stream: Option<Arc<Mutex<TcpStream>>>,
+    /// Versão da última operação aplicada, informada no PONG.
+    pub offset: u64,
+    /// Atraso em relação ao log do primário, calculado a cada PONG.
+    pub lag: ReplicationLag,
 }
 
 impl ReplicaStatus {
@@ -22,26 +32,69 @@ impl ReplicaStatus {
         Self {
             name,
             ready,
+            state: if ready { ReplicaState::Ready } else { ReplicaState::Connecting },
             addr,
             ..Self::default()
         }
     }
+}
 
-    pub fn default() -> Self {
-        Self {
-            name: String::new(),
-            ping: Duration::default(),
-            failures: 0,
-            ready: false,
-            addr: None,
-            stream: None,
+/// Situação da conexão do monitor com uma réplica. `Suspect` é uma réplica
+/// conectada que deixou de responder aos PINGs mas ainda não foi dada como
+/// caída; `Down` é uma réplica sem conexão, à espera da próxima tentativa.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplicaState {
+    #[default]
+    Connecting,
+    Ready,
+    Suspect,
+    Down,
+}
+
+impl fmt::Display for ReplicaState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplicaState::Connecting => write!(f, "connecting"),
+            ReplicaState::Ready => write!(f, "ready"),
+            ReplicaState::Suspect => write!(f, "suspect"),
+            ReplicaState::Down => write!(f, "down"),
         }
     }
 }
 
-impl Default for ReplicaStatus {
-    fn default() -> Self {
-        Self::default()
+/// Mudança de estado de uma réplica, publicada pelo monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaEvent {
+    pub name: String,
+    pub from: ReplicaState,
+    pub to: ReplicaState,
+}
+
+/// Atraso de uma réplica: as operações do log do primário que ela ainda
+/// não aplicou, o tamanho delas em bytes e há quantos segundos espera a mais
+/// antiga.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplicationLag {
+    pub operations: u64,
+    pub bytes: u64,
+    pub seconds: f64,
+}
+
+/// Atraso máximo de uma réplica que recebe leituras. Acima de qualquer um
+/// dos limites, os drivers deixam de mandar leituras a ela.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaxLag {
+    pub operations: Option<u64>,
+    pub bytes: Option<u64>,
+    pub seconds: Option<f64>,
+}
+
+impl MaxLag {
+    pub fn exceeded(&self, lag: &ReplicationLag) -> bool {
+        self.operations.is_some_and(|max| lag.operations > max)
+            || self.bytes.is_some_and(|max| lag.bytes > max)
+            || self.seconds.is_some_and(|max| lag.seconds > max)
     }
 }
 
@@ -53,14 +106,14 @@ mod duration_serde {
     where
         S: Serializer,
     {
-        serializer.serialize_u64(duration.as_secs())
+        serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let secs = u64::deserialize(deserializer)?;
-        Ok(Duration::from_secs(secs))
+        let millis = f64::deserialize(deserializer)?;
+        Ok(Duration::try_from_secs_f64(millis / 1000.0).unwrap_or_default())
     }
 }
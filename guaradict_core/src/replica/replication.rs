@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::TcpStream;
+use tokio::sync::{watch, Mutex};
+use tokio::time::Duration;
+
+use super::{LocalIdentity, LogOperator, Operation, ReplicaStatus, SharedElection};
+use crate::commands::server;
+
+// Intervalo entre tentativas de conexão com uma réplica
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Mensagens trocadas entre o primário e uma réplica, uma por linha em JSON,
+/// depois que o primário se identifica com `HELLO` e abre a conexão com
+/// `SYNC <termo> <nome>`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ReplicationMessage {
+    /// Do primário para a réplica: um lote do log de operações.
+    Batch { operations: Vec<Operation> },
+    /// Da réplica para o primário: versão da última operação aplicada.
+    Ack { offset: u64 },
+    /// Da réplica para o primário: o termo do primário está desatualizado.
+    Rejected { term: u64, leader: Option<String> },
+}
+
+impl ReplicationMessage {
+    pub fn to_line(&self) -> String {
+        format!("{}\n", serde_json::to_string(self).unwrap())
+    }
+
+    pub fn from_line(line: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(line.trim())
+    }
+}
+
+/// Quantas confirmações uma escrita exige antes de o primário responder.
+/// `Replicas(n)` conta apenas réplicas; `Majority` é a maioria dos nós,
+/// contando o primário, e `All` são todas as réplicas configuradas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "AcksValue", into = "AcksValue")]
+pub enum Acks {
+    #[default]
+    None,
+    Replicas(usize),
+    Majority,
+    All,
+}
+
+impl Acks {
+    /// Quantidade de réplicas que precisam confirmar a escrita.
+    pub fn required(&self, replicas: usize) -> usize {
+        match self {
+            Acks::None => 0,
+            Acks::Replicas(count) => *count,
+            // Maioria de réplicas + 1 nós, já contando o voto do primário
+            Acks::Majority => replicas.div_ceil(2),
+            Acks::All => replicas,
+        }
+    }
+}
+
+impl FromStr for Acks {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "majority" => Ok(Acks::Majority),
+            "all" => Ok(Acks::All),
+            count => match count.parse::<usize>() {
+                Ok(0) => Ok(Acks::None),
+                Ok(count) => Ok(Acks::Replicas(count)),
+                Err(_) => Err(format!("Valor de acks inválido: {}. Valores permitidos: um número, 'majority', 'all'", value)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Acks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Acks::None => write!(f, "0"),
+            Acks::Replicas(count) => write!(f, "{}", count),
+            Acks::Majority => write!(f, "majority"),
+            Acks::All => write!(f, "all"),
+        }
+    }
+}
+
+// Na configuração `acks` pode ser um número ou um nome
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum AcksValue {
+    Count(usize),
+    Name(String),
+}
+
+impl TryFrom<AcksValue> for Acks {
+    type Error = String;
+
+    fn try_from(value: AcksValue) -> Result<Self, Self::Error> {
+        match value {
+            AcksValue::Count(0) => Ok(Acks::None),
+            AcksValue::Count(count) => Ok(Acks::Replicas(count)),
+            AcksValue::Name(name) => name.parse(),
+        }
+    }
+}
+
+impl From<Acks> for AcksValue {
+    fn from(acks: Acks) -> Self {
+        match acks {
+            Acks::None => AcksValue::Count(0),
+            Acks::Replicas(count) => AcksValue::Count(count),
+            acks => AcksValue::Name(acks.to_string()),
+        }
+    }
+}
+
+/// Confirmações recebidas das réplicas: a versão da última operação que cada
+/// uma aplicou. As escritas esperam aqui até que réplicas suficientes as
+/// tenham confirmado.
+#[derive(Debug)]
+pub struct ReplicationAcks {
+    replicas: AtomicUsize,
+    acked: watch::Sender<HashMap<String, u64>>,
+}
+
+impl ReplicationAcks {
+    pub fn new(replicas: usize) -> Self {
+        Self {
+            replicas: AtomicUsize::new(replicas),
+            acked: watch::Sender::new(HashMap::new()),
+        }
+    }
+
+    /// Quantidade de réplicas configuradas.
+    pub fn replica_count(&self) -> usize {
+        self.replicas.load(Ordering::Relaxed)
+    }
+
+    /// Atualiza a quantidade de réplicas, quando o nó é promovido a primário.
+    pub fn set_replica_count(&self, replicas: usize) {
+        self.replicas.store(replicas, Ordering::Relaxed);
+    }
+
+    pub fn ack(&self, replica: &str, offset: u64) {
+        self.acked.send_if_modified(|acked| {
+            let current = acked.entry(replica.to_string()).or_default();
+            if offset > *current {
+                *current = offset;
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    /// Quantas réplicas já aplicaram a operação de versão `offset`.
+    pub fn acked(&self, offset: u64) -> usize {
+        count_acked(&self.acked.borrow(), offset)
+    }
+
+    /// Espera até `required` réplicas confirmarem a versão `offset` ou até o
+    /// tempo acabar, retornando quantas confirmaram. Sem tempo limite espera
+    /// indefinidamente.
+    pub async fn wait_for(&self, offset: u64, required: usize, timeout: Option<Duration>) -> usize {
+        if required > 0 {
+            let mut acked = self.acked.subscribe();
+            let wait = acked.wait_for(|acked| count_acked(acked, offset) >= required);
+
+            match timeout {
+                Some(timeout) => {
+                    let _ = tokio::time::timeout(timeout, wait).await;
+                }
+                None => {
+                    let _ = wait.await;
+                }
+            }
+        }
+
+        self.acked(offset)
+    }
+}
+
+fn count_acked(acked: &HashMap<String, u64>, offset: u64) -> usize {
+    acked.values().filter(|acked| **acked >= offset).count()
+}
+
+/// Envia o log de operações do primário às réplicas. Cada réplica tem sua
+/// própria conexão, pela qual recebe os lotes ainda não vistos e devolve as
+/// confirmações. O envio dura enquanto o nó for o primário do termo em que
+/// começou.
+pub struct ReplicationServer {
+    replicas: Vec<ReplicaStatus>,
+    operations_log: Arc<Mutex<LogOperator>>,
+    offset: watch::Receiver<u64>,
+    acks: Arc<ReplicationAcks>,
+    election: SharedElection,
+    identity: LocalIdentity,
+}
+
+impl ReplicationServer {
+    pub fn new(replicas: Vec<ReplicaStatus>, operations_log: Arc<Mutex<LogOperator>>, offset: watch::Receiver<u64>, acks: Arc<ReplicationAcks>, election: SharedElection, identity: LocalIdentity) -> Self {
+        Self {
+            replicas,
+            operations_log,
+            offset,
+            acks,
+            election,
+            identity,
+        }
+    }
+
+    pub async fn start(&self) {
+        let (leader, term) = {
+            let election = self.election.lock().unwrap();
+            (election.name().to_string(), election.term())
+        };
+
+        for replica in &self.replicas {
+            let Some(addr) = replica.bus else {
+                continue;
+            };
+
+            let name = replica.name.clone();
+            let operations_log = self.operations_log.clone();
+            let offset = self.offset.clone();
+            let acks = self.acks.clone();
+            let election = self.election.clone();
+            let identity = self.identity.clone();
+            let sync = server::Command::Sync(term, leader.clone());
+
+            tokio::spawn(async move {
+                while election.lock().unwrap().is_leader(term) {
+                    let result = match identity.connect(addr, &name).await {
+                        Ok((stream, _)) => stream_to(&name, stream, &sync, &operations_log, offset.clone(), &acks, &election).await,
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = result {
+                        println!("Replicação para {} interrompida: {}", name, e);
+                    }
+                    tokio::time::sleep(RECONNECT_INTERVAL).await;
+                }
+                println!("Replicação para {} encerrada: o nó deixou de ser primário do termo {}", name, term);
+            });
+        }
+    }
+}
+
+async fn stream_to(name: &str, stream: TcpStream, sync: &server::Command, operations_log: &Mutex<LogOperator>, mut offset: watch::Receiver<u64>, acks: &ReplicationAcks, election: &SharedElection) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    writer.write_all(format!("{}\n", sync.serialize()).as_bytes()).await?;
+
+    // A réplica responde com a versão que já possui; o envio começa dali
+    let mut sent = match read_message(&mut lines).await? {
+        ReplicationMessage::Ack { offset } => offset,
+        ReplicationMessage::Rejected { term, leader } => {
+            // Outro nó foi eleito num termo mais novo: este deixa de ser primário
+            if let Some(leader) = leader {
+                election.lock().unwrap().accept_leader(term, &leader);
+            } else {
+                election.lock().unwrap().observe_term(term);
+            }
+            return Err(std::io::Error::other(format!("Termo desatualizado, o termo atual é {}", term)));
+        }
+        _ => return Err(std::io::Error::other("Resposta inválida da réplica")),
+    };
+    acks.ack(name, sent);
+    println!("Replicando para {} a partir da versão {}", name, sent);
+
+    loop {
+        offset.borrow_and_update();
+        let batches = operations_log.lock().await.batches_after(sent);
+
+        for operations in batches {
+            let last = operations.last().map_or(sent, |operation| operation.version);
+            writer.write_all(ReplicationMessage::Batch { operations }.to_line().as_bytes()).await?;
+            sent = last;
+        }
+
+        tokio::select! {
+            changed = offset.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+            }
+            message = read_message(&mut lines) => {
+                if let ReplicationMessage::Ack { offset } = message? {
+                    acks.ack(name, offset);
+                }
+            }
+        }
+    }
+}
+
+async fn read_message(lines: &mut Lines<BufReader<OwnedReadHalf>>) -> std::io::Result<ReplicationMessage> {
+    let line = lines
+        .next_line()
+        .await?
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Conexão encerrada pela réplica"))?;
+
+    ReplicationMessage::from_line(&line).map_err(std::io::Error::other)
+}
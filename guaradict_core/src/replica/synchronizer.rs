@@ -1,48 +1,48 @@
 use std::sync::Arc;
 use tokio::sync::mpsc::Receiver;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
 
-use crate::commands::client;
-use crate::replica::log_operator::LogOperator;
+use crate::replica::log_operator::{LogOperator, Operation};
 
 pub struct SynchronizerServer {
-    rx: Arc<Mutex<Receiver<client::Command>>>,
+    rx: Arc<Mutex<Receiver<Vec<Operation>>>>,
     operations_log: Arc<Mutex<LogOperator>>,
+    // Versão da última operação acrescentada ao log
+    offset: watch::Sender<u64>,
 }
 
 impl SynchronizerServer {
-    pub fn new(rx: Receiver<client::Command>, operations_log: LogOperator) -> Self {
+    pub fn new(rx: Receiver<Vec<Operation>>, operations_log: LogOperator) -> Self {
+        let offset = operations_log.operations.last().map_or(0, |operation| operation.version);
+
         Self {
             rx: Arc::new(Mutex::new(rx)),
             operations_log: Arc::new(Mutex::new(operations_log)),
+            offset: watch::Sender::new(offset),
         }
     }
 
+    pub fn operations_log(&self) -> Arc<Mutex<LogOperator>> {
+        self.operations_log.clone()
+    }
+
+    /// Acompanha o offset do log, que avança a cada lote acrescentado.
+    pub fn subscribe_offset(&self) -> watch::Receiver<u64> {
+        self.offset.subscribe()
+    }
+
     pub async fn start(&self) {
         let mut rx = self.rx.as_ref().lock().await;
 
-        while let Some(log) = rx.recv().await {
+        while let Some(operations) = rx.recv().await {
+            let last = operations.last().map(|operation| operation.version);
+
             let mut op = self.operations_log.as_ref().lock().await;
-            match log {
-                client::Command::Add(key, value) => {
-                    op.insert(key, value);
-                    drop(op);
-                },
-                client::Command::Set(key, new_value) => {
-                    // @TODO valor antigo do dicioario com enum proprio de log de operações
-                    let prev_value = None;
-                    op.update(key, new_value, prev_value);
-                    drop(op);
-                },
-                client::Command::Del(key) => {
-                    op.delete(key);
-                    drop(op);
-                },
-                client::Command::Get(_) => {
-                    println!("{:#?}", op);
-                    drop(op);
-                },
-                client::Command::Quit => {},
+            op.append_batch(operations);
+            drop(op);
+
+            if let Some(offset) = last {
+                self.offset.send_replace(offset);
             }
         }
     }
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, ImmutableString, Scope, AST};
+
+use crate::errors::{DictionaryError, ScriptError};
+use crate::{Dictionary, ListSide, Savepoint};
+
+/// Estado de uma execução em andamento, compartilhado com as funções
+/// registradas no motor. O dicionário fica aqui apenas durante a execução,
+/// junto com o estado anterior das chaves que o script altera.
+#[derive(Default)]
+struct Execution {
+    dictionary: Option<Dictionary>,
+    savepoint: Option<Savepoint>,
+    deadline: Option<Instant>,
+}
+
+type SharedExecution = Arc<Mutex<Execution>>;
+
+/// Executa scripts Rhai contra o dicionário. Os scripts compilados ficam em
+/// cache pelo hash SHA-1 do código-fonte.
+///
+/// As escritas feitas pelo script são registradas no log de operações do
+/// dicionário como as de qualquer comando, então o que se replica é o
+/// resultado do script, não o script. O script é atômico: num erro ou
+/// estouro de tempo as chaves que ele alterou voltam ao valor anterior e as
+/// suas operações saem do log, então nada do script é replicado.
+pub struct ScriptEngine {
+    engine: Engine,
+    execution: SharedExecution,
+    cache: HashMap<String, AST>,
+    time_limit: Duration,
+}
+
+impl ScriptEngine {
+    pub fn new(time_limit: Duration) -> Self {
+        let execution = SharedExecution::default();
+        let mut engine = Engine::new();
+
+        let progress = execution.clone();
+        engine.on_progress(move |operations| {
+            if operations % 256 != 0 {
+                return None;
+            }
+
+            let deadline = progress.lock().unwrap().deadline;
+            match deadline {
+                Some(deadline) if Instant::now() > deadline => Some(Dynamic::UNIT),
+                _ => None,
+            }
+        });
+
+        register_functions(&mut engine, &execution);
+
+        Self {
+            engine,
+            execution,
+            cache: HashMap::new(),
+            time_limit,
+        }
+    }
+
+    /// Compila o script e o guarda no cache, retornando seu hash.
+    pub fn load(&mut self, source: &str) -> Result<String, ScriptError> {
+        let hash = script_hash(source);
+        if !self.cache.contains_key(&hash) {
+            let ast = self.engine.compile(source).map_err(|e| ScriptError::Compile(e.to_string()))?;
+            self.cache.insert(hash.clone(), ast);
+        }
+        Ok(hash)
+    }
+
+    pub fn exists(&self, hash: &str) -> bool {
+        self.cache.contains_key(hash)
+    }
+
+    pub fn flush(&mut self) {
+        self.cache.clear();
+    }
+
+    pub fn eval(&mut self, source: &str, keys: Vec<String>, args: Vec<String>, dictionary: &mut Dictionary) -> Result<serde_json::Value, ScriptError> {
+        let hash = self.load(source)?;
+        self.eval_hash(&hash, keys, args, dictionary)
+    }
+
+    /// Executa um script do cache. Os scripts recebem as variáveis `KEYS` e
+    /// `ARGV` e o valor da última expressão é o resultado.
+    pub fn eval_hash(&self, hash: &str, keys: Vec<String>, args: Vec<String>, dictionary: &mut Dictionary) -> Result<serde_json::Value, ScriptError> {
+        let ast = self.cache.get(hash).ok_or_else(|| ScriptError::NotFound(hash.to_string()))?;
+
+        let mut scope = Scope::new();
+        scope.push_constant("KEYS", keys.into_iter().map(Dynamic::from).collect::<Array>());
+        scope.push_constant("ARGV", args.into_iter().map(Dynamic::from).collect::<Array>());
+
+        {
+            let mut execution = self.execution.lock().unwrap();
+            execution.savepoint = Some(dictionary.savepoint());
+            execution.dictionary = Some(std::mem::take(dictionary));
+            execution.deadline = Some(Instant::now() + self.time_limit);
+        }
+
+        let result = self.engine.eval_ast_with_scope::<Dynamic>(&mut scope, ast);
+
+        let savepoint = {
+            let mut execution = self.execution.lock().unwrap();
+            *dictionary = execution.dictionary.take().unwrap_or_default();
+            execution.deadline = None;
+            execution.savepoint.take()
+        };
+
+        let result = match result {
+            Ok(value) => rhai::serde::from_dynamic(&value).map_err(|e| ScriptError::Runtime(e.to_string())),
+            Err(e) => match *e {
+                EvalAltResult::ErrorTerminated(..) => Err(ScriptError::Timeout(self.time_limit)),
+                e => Err(ScriptError::Runtime(e.to_string())),
+            },
+        };
+        if let (Err(_), Some(savepoint)) = (&result, savepoint) {
+            dictionary.rollback(savepoint);
+        }
+        result
+    }
+}
+
+/// Hash SHA-1 em hexadecimal do código-fonte do script.
+pub fn script_hash(source: &str) -> String {
+    sha1_smol::Sha1::from(source).digest().to_string()
+}
+
+fn with_dictionary<T, F>(execution: &SharedExecution, f: F) -> Result<T, Box<EvalAltResult>>
+where
+    F: FnOnce(&mut Dictionary) -> Result<T, DictionaryError>,
+{
+    let mut execution = execution.lock().unwrap();
+    let dictionary = execution.dictionary.as_mut().ok_or("Nenhum script em execução")?;
+    f(dictionary).map_err(|e| e.to_string().into())
+}
+
+// Como `with_dictionary`, guardando antes o estado da chave que vai mudar
+fn write_key<T, F>(execution: &SharedExecution, key: &str, f: F) -> Result<T, Box<EvalAltResult>>
+where
+    F: FnOnce(&mut Dictionary) -> Result<T, DictionaryError>,
+{
+    let mut execution = execution.lock().unwrap();
+    let Execution { dictionary, savepoint, .. } = &mut *execution;
+    let dictionary = dictionary.as_mut().ok_or("Nenhum script em execução")?;
+    if let Some(savepoint) = savepoint {
+        savepoint.remember(dictionary, key);
+    }
+    f(dictionary).map_err(|e| e.to_string().into())
+}
+
+fn optional(value: Option<String>) -> Dynamic {
+    value.map_or(Dynamic::UNIT, Dynamic::from)
+}
+
+// Funções disponíveis aos scripts, com os mesmos nomes dos comandos
+fn register_functions(engine: &mut Engine, execution: &SharedExecution) {
+    let shared = execution.clone();
+    engine.register_fn("get", move |key: ImmutableString| {
+        with_dictionary(&shared, |dictionary| Ok(optional(dictionary.get_definition(&key).cloned())))
+    });
+
+    let shared = execution.clone();
+    engine.register_fn("set", move |key: ImmutableString, value: ImmutableString| {
+        write_key(&shared, &key, |dictionary| {
+            dictionary.add_entry(key.to_string(), value.to_string());
+            Ok(())
+        })
+    });
+
+    let shared = execution.clone();
+    engine.register_fn("del", move |key: ImmutableString| {
+        write_key(&shared, &key, |dictionary| Ok(dictionary.remove_entries(&[key.to_string()]) > 0))
+    });
+
+    let shared = execution.clone();
+    engine.register_fn("exists", move |key: ImmutableString| {
+        with_dictionary(&shared, |dictionary| Ok(dictionary.get(&key).is_some()))
+    });
+
+    let shared = execution.clone();
+    engine.register_fn("version", move |key: ImmutableString| {
+        with_dictionary(&shared, |dictionary| Ok(dictionary.version(&key) as i64))
+    });
+
+    let shared = execution.clone();
+    engine.register_fn("hget", move |key: ImmutableString, field: ImmutableString| {
+        with_dictionary(&shared, |dictionary| Ok(optional(dictionary.map_get(&key, &field)?.cloned())))
+    });
+
+    let shared = execution.clone();
+    engine.register_fn("hset", move |key: ImmutableString, field: ImmutableString, value: ImmutableString| {
+        write_key(&shared, &key, |dictionary| dictionary.map_set(&key, &field, value.to_string()))
+    });
+
+    let shared = execution.clone();
+    engine.register_fn("hdel", move |key: ImmutableString, field: ImmutableString| {
+        write_key(&shared, &key, |dictionary| dictionary.map_delete(&key, &field))
+    });
+
+    let shared = execution.clone();
+    engine.register_fn("hincrby", move |key: ImmutableString, field: ImmutableString, by: i64| {
+        write_key(&shared, &key, |dictionary| dictionary.map_increment(&key, &field, by))
+    });
+
+    for (name, side) in [("lpush", ListSide::Left), ("rpush", ListSide::Right)] {
+        let shared = execution.clone();
+        engine.register_fn(name, move |key: ImmutableString, value: ImmutableString| {
+            write_key(&shared, &key, |dictionary| Ok(dictionary.list_push(&key, side, vec![value.to_string()])? as i64))
+        });
+    }
+
+    for (name, side) in [("lpop", ListSide::Left), ("rpop", ListSide::Right)] {
+        let shared = execution.clone();
+        engine.register_fn(name, move |key: ImmutableString| {
+            write_key(&shared, &key, |dictionary| Ok(optional(dictionary.list_pop(&key, side)?)))
+        });
+    }
+
+    let shared = execution.clone();
+    engine.register_fn("llen", move |key: ImmutableString| {
+        with_dictionary(&shared, |dictionary| Ok(dictionary.list_len(&key)? as i64))
+    });
+
+    let shared = execution.clone();
+    engine.register_fn("sadd", move |key: ImmutableString, member: ImmutableString| {
+        write_key(&shared, &key, |dictionary| Ok(dictionary.set_add(&key, vec![member.to_string()])? as i64))
+    });
+
+    let shared = execution.clone();
+    engine.register_fn("srem", move |key: ImmutableString, member: ImmutableString| {
+        write_key(&shared, &key, |dictionary| Ok(dictionary.set_remove(&key, vec![member.to_string()])? as i64))
+    });
+
+    let shared = execution.clone();
+    engine.register_fn("sismember", move |key: ImmutableString, member: ImmutableString| {
+        with_dictionary(&shared, |dictionary| dictionary.set_is_member(&key, &member))
+    });
+}
@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+
+// Parâmetros do BM25 usados no ranqueamento dos resultados
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+/// Índice invertido sobre as definições do dicionário.
+///
+/// Cada termo aponta para as palavras cujas definições o contêm, junto com a
+/// frequência do termo na definição.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct SearchIndex {
+    postings: HashMap<String, HashMap<String, u32>>,
+    documents: HashMap<String, Document>,
+    total_tokens: usize,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+struct Document {
+    length: usize,
+    terms: Vec<String>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct SearchHit {
+    pub word: String,
+    pub score: f64,
+}
+
+/// Consulta em forma normal disjuntiva: grupos separados por `OR`, termos
+/// dentro de um grupo combinados com `AND` (implícito ou explícito).
+#[derive(PartialEq, Debug, Clone)]
+pub struct SearchQuery {
+    pub groups: Vec<Vec<String>>,
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Result<SearchQuery, &'static str> {
+        let mut groups = Vec::new();
+        let mut current = Vec::new();
+
+        for part in input.split_whitespace() {
+            match part {
+                "OR" | "|" => {
+                    if !current.is_empty() {
+                        groups.push(std::mem::take(&mut current));
+                    }
+                }
+                "AND" | "&" => {}
+                _ => current.extend(tokenize(part)),
+            }
+        }
+
+        if !current.is_empty() {
+            groups.push(current);
+        }
+
+        if groups.is_empty() {
+            return Err("Consulta de busca vazia");
+        }
+
+        Ok(SearchQuery { groups })
+    }
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, word: &str, text: &str) {
+        self.remove(word);
+
+        let tokens = tokenize(text);
+        if tokens.is_empty() {
+            return;
+        }
+
+        let mut terms = Vec::new();
+        for token in &tokens {
+            let frequency = self
+                .postings
+                .entry(token.clone())
+                .or_default()
+                .entry(word.to_string())
+                .or_insert(0);
+            if *frequency == 0 {
+                terms.push(token.clone());
+            }
+            *frequency += 1;
+        }
+
+        self.total_tokens += tokens.len();
+        self.documents.insert(word.to_string(), Document { length: tokens.len(), terms });
+    }
+
+    pub fn remove(&mut self, word: &str) {
+        let Some(document) = self.documents.remove(word) else {
+            return;
+        };
+
+        self.total_tokens -= document.length;
+        for term in document.terms {
+            if let Some(words) = self.postings.get_mut(&term) {
+                words.remove(word);
+                if words.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.postings.clear();
+        self.documents.clear();
+        self.total_tokens = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    pub fn search(&self, query: &SearchQuery) -> Vec<SearchHit> {
+        let mut matches: HashSet<&str> = HashSet::new();
+
+        for group in &query.groups {
+            let mut group_matches: Option<HashSet<&str>> = None;
+
+            for term in group {
+                let words = self
+                    .postings
+                    .get(term)
+                    .map(|words| words.keys().map(String::as_str).collect::<HashSet<&str>>())
+                    .unwrap_or_default();
+
+                group_matches = Some(match group_matches {
+                    Some(previous) => previous.intersection(&words).copied().collect(),
+                    None => words,
+                });
+            }
+
+            matches.extend(group_matches.unwrap_or_default());
+        }
+
+        let terms = query.groups.iter().flatten().collect::<HashSet<&String>>();
+        let mut hits = matches
+            .into_iter()
+            .map(|word| SearchHit {
+                word: word.to_string(),
+                score: terms.iter().map(|term| self.score(term, word)).sum(),
+            })
+            .collect::<Vec<SearchHit>>();
+
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.word.cmp(&b.word)));
+        hits
+    }
+
+    fn score(&self, term: &str, word: &str) -> f64 {
+        let Some(words) = self.postings.get(term) else {
+            return 0.0;
+        };
+        let Some(frequency) = words.get(word) else {
+            return 0.0;
+        };
+
+        let documents = self.documents.len() as f64;
+        let document_frequency = words.len() as f64;
+        let idf = (1.0 + (documents - document_frequency + 0.5) / (document_frequency + 0.5)).ln();
+
+        let length = self.documents.get(word).map(|document| document.length).unwrap_or_default() as f64;
+        let average_length = self.total_tokens as f64 / documents;
+        let frequency = *frequency as f64;
+
+        idf * frequency * (BM25_K1 + 1.0)
+            / (frequency + BM25_K1 * (1.0 - BM25_B + BM25_B * length / average_length))
+    }
+}
+
+/// Quebra o texto em termos normalizados: minúsculos e sem acentos.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.chars().flat_map(char::to_lowercase).map(fold_accent).collect())
+        .collect()
+}
+
+/// Remove o acento de um caractere minúsculo (ex.: 'ã' -> 'a', 'ç' -> 'c').
+pub fn fold_accent(c: char) -> char {
+    match c {
+        'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        'ñ' => 'n',
+        _ => c,
+    }
+}
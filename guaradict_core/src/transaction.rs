@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use crate::commands::client::Command;
+use crate::errors::TransactionError;
+use crate::Dictionary;
+
+/// Estado de transação de uma conexão: comandos enfileirados entre MULTI e
+/// EXEC e as chaves observadas com WATCH.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    queued: Option<Vec<Command>>,
+    failed: bool,
+    // Versão de cada chave observada no momento do WATCH
+    watched: HashMap<String, u64>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.queued.is_some()
+    }
+
+    pub fn begin(&mut self) -> Result<(), TransactionError> {
+        if self.is_active() {
+            return Err(TransactionError::Nested);
+        }
+
+        self.queued = Some(Vec::new());
+        self.failed = false;
+        Ok(())
+    }
+
+    pub fn queue(&mut self, command: Command) {
+        if let Some(queued) = &mut self.queued {
+            queued.push(command);
+        }
+    }
+
+    /// Marca a transação em andamento para ser descartada no EXEC, como
+    /// quando um comando enfileirado é inválido.
+    pub fn fail(&mut self) {
+        if self.is_active() {
+            self.failed = true;
+        }
+    }
+
+    pub fn watch(&mut self, keys: Vec<String>, dictionary: &Dictionary) -> Result<(), TransactionError> {
+        if self.is_active() {
+            return Err(TransactionError::WatchInsideMulti);
+        }
+
+        for key in keys {
+            let version = dictionary.version(&key);
+            self.watched.entry(key).or_insert(version);
+        }
+        Ok(())
+    }
+
+    pub fn unwatch(&mut self) {
+        self.watched.clear();
+    }
+
+    pub fn discard(&mut self) -> Result<(), TransactionError> {
+        if self.queued.take().is_none() {
+            return Err(TransactionError::NotStarted("DISCARD"));
+        }
+
+        self.failed = false;
+        self.unwatch();
+        Ok(())
+    }
+
+    /// Encerra a transação e devolve os comandos a executar. Deve ser chamado
+    /// com o dicionário travado até que os comandos sejam executados, para que
+    /// nenhuma chave observada mude entre a verificação e a execução.
+    pub fn exec(&mut self, dictionary: &Dictionary) -> Result<Vec<Command>, TransactionError> {
+        let queued = self.queued.take().ok_or(TransactionError::NotStarted("EXEC"))?;
+        let failed = std::mem::take(&mut self.failed);
+        let watched = std::mem::take(&mut self.watched);
+
+        if failed {
+            return Err(TransactionError::Failed);
+        }
+
+        if watched.iter().any(|(key, version)| dictionary.version(key) != *version) {
+            return Err(TransactionError::WatchedKeyChanged);
+        }
+
+        Ok(queued)
+    }
+}
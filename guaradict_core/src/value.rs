@@ -0,0 +1,255 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use serde::{Deserialize, Serialize};
+
+use crate::crdt::Crdt;
+use crate::replica::{OperationKey, OperationValue};
+
+/// Valor armazenado no dicionário para uma palavra.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum Value {
+    Text(String),
+    Entry(Entry),
+    Map(HashMap<String, String>),
+    List(VecDeque<String>),
+    Set(HashSet<String>),
+    Crdt(Crdt),
+}
+
+/// Extremidade de uma lista: `Left` é a cabeça e `Right` a cauda.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ListSide {
+    Left,
+    Right,
+}
+
+/// Verbete estruturado: uma palavra com uma ou mais acepções.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct Entry {
+    pub senses: Vec<Sense>,
+}
+
+/// Acepção de um verbete.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Sense {
+    pub definition: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part_of_speech: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub synonyms: Vec<String>,
+}
+
+impl Sense {
+    pub fn new(definition: String) -> Self {
+        Self {
+            definition,
+            ..Self::default()
+        }
+    }
+}
+
+impl Value {
+    /// Definição principal: o texto simples ou a primeira acepção do verbete.
+    pub fn definition(&self) -> Option<&String> {
+        match self {
+            Value::Text(text) => Some(text),
+            Value::Entry(entry) => entry.senses.first().map(|sense| &sense.definition),
+            _ => None,
+        }
+    }
+
+    /// Converte o valor em verbete; um texto simples vira uma única acepção.
+    /// Valores de outros tipos não são verbetes.
+    pub fn to_entry(&self) -> Option<Entry> {
+        match self {
+            Value::Text(text) => Some(Entry {
+                senses: vec![Sense::new(text.to_string())],
+            }),
+            Value::Entry(entry) => Some(entry.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Text(_) => "text",
+            Value::Entry(_) => "entry",
+            Value::Map(_) => "map",
+            Value::List(_) => "list",
+            Value::Set(_) => "set",
+            Value::Crdt(crdt) => crdt.type_name(),
+        }
+    }
+
+    /// Representação JSON, no mesmo formato das respostas de leitura.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Text(text) => serde_json::Value::from(text.as_str()),
+            Value::Entry(entry) => serde_json::to_value(entry).unwrap(),
+            Value::Map(map) => serde_json::to_value(map).unwrap(),
+            Value::List(list) => serde_json::to_value(list).unwrap(),
+            Value::Set(set) => {
+                let mut members = set.iter().collect::<Vec<&String>>();
+                members.sort();
+                serde_json::to_value(members).unwrap()
+            }
+            Value::Crdt(crdt) => crdt.to_json(),
+        }
+    }
+
+    /// Texto indexado pela busca: definições e exemplos.
+    pub fn searchable_text(&self) -> String {
+        match self {
+            Value::Text(text) => text.to_string(),
+            Value::Entry(entry) => entry
+                .senses
+                .iter()
+                .flat_map(|sense| std::iter::once(&sense.definition).chain(sense.examples.iter()))
+                .map(String::as_str)
+                .collect::<Vec<&str>>()
+                .join("\n"),
+            Value::Map(map) => map.values().map(String::as_str).collect::<Vec<&str>>().join("\n"),
+            Value::List(list) => list.iter().map(String::as_str).collect::<Vec<&str>>().join("\n"),
+            Value::Set(set) => set.iter().map(String::as_str).collect::<Vec<&str>>().join("\n"),
+            Value::Crdt(crdt) => crdt.searchable_text(),
+        }
+    }
+}
+
+impl From<&Value> for OperationValue {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Text(text) => OperationValue::from(text.as_str()),
+            Value::Entry(entry) => OperationValue::from(entry),
+            Value::Map(map) => OperationValue::MapValue(
+                map.iter()
+                    .map(|(field, value)| (field.as_str().into(), value.as_str().into()))
+                    .collect(),
+            ),
+            Value::List(list) => list.iter().map(String::as_str).collect::<Vec<&str>>().into(),
+            Value::Set(set) => {
+                let mut members = set.iter().cloned().collect::<Vec<String>>();
+                members.sort();
+                OperationValue::SetValue(members)
+            }
+            Value::Crdt(crdt) => OperationValue::CrdtValue(crdt.clone()),
+        }
+    }
+}
+
+impl TryFrom<&OperationValue> for Value {
+    type Error = &'static str;
+
+    fn try_from(value: &OperationValue) -> Result<Self, Self::Error> {
+        match value {
+            OperationValue::StringValue(text) => Ok(Value::Text(text.to_string())),
+            OperationValue::MapValue(map) => Entry::try_from(value).map(Value::Entry).or_else(|_| {
+                map.iter()
+                    .map(|(field, value)| match value {
+                        OperationValue::StringValue(text) => Ok((field.to_string(), text.to_string())),
+                        _ => Err("Valor de operação não suportado"),
+                    })
+                    .collect::<Result<HashMap<String, String>, _>>()
+                    .map(Value::Map)
+            }),
+            OperationValue::VecValue(values) => values
+                .iter()
+                .map(|value| match value {
+                    OperationValue::StringValue(text) => Ok(text.to_string()),
+                    _ => Err("Valor de operação não suportado"),
+                })
+                .collect::<Result<VecDeque<String>, _>>()
+                .map(Value::List),
+            OperationValue::SetValue(members) => Ok(Value::Set(members.iter().cloned().collect())),
+            OperationValue::CrdtValue(crdt) => Ok(Value::Crdt(crdt.clone())),
+            _ => Err("Valor de operação não suportado"),
+        }
+    }
+}
+
+impl From<&Entry> for OperationValue {
+    fn from(entry: &Entry) -> Self {
+        let senses = entry.senses.iter().map(OperationValue::from).collect::<Vec<OperationValue>>();
+        OperationValue::from(vec![("senses", senses)])
+    }
+}
+
+impl TryFrom<&OperationValue> for Entry {
+    type Error = &'static str;
+
+    fn try_from(value: &OperationValue) -> Result<Self, Self::Error> {
+        let Some(OperationValue::VecValue(senses)) = field(value, "senses") else {
+            return Err("Verbete sem acepções");
+        };
+
+        let senses = senses.iter().map(Sense::try_from).collect::<Result<Vec<Sense>, _>>()?;
+        Ok(Entry { senses })
+    }
+}
+
+impl From<&Sense> for OperationValue {
+    fn from(sense: &Sense) -> Self {
+        let mut map: HashMap<OperationKey, OperationValue> = HashMap::new();
+        map.insert("definition".into(), sense.definition.as_str().into());
+        if let Some(part_of_speech) = &sense.part_of_speech {
+            map.insert("partOfSpeech".into(), part_of_speech.as_str().into());
+        }
+        if let Some(language) = &sense.language {
+            map.insert("language".into(), language.as_str().into());
+        }
+        map.insert("examples".into(), sense.examples.clone().into());
+        map.insert("synonyms".into(), sense.synonyms.clone().into());
+
+        OperationValue::MapValue(map)
+    }
+}
+
+impl TryFrom<&OperationValue> for Sense {
+    type Error = &'static str;
+
+    fn try_from(value: &OperationValue) -> Result<Self, Self::Error> {
+        let Some(OperationValue::StringValue(definition)) = field(value, "definition") else {
+            return Err("Acepção sem definição");
+        };
+
+        Ok(Sense {
+            definition: definition.to_string(),
+            part_of_speech: string_field(value, "partOfSpeech"),
+            examples: strings_field(value, "examples"),
+            language: string_field(value, "language"),
+            synonyms: strings_field(value, "synonyms"),
+        })
+    }
+}
+
+fn field<'a>(value: &'a OperationValue, name: &str) -> Option<&'a OperationValue> {
+    match value {
+        OperationValue::MapValue(map) => map.get(&OperationKey::from(name)),
+        _ => None,
+    }
+}
+
+fn string_field(value: &OperationValue, name: &str) -> Option<String> {
+    match field(value, name) {
+        Some(OperationValue::StringValue(text)) => Some(text.to_string()),
+        _ => None,
+    }
+}
+
+fn strings_field(value: &OperationValue, name: &str) -> Vec<String> {
+    match field(value, name) {
+        Some(OperationValue::VecValue(values)) => values
+            .iter()
+            .filter_map(|value| match value {
+                OperationValue::StringValue(text) => Some(text.to_string()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,321 @@
+use guaradict_core::cluster::{
+    key_slot, plan_rebalance, ClusterNode, NodeRole, ReadPreference, Redirect, SlotMap, SlotMove, SlotRange, SlotState, Topology,
+    TopologyNode, CLUSTER_DISABLED, SLOT_COUNT,
+};
+use guaradict_core::commands::client::Command;
+use guaradict_core::errors::ClusterError;
+use guaradict_core::replica::ReplicationLag;
+use guaradict_core::Dictionary;
+
+fn node(name: &str, port: u16) -> ClusterNode {
+    ClusterNode { name: name.to_string(), addr: format!("127.0.0.1:{}", port), bus: format!("127.0.0.1:{}", port + 10000) }
+}
+
+fn ranges(ranges: &[&str]) -> Vec<SlotRange> {
+    ranges.iter().map(|range| range.parse().unwrap()).collect()
+}
+
+fn three_shards() -> SlotMap {
+    SlotMap::new("shard-node-1", vec![
+        (node("shard-node-1", 13161), ranges(&["0-5460"])),
+        (node("shard-node-2", 13162), ranges(&["5461-10922"])),
+        (node("shard-node-3", 13163), ranges(&["10923-16383"])),
+    ])
+    .unwrap()
+}
+
+// Primeira chave encontrada que cai no slot
+fn key_in_slot_of(slot: u16) -> String {
+    (0..).map(|n| format!("chave{}", n)).find(|key| key_slot(key) == slot).unwrap()
+}
+
+#[test]
+fn test_key_slot() {
+    // Valores de referência do CRC16 (XMODEM) usado pelo Redis Cluster
+    assert_eq!(key_slot("123456789"), 0x31C3 % SLOT_COUNT);
+    assert_eq!(key_slot("foo"), 12182);
+    assert_eq!(key_slot(""), 0);
+
+    // Só o trecho entre chaves conta, se não estiver vazio
+    assert_eq!(key_slot("{usuario:1}:perfil"), key_slot("usuario:1"));
+    assert_eq!(key_slot("pedido:{usuario:1}"), key_slot("{usuario:1}:perfil"));
+    assert_ne!(key_slot("{}casa"), key_slot("casa"));
+}
+
+#[test]
+fn test_slot_range_parse() {
+    assert_eq!("0-5460".parse::<SlotRange>().unwrap(), SlotRange::new(0, 5460).unwrap());
+    assert_eq!("42".parse::<SlotRange>().unwrap().to_string(), "42");
+    assert!("10-5".parse::<SlotRange>().is_err());
+    assert!("0-16384".parse::<SlotRange>().is_err());
+    assert!("a-b".parse::<SlotRange>().is_err());
+}
+
+#[test]
+fn test_slot_map_assignments() {
+    let map = SlotMap::new("shard-node-1", vec![
+        (node("shard-node-1", 13161), ranges(&["0-99", "200-299"])),
+        (node("shard-node-2", 13162), ranges(&["100-199"])),
+    ])
+    .unwrap();
+
+    assert!(map.is_local(250));
+    assert!(!map.is_local(150));
+    assert_eq!(map.owner(150).unwrap().name, "shard-node-2");
+    assert!(map.owner(300).is_none());
+
+    let assignments = map.assignments();
+    assert_eq!(assignments.len(), 3);
+    assert_eq!((assignments[1].start, assignments[1].end), (100, 199));
+    assert_eq!(assignments[1].addr, "127.0.0.1:13162");
+    assert_eq!(map.node_slots("shard-node-1"), ranges(&["0-99", "200-299"]));
+}
+
+#[test]
+fn test_slot_map_rejects_overlap() {
+    let result = SlotMap::new("shard-node-1", vec![
+        (node("shard-node-1", 13161), ranges(&["0-100"])),
+        (node("shard-node-2", 13162), ranges(&["100-200"])),
+    ]);
+
+    assert_eq!(
+        result.unwrap_err(),
+        ClusterError::SlotOverlap { slot: 100, first: "shard-node-1".to_string(), second: "shard-node-2".to_string() }
+    );
+}
+
+#[test]
+fn test_route_redirects_to_owner() {
+    let map = three_shards();
+
+    let local = key_in_slot_of(100);
+    let remote = key_in_slot_of(6000);
+    assert_eq!(map.route([local.as_str()], false, |_| true), Ok(()));
+    assert_eq!(map.route([], false, |_| true), Ok(()));
+
+    let moved = map.route([remote.as_str()], false, |_| true).unwrap_err();
+    assert_eq!(moved, ClusterError::Moved { slot: 6000, addr: "127.0.0.1:13162".to_string() });
+    assert_eq!(moved.to_string(), "MOVED 6000 127.0.0.1:13162");
+
+    // Chaves de slots diferentes não podem ir no mesmo comando, mesmo que
+    // os dois slots sejam deste nó
+    let other_local = key_in_slot_of(101);
+    assert_eq!(map.route([local.as_str(), other_local.as_str()], false, |_| true), Err(ClusterError::CrossSlot));
+    assert_ne!(map.route(["{pedido}:1", "{pedido}:2"], false, |_| true), Err(ClusterError::CrossSlot));
+}
+
+#[test]
+fn test_route_unassigned_slot() {
+    let map = SlotMap::new("shard-node-1", vec![(node("shard-node-1", 13161), ranges(&["0-100"]))]).unwrap();
+
+    let key = key_in_slot_of(200);
+    assert_eq!(map.route([key.as_str()], false, |_| true), Err(ClusterError::SlotUnassigned(200)));
+}
+
+#[test]
+fn test_route_during_migration() {
+    let mut source = three_shards();
+    let mut target = SlotMap::new("shard-node-2", vec![
+        (node("shard-node-1", 13161), ranges(&["0-5460"])),
+        (node("shard-node-2", 13162), ranges(&["5461-10922"])),
+    ])
+    .unwrap();
+
+    let moved = key_in_slot_of(100);
+    let kept = format!("{{{}}}:outra", moved);
+    target.set_slot(100, SlotState::Importing("shard-node-1".to_string())).unwrap();
+    source.set_slot(100, SlotState::Migrating("shard-node-2".to_string())).unwrap();
+    assert_eq!(source.migrating(100).unwrap().name, "shard-node-2");
+
+    // A origem atende as chaves que ainda tem e pede ASK para as demais
+    let exists = |key: &str| key == kept;
+    assert_eq!(source.route([kept.as_str()], false, exists), Ok(()));
+    let ask = source.route([moved.as_str()], false, exists).unwrap_err();
+    assert_eq!(ask.to_string(), "ASK 100 127.0.0.1:13162");
+    assert_eq!(source.route([moved.as_str(), kept.as_str()], false, exists), Err(ask));
+
+    // O destino só atende depois de ASKING
+    assert!(matches!(target.route([moved.as_str()], false, |_| false), Err(ClusterError::Moved { slot: 100, .. })));
+    assert_eq!(target.route([moved.as_str()], true, |_| false), Ok(()));
+
+    // Ao fim, o novo dono passa a atender sem ASKING
+    for map in [&mut source, &mut target] {
+        map.set_slot(100, SlotState::Node("shard-node-2".to_string())).unwrap();
+    }
+    assert!(source.migrating(100).is_none());
+    assert!(target.importing(100).is_none());
+    assert_eq!(target.route([moved.as_str()], false, |_| false), Ok(()));
+    assert_eq!(source.route([moved.as_str()], true, |_| false), Err(ClusterError::Moved { slot: 100, addr: "127.0.0.1:13162".to_string() }));
+}
+
+#[test]
+fn test_set_slot_validation() {
+    let mut map = three_shards();
+
+    assert_eq!(map.set_slot(6000, SlotState::Migrating("shard-node-3".to_string())), Err(ClusterError::SlotNotOwned(6000)));
+    assert_eq!(map.set_slot(6000, SlotState::Importing("shard-node-3".to_string())), Err(ClusterError::SlotNotOwned(6000)));
+    assert_eq!(map.set_slot(1, SlotState::Node("shard-node-9".to_string())), Err(ClusterError::UnknownNode("shard-node-9".to_string())));
+
+    map.set_slot(1, SlotState::Migrating("shard-node-3".to_string())).unwrap();
+    map.set_slot(1, SlotState::Stable).unwrap();
+    assert!(map.migrating(1).is_none());
+    assert!(map.is_local(1));
+}
+
+#[test]
+fn test_plan_rebalance() {
+    // Um nó novo, sem slots, recebe um terço do cluster
+    let nodes = vec![
+        ("shard-node-1".to_string(), ranges(&["0-8191"])),
+        ("shard-node-2".to_string(), ranges(&["8192-16383"])),
+        ("shard-node-3".to_string(), Vec::new()),
+    ];
+    let moves = plan_rebalance(&nodes);
+    assert_eq!(moves, vec![
+        SlotMove { range: SlotRange::new(5462, 8191).unwrap(), from: "shard-node-1".to_string(), to: "shard-node-3".to_string() },
+        SlotMove { range: SlotRange::new(13653, 16383).unwrap(), from: "shard-node-2".to_string(), to: "shard-node-3".to_string() },
+    ]);
+
+    // O nó novo fica com a sua cota
+    let moved = moves.iter().map(|slot_move| (slot_move.range.end - slot_move.range.start) as usize + 1).sum::<usize>();
+    assert_eq!(moved, 5461);
+
+    // Um cluster equilibrado não move nada
+    let balanced = vec![
+        ("shard-node-1".to_string(), ranges(&["0-5461"])),
+        ("shard-node-2".to_string(), ranges(&["5462-10922"])),
+        ("shard-node-3".to_string(), ranges(&["10923-16383"])),
+    ];
+    assert!(plan_rebalance(&balanced).is_empty());
+}
+
+#[test]
+fn test_dictionary_keys_in_slot() {
+    let mut dictionary = Dictionary::new();
+    for key in ["{pedido}:3", "{pedido}:1", "{pedido}:2", "casa"] {
+        dictionary.add_entry(key.to_string(), "valor".to_string());
+    }
+
+    let slot = key_slot("pedido");
+    assert_eq!(dictionary.count_keys_in_slot(slot), 3);
+    assert_eq!(dictionary.keys_in_slot(slot, 2), vec!["{pedido}:1".to_string(), "{pedido}:2".to_string()]);
+    assert_eq!(dictionary.keys_in_slot(key_slot("casa"), 10), vec!["casa".to_string()]);
+}
+
+fn replica(name: &str, port: u16, ping: f64, ready: bool) -> TopologyNode {
+    TopologyNode {
+        name: name.to_string(),
+        addr: format!("127.0.0.1:{}", port),
+        ping,
+        ready,
+        lag: ReplicationLag::default(),
+        lagging: false,
+    }
+}
+
+// Topologia dos três shards vista por um driver, com as réplicas do primeiro
+fn topology() -> Topology {
+    let slots = serde_json::to_string(&three_shards().assignments()).unwrap();
+    let mut topology = Topology::from_cluster_slots("127.0.0.1:13161", &slots).unwrap();
+    let role = NodeRole {
+        role: guaradict_core::replica::Role::Primary,
+        name: "shard-node-1".to_string(),
+        leader: None,
+        replicas: vec![
+            replica("replica-node-1", 13171, 4.0, true),
+            replica("replica-node-2", 13172, 1.5, true),
+            replica("replica-node-3", 13173, 0.5, false),
+        ],
+    };
+    let role = serde_json::from_str(&serde_json::to_string(&role).unwrap()).unwrap();
+    topology.set_role("127.0.0.1:13161", role);
+    topology
+}
+
+#[test]
+fn test_topology_routes_writes_to_owner() {
+    let mut topology = topology();
+    assert_eq!(topology.primaries(), vec!["127.0.0.1:13161", "127.0.0.1:13162", "127.0.0.1:13163"]);
+
+    let key = key_in_slot_of(6000);
+    assert_eq!(topology.route(&[key.as_str()], false, ReadPreference::Nearest).unwrap(), "127.0.0.1:13162");
+    // Sem réplicas, as leituras ficam com o primário
+    assert_eq!(topology.route(&[key.as_str()], true, ReadPreference::Replica).unwrap(), "127.0.0.1:13162");
+    assert!(topology.route(&[], false, ReadPreference::Primary).is_none());
+
+    // Fora do modo cluster, o nó inicial atende tudo
+    let mut single = Topology::from_cluster_slots("127.0.0.1:13141", CLUSTER_DISABLED).unwrap();
+    assert_eq!(single.route(&["casa"], false, ReadPreference::Primary).unwrap(), "127.0.0.1:13141");
+    assert!(Topology::from_cluster_slots("127.0.0.1:13141", "Invalid command").is_err());
+}
+
+#[test]
+fn test_topology_read_preference() {
+    let mut topology = topology();
+    let key = key_in_slot_of(100);
+    let mut read = |preference| topology.route(&[key.as_str()], true, preference).unwrap();
+
+    assert_eq!(read(ReadPreference::Primary), "127.0.0.1:13161");
+    // A réplica mais rápida que está pronta
+    assert_eq!(read(ReadPreference::Nearest), "127.0.0.1:13172");
+    // As leituras se revezam entre as réplicas prontas
+    let mut replicas = vec![read(ReadPreference::Replica), read(ReadPreference::Replica)];
+    replicas.sort();
+    assert_eq!(replicas, vec!["127.0.0.1:13171", "127.0.0.1:13172"]);
+
+    // Uma réplica atrasada demais deixa de receber leituras
+    let mut role = NodeRole {
+        role: guaradict_core::replica::Role::Primary,
+        name: "shard-node-1".to_string(),
+        leader: None,
+        replicas: vec![replica("replica-node-1", 13171, 4.0, true), replica("replica-node-2", 13172, 1.5, true)],
+    };
+    role.replicas[1].lag = ReplicationLag { operations: 500, bytes: 20000, seconds: 3.0 };
+    role.replicas[1].lagging = true;
+    topology.set_role("127.0.0.1:13161", role);
+    assert_eq!(topology.route(&[key.as_str()], true, ReadPreference::Nearest).unwrap(), "127.0.0.1:13171");
+    assert_eq!(topology.route(&[key.as_str()], true, ReadPreference::Replica).unwrap(), "127.0.0.1:13171");
+
+    assert_eq!("nearest".parse::<ReadPreference>(), Ok(ReadPreference::Nearest));
+    assert!("secondary".parse::<ReadPreference>().is_err());
+}
+
+#[test]
+fn test_topology_routes_script_cache_to_primary() {
+    let mut topology = topology();
+    let key = key_in_slot_of(100);
+    let hash = "e0e1f9fabfc9d4800c877a703b823ac0578ff8db".to_string();
+    // O cache de scripts do primário é o que o EVALSHA consulta depois
+    let commands = [
+        Command::ScriptLoad("get(\"casa\")".to_string()),
+        Command::ScriptExists(vec![hash.clone()]),
+        Command::ScriptFlush,
+        Command::EvalSha(hash, vec![key.clone()], Vec::new()),
+    ];
+    for command in &commands {
+        assert!(!command.is_read_only(), "{}", command.execute());
+        for preference in [ReadPreference::Replica, ReadPreference::Nearest] {
+            let target = topology.route(&command.keys(), command.is_read_only(), preference);
+            assert!(matches!(target.as_deref(), None | Some("127.0.0.1:13161")), "{} -> {:?}", command.execute(), target);
+        }
+    }
+
+    // Uma leitura da mesma chave ainda vai à réplica
+    assert_eq!(topology.route(&[key.as_str()], true, ReadPreference::Nearest).unwrap(), "127.0.0.1:13172");
+}
+
+#[test]
+fn test_topology_follows_moved() {
+    let mut topology = topology();
+    let key = key_in_slot_of(100);
+    assert!(!topology.is_stale(std::time::Duration::from_secs(30)));
+
+    let redirect = Redirect::parse("MOVED 100 127.0.0.1:13164").unwrap();
+    assert_eq!(redirect, Redirect::Moved { slot: 100, addr: "127.0.0.1:13164".to_string() });
+    topology.moved(100, "127.0.0.1:13164");
+
+    assert_eq!(topology.route(&[key.as_str()], false, ReadPreference::Primary).unwrap(), "127.0.0.1:13164");
+    assert!(topology.is_stale(std::time::Duration::from_secs(30)));
+    assert_eq!(Redirect::parse("ASK 100 127.0.0.1:13162"), Some(Redirect::Ask { slot: 100, addr: "127.0.0.1:13162".to_string() }));
+    assert!(Redirect::parse("MOVED para outro lugar").is_none());
+}
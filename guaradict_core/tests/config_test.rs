@@ -1,4 +1,7 @@
-use guaradict_core::config::parse_config_file;
+use std::time::Duration;
+use guaradict_core::cluster::SlotRange;
+use guaradict_core::config::{parse_config_file, validate_config, WriteConcern};
+use guaradict_core::replica::{Acks, ConflictResolution, Detector, MaxLag, MonitorSettings};
 
 #[test]
 fn test_parse_config_file_multi_primary_multi_replica() {
@@ -11,6 +14,7 @@ fn test_parse_config_file_multi_primary_multi_replica() {
     assert_eq!(config.port, 13141);
     assert!(config.database.is_none());
     assert!(config.replicas.is_some());
+    assert_eq!(config.multi_primary.unwrap().conflict_resolution, Some(ConflictResolution::VectorClock));
 
     let replicas = config.replicas.unwrap();
     assert_eq!(replicas.len(), 3);
@@ -71,9 +75,38 @@ fn test_parse_config_file_primary_multi_replica() {
     assert!(replica2.database.is_none());
 }
 
+#[test]
+fn test_parse_config_file_write_concern() {
+    let config = parse_config_file("tests/fixtures/primary-write-concern.yaml").unwrap();
+    assert_eq!(config.write_concern, Some(WriteConcern { acks: Acks::Majority, timeout: Some(500) }));
+    assert_eq!(config.replicas.unwrap().len(), 1);
+}
+
+#[test]
+fn test_parse_config_file_max_lag() {
+    let config = parse_config_file("tests/fixtures/primary-max-lag.yaml").unwrap();
+    assert_eq!(config.max_lag, Some(MaxLag { operations: Some(1000), bytes: None, seconds: Some(2.5) }));
+}
+
+#[test]
+fn test_parse_config_file_monitor() {
+    let config = parse_config_file("tests/fixtures/primary-monitor.yaml").unwrap();
+    // Campos ausentes do monitor ficam com os valores padrão
+    let settings = config.monitor.unwrap().settings();
+    assert_eq!(settings.interval, Duration::from_secs(2));
+    assert_eq!(settings.ping_timeout, Duration::from_millis(500));
+    assert_eq!(settings.connect_timeout, MonitorSettings::default().connect_timeout);
+    assert_eq!(settings.failures, 3);
+    assert_eq!(settings.detector, Detector::Phi);
+    assert_eq!(settings.phi_threshold, 10.0);
+    assert_eq!(settings.backoff, Duration::from_millis(500));
+    assert_eq!(settings.max_backoff, Duration::from_secs(10));
+}
+
 #[test]
 fn test_parse_config_file_primary_replica() {
     let config = parse_config_file("tests/fixtures/primary-replica.yaml").unwrap();
+    assert!(config.write_concern.is_none());
     assert_eq!(config.node_type, "primary");
     assert_eq!(config.name, "primary-node");
     assert_eq!(config.ip, "127.0.0.1");
@@ -105,3 +138,78 @@ fn test_parse_config_file_replica() {
     assert_eq!(config.database, Some(String::from("my-database")));
     assert!(config.replicas.is_none());
 }
+
+#[test]
+fn test_parse_config_file_replica_failover() {
+    let config = parse_config_file("tests/fixtures/replica-failover.yaml").unwrap();
+    assert_eq!(config.node_type, "replica");
+    assert_eq!(config.primary.unwrap().port, 13141);
+    assert_eq!(config.peers.unwrap()[0].name, "replica-node-2");
+    assert_eq!(config.failover.unwrap().timeout, Some(1500));
+}
+
+#[test]
+fn test_parse_config_file_raft() {
+    let config = parse_config_file("tests/fixtures/raft-node.yaml").unwrap();
+    assert_eq!(config.name, "raft-node-1");
+    assert_eq!(config.peers.unwrap().len(), 2);
+
+    let raft = config.raft.unwrap();
+    assert_eq!(raft.tick, Some(50));
+    assert_eq!(raft.election_ticks, Some(20));
+    assert_eq!(raft.heartbeat_ticks, None);
+}
+
+#[test]
+fn test_parse_config_file_cluster() {
+    let config = parse_config_file("tests/fixtures/cluster-node.yaml").unwrap();
+    assert_eq!(config.cluster_id.as_deref(), Some("guaradict-shards"));
+    let cluster = config.cluster.unwrap();
+    assert_eq!(cluster.slots, vec![SlotRange::new(0, 5460).unwrap()]);
+
+    let nodes = cluster.nodes.unwrap();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[1].name, "shard-node-3");
+    assert_eq!(nodes[1].slots[0].to_string(), "10923-16383");
+}
+
+#[test]
+fn test_config_bus_port() {
+    let config = parse_config_file("tests/fixtures/cluster-node.yaml").unwrap();
+    assert_eq!(config.bus_port(), 23161);
+
+    let nodes = config.cluster.unwrap().nodes.unwrap();
+    assert_eq!(nodes[0].bus_port(), 24162);
+    assert_eq!(nodes[1].bus_port(), 23163);
+}
+
+#[test]
+fn test_validate_bus_port() {
+    let node = |extra: &str| serde_yaml::from_str(&format!("{{nodeType: primary, name: n, ip: 127.0.0.1, host: localhost, {}}}", extra)).unwrap();
+    assert!(validate_config(&node("port: 13141")).is_ok());
+    assert!(validate_config(&node("port: 13141, busPort: 13141")).is_err());
+    // Sem busPort, a porta padrão do barramento passaria de 65535
+    assert!(validate_config(&node("port: 60000")).is_err());
+    assert!(validate_config(&node("port: 60000, busPort: 50000")).is_ok());
+}
+
+#[test]
+fn test_validate_max_lag() {
+    let node = |max_lag: &str| serde_yaml::from_str(&format!("{{nodeType: primary, name: n, ip: 127.0.0.1, host: localhost, port: 13141, maxLag: {}}}", max_lag)).unwrap();
+    assert!(validate_config(&node("{operations: 100, bytes: 4096, seconds: 0.5}")).is_ok());
+    assert!(validate_config(&node("{seconds: -1}")).is_err());
+    assert!(validate_config(&node("{versions: 10}")).is_err());
+}
+
+#[test]
+fn test_validate_monitor() {
+    let node = |monitor: &str| serde_yaml::from_str(&format!("{{nodeType: primary, name: n, ip: 127.0.0.1, host: localhost, port: 13141, monitor: {}}}", monitor)).unwrap();
+    assert!(validate_config(&node("{interval: 1000, failures: 5, detector: fixed}")).is_ok());
+    assert!(validate_config(&node("{detector: phi, phiThreshold: 12.5}")).is_ok());
+    assert!(validate_config(&node("{pingTimeout: 0}")).is_err());
+    assert!(validate_config(&node("{detector: gossip}")).is_err());
+    assert!(validate_config(&node("{phiThreshold: -1}")).is_err());
+    assert!(validate_config(&node("{backoff: 200, maxBackoff: 5000}")).is_ok());
+    assert!(validate_config(&node("{maxBackoff: 0}")).is_err());
+    assert!(validate_config(&node("{retries: 3}")).is_err());
+}
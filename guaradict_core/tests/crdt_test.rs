@@ -0,0 +1,173 @@
+use guaradict_core::replica::{ConflictResolution, ConflictResolver, HlcTimestamp, Operation, ReplicationMessage};
+use guaradict_core::errors::DictionaryError;
+use guaradict_core::{Crdt, Dictionary, LwwRegister, OrSet, PnCounter};
+
+fn node(name: &str) -> Dictionary {
+    let mut dictionary = Dictionary::new();
+    dictionary.set_node(name);
+    dictionary
+}
+
+// Operações pendentes do dicionário, depois de passar pela serialização
+fn take(dictionary: &mut Dictionary) -> Vec<Operation> {
+    let line = ReplicationMessage::Batch { operations: dictionary.take_operations() }.to_line();
+    let ReplicationMessage::Batch { operations } = ReplicationMessage::from_line(&line).unwrap() else {
+        panic!("Mensagem inesperada");
+    };
+    operations
+}
+
+fn value(dictionary: &Dictionary, key: &str) -> serde_json::Value {
+    dictionary.crdt_get(key).unwrap().unwrap().to_json()
+}
+
+fn dot(wall: u64, node: &str) -> HlcTimestamp {
+    HlcTimestamp { wall, logical: 0, node: node.to_string() }
+}
+
+#[test]
+fn test_counter_merges_concurrent_increments() {
+    let mut counter1 = PnCounter::default();
+    let mut counter2 = PnCounter::default();
+
+    let delta1 = counter1.increment("node-1", 5);
+    let delta2 = counter2.increment("node-2", 3);
+    let delta3 = counter2.increment("node-2", -1);
+
+    assert!(counter1.merge(&delta2));
+    assert!(counter1.merge(&delta3));
+    // Deltas fora de ordem e repetidos não mudam o resultado
+    assert!(counter2.merge(&delta1));
+    assert!(!counter2.merge(&delta1));
+    assert!(!counter1.merge(&delta2));
+
+    assert_eq!(counter1.value(), 7);
+    assert_eq!(counter1, counter2);
+}
+
+#[test]
+fn test_set_add_wins_over_concurrent_remove() {
+    let mut set1 = OrSet::default();
+    let add = set1.add("azul", dot(1, "node-1"));
+    let mut set2 = OrSet::default();
+    set2.merge(&add);
+
+    // O nó 2 remove a adição que viu enquanto o nó 1 adiciona de novo
+    let remove = set2.remove("azul").unwrap();
+    let add_again = set1.add("azul", dot(2, "node-1"));
+
+    set1.merge(&remove);
+    set2.merge(&add_again);
+    assert!(set1.contains("azul"));
+    assert_eq!(set1, set2);
+
+    // Uma adição já removida não volta
+    let mut set3 = OrSet::default();
+    set3.merge(&remove);
+    set3.merge(&add);
+    assert!(set3.is_empty());
+    assert!(set2.remove("verde").is_none());
+}
+
+#[test]
+fn test_register_keeps_latest_write() {
+    let mut register1 = LwwRegister::default();
+    let mut register2 = LwwRegister::default();
+
+    let older = register1.set("moradia".to_string(), dot(1, "node-1"));
+    let newer = register2.set("lar".to_string(), dot(1, "node-2"));
+
+    assert!(register1.merge(&newer));
+    assert!(!register2.merge(&older));
+    assert_eq!(register1.value().map(String::as_str), Some("lar"));
+    assert_eq!(register1, register2);
+}
+
+#[test]
+fn test_dictionary_crdt_commands() {
+    let mut dictionary = node("node-1");
+
+    assert_eq!(dictionary.crdt_increment("visitas", 4).unwrap(), 4);
+    assert_eq!(dictionary.crdt_increment("visitas", -1).unwrap(), 3);
+    assert_eq!(dictionary.crdt_set_add("cores", vec!["azul".to_string(), "verde".to_string()]).unwrap(), 2);
+    assert_eq!(dictionary.crdt_set_add("cores", vec!["azul".to_string()]).unwrap(), 0);
+    assert_eq!(dictionary.crdt_set_remove("cores", vec!["azul".to_string(), "roxo".to_string()]).unwrap(), 1);
+    dictionary.crdt_register_set("titulo", "Dicionário".to_string()).unwrap();
+    dictionary.crdt_map_set("perfil", "nome", "Ana".to_string()).unwrap();
+    dictionary.crdt_map_set("perfil", "cidade", "Recife".to_string()).unwrap();
+    assert!(dictionary.crdt_map_delete("perfil", "cidade").unwrap());
+    assert!(!dictionary.crdt_map_delete("perfil", "cidade").unwrap());
+
+    assert_eq!(value(&dictionary, "visitas"), serde_json::json!(3));
+    assert_eq!(value(&dictionary, "cores"), serde_json::json!(["verde"]));
+    assert_eq!(value(&dictionary, "titulo"), serde_json::json!("Dicionário"));
+    assert_eq!(value(&dictionary, "perfil"), serde_json::json!({ "nome": "Ana" }));
+    assert_eq!(dictionary.crdt_get("perfil").unwrap().unwrap().type_name(), "ormap");
+    assert!(dictionary.crdt_get("ausente").unwrap().is_none());
+
+    // Cada escrita gera um delta e uma nova versão
+    let operations = take(&mut dictionary);
+    assert_eq!(operations.len(), 9);
+    assert_eq!(operations.last().unwrap().version, dictionary.revision());
+
+    // O tipo de uma chave não muda
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+    assert!(matches!(dictionary.crdt_increment("casa", 1), Err(DictionaryError::WrongType(_))));
+    assert!(matches!(dictionary.crdt_set_add("visitas", vec!["a".to_string()]), Err(DictionaryError::WrongType(_))));
+    assert!(matches!(dictionary.crdt_map_delete("cores", "azul"), Err(DictionaryError::WrongType(_))));
+}
+
+#[test]
+fn test_deltas_converge_in_any_order() {
+    let mut node1 = node("node-1");
+    let mut node2 = node("node-2");
+
+    node1.crdt_increment("visitas", 2).unwrap();
+    node1.crdt_set_add("cores", vec!["azul".to_string()]).unwrap();
+    node1.crdt_map_set("perfil", "nome", "Ana".to_string()).unwrap();
+    let from1 = take(&mut node1);
+
+    node2.crdt_increment("visitas", 5).unwrap();
+    node2.crdt_set_add("cores", vec!["verde".to_string()]).unwrap();
+    node2.crdt_map_set("perfil", "cidade", "Recife".to_string()).unwrap();
+    let from2 = take(&mut node2);
+
+    node1.replay(from2.iter().rev());
+    node2.replay(&from1);
+    // Aplicar de novo não muda nada
+    node2.replay(&from1);
+
+    for key in ["visitas", "cores", "perfil"] {
+        assert_eq!(node1.crdt_get(key).unwrap(), node2.crdt_get(key).unwrap());
+    }
+    assert_eq!(value(&node1, "visitas"), serde_json::json!(7));
+    assert_eq!(value(&node1, "cores"), serde_json::json!(["azul", "verde"]));
+    assert_eq!(value(&node1, "perfil"), serde_json::json!({ "cidade": "Recife", "nome": "Ana" }));
+}
+
+#[test]
+fn test_multi_primary_keeps_concurrent_increments() {
+    let mut node1 = node("primary-node-1");
+    let mut node2 = node("primary-node-2");
+    let mut resolver1 = ConflictResolver::new("primary-node-1", ConflictResolution::LastWriterWins);
+    let mut resolver2 = ConflictResolver::new("primary-node-2", ConflictResolution::LastWriterWins);
+
+    node1.crdt_increment("visitas", 1).unwrap();
+    let mut write1 = take(&mut node1);
+    resolver1.stamp(&mut write1, &node1);
+    node2.crdt_increment("visitas", 1).unwrap();
+    let mut write2 = take(&mut node2);
+    resolver2.stamp(&mut write2, &node2);
+    assert!(write1[0].stamp.is_none());
+
+    // Nenhum incremento se perde, e o reenvio não volta a circular
+    let forwarded1 = resolver1.merge(&write2, &mut node1);
+    let forwarded2 = resolver2.merge(&write1, &mut node2);
+    assert_eq!(forwarded1.len(), 1);
+    assert!(resolver2.merge(&forwarded1, &mut node2).is_empty());
+    assert!(resolver1.merge(&forwarded2, &mut node1).is_empty());
+
+    assert_eq!(value(&node1, "visitas"), serde_json::json!(2));
+    assert_eq!(node1.crdt_get("visitas").unwrap(), node2.crdt_get("visitas").unwrap());
+    assert!(matches!(node1.crdt_get("visitas").unwrap(), Some(Crdt::Counter(_))));
+}
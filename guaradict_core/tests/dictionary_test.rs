@@ -1,4 +1,6 @@
-use guaradict_core::Dictionary;
+use guaradict_core::{Dictionary, ListSide, Sense, Value};
+use guaradict_core::errors::DictionaryError;
+use guaradict_core::replica::{LogOperator, OperationKind, OperationValue};
 
 #[test]
 fn test_add_entry() {
@@ -22,3 +24,399 @@ fn test_remove_entry() {
     dictionary.remove_entry("hello");
     assert_eq!(dictionary.len(), 0);
 }
+
+#[test]
+fn test_search_requires_index() {
+    let dictionary = Dictionary::new();
+    assert_eq!(dictionary.search("rio"), Err(DictionaryError::SearchIndexDisabled));
+}
+
+#[test]
+fn test_search_follows_mutations() {
+    let mut dictionary = Dictionary::with_search_index();
+    dictionary.add_entry("riacho".to_string(), "pequeno rio".to_string());
+    dictionary.add_entry("mar".to_string(), "grande massa de água salgada".to_string());
+
+    let hits = dictionary.search("rio").unwrap();
+    assert_eq!(hits[0].word, "riacho");
+
+    dictionary.add_entry("riacho".to_string(), "córrego".to_string());
+    assert!(dictionary.search("rio").unwrap().is_empty());
+    assert_eq!(dictionary.search("corrego").unwrap()[0].word, "riacho");
+
+    dictionary.remove_entry("riacho");
+    assert!(dictionary.search("corrego").unwrap().is_empty());
+}
+
+#[test]
+fn test_replay_rebuilds_index() {
+    let mut log_operator = LogOperator::new();
+    log_operator.insert("rio", "curso de água");
+    log_operator.insert("mar", "água salgada");
+    log_operator.update("rio", "corrente de água doce", None);
+    log_operator.delete("mar");
+
+    let mut dictionary = Dictionary::with_search_index();
+    dictionary.replay(&log_operator.operations);
+
+    assert_eq!(dictionary.len(), 1);
+    assert_eq!(dictionary.get_definition("rio").unwrap(), "corrente de água doce");
+    let hits = dictionary.search("agua").unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].word, "rio");
+}
+
+#[test]
+fn test_add_sense_converts_text_entry() {
+    let mut dictionary = Dictionary::new();
+    dictionary.add_entry("manga".to_string(), "fruta da mangueira".to_string());
+
+    let mut sense = Sense::new("parte do vestuário que cobre o braço".to_string());
+    sense.part_of_speech = Some("substantivo".to_string());
+    sense.synonyms = vec!["braçal".to_string()];
+    assert_eq!(dictionary.add_sense("manga", sense), Ok(2));
+
+    let entry = dictionary.get_entry("manga").unwrap().unwrap();
+    assert_eq!(entry.senses.len(), 2);
+    assert_eq!(entry.senses[0].definition, "fruta da mangueira");
+    assert_eq!(entry.senses[1].part_of_speech, Some("substantivo".to_string()));
+    assert_eq!(dictionary.get_definition("manga").unwrap(), "fruta da mangueira");
+}
+
+#[test]
+fn test_remove_sense() {
+    let mut dictionary = Dictionary::new();
+    dictionary.add_sense("banco", Sense::new("assento".to_string())).unwrap();
+    dictionary.add_sense("banco", Sense::new("instituição financeira".to_string())).unwrap();
+
+    assert_eq!(dictionary.remove_sense("banco", 5), Err(DictionaryError::SenseNotFound(5)));
+    assert_eq!(dictionary.remove_sense("banco", 0), Ok(1));
+    assert_eq!(dictionary.get_definition("banco").unwrap(), "instituição financeira");
+    assert_eq!(dictionary.remove_sense("banco", 0), Ok(0));
+    assert!(dictionary.get("banco").is_none());
+    assert_eq!(dictionary.remove_sense("banco", 0), Err(DictionaryError::KeyNotFound("banco".to_string())));
+}
+
+#[test]
+fn test_entry_round_trips_through_journal() {
+    let mut primary = Dictionary::new();
+    let mut sense = Sense::new("curso de água natural".to_string());
+    sense.examples = vec!["o rio transbordou".to_string()];
+    sense.language = Some("pt-BR".to_string());
+    primary.add_sense("rio", sense).unwrap();
+    primary.add_sense("rio", Sense::new("grande quantidade".to_string())).unwrap();
+    primary.add_entry("mar".to_string(), "água salgada".to_string());
+    primary.remove_entry("mar");
+
+    let operations = primary.take_operations();
+    assert_eq!(operations.len(), 4);
+    assert!(primary.take_operations().is_empty());
+
+    let mut replica = Dictionary::with_search_index();
+    replica.replay(&operations);
+    assert_eq!(replica.entries, primary.entries);
+    assert_eq!(replica.search("transbordou").unwrap()[0].word, "rio");
+}
+
+#[test]
+fn test_map_fields() {
+    let mut dictionary = Dictionary::new();
+    assert_eq!(dictionary.map_set("casa", "cor", "azul".to_string()), Ok(true));
+    assert_eq!(dictionary.map_set("casa", "cor", "verde".to_string()), Ok(false));
+    assert_eq!(dictionary.map_set("casa", "andares", "2".to_string()), Ok(true));
+
+    assert_eq!(dictionary.map_get("casa", "cor").unwrap().unwrap(), "verde");
+    assert_eq!(dictionary.map_get("casa", "porta"), Ok(None));
+    assert_eq!(dictionary.map_get_all("casa").unwrap().unwrap().len(), 2);
+
+    assert_eq!(dictionary.map_delete("casa", "cor"), Ok(true));
+    assert_eq!(dictionary.map_delete("casa", "cor"), Ok(false));
+    assert_eq!(dictionary.map_delete("casa", "andares"), Ok(true));
+    assert!(dictionary.get("casa").is_none());
+}
+
+#[test]
+fn test_map_increment() {
+    let mut dictionary = Dictionary::new();
+    assert_eq!(dictionary.map_increment("contador", "visitas", 5), Ok(5));
+    assert_eq!(dictionary.map_increment("contador", "visitas", -2), Ok(3));
+
+    dictionary.map_set("contador", "nome", "casa".to_string()).unwrap();
+    assert_eq!(
+        dictionary.map_increment("contador", "nome", 1),
+        Err(DictionaryError::NotAnInteger("nome".to_string()))
+    );
+}
+
+#[test]
+fn test_map_wrong_type() {
+    let mut dictionary = Dictionary::new();
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+
+    let error = Err(DictionaryError::WrongType("casa".to_string()));
+    assert_eq!(dictionary.map_set("casa", "cor", "azul".to_string()), error);
+    assert_eq!(dictionary.map_delete("casa", "cor"), Err(DictionaryError::WrongType("casa".to_string())));
+
+    dictionary.map_set("quarto", "cor", "azul".to_string()).unwrap();
+    assert_eq!(
+        dictionary.add_sense("quarto", Sense::new("cômodo".to_string())),
+        Err(DictionaryError::WrongType("quarto".to_string()))
+    );
+}
+
+#[test]
+fn test_map_replicates_only_changed_field() {
+    let mut primary = Dictionary::new();
+    primary.map_set("casa", "cor", "azul".to_string()).unwrap();
+    primary.map_set("casa", "andares", "2".to_string()).unwrap();
+    primary.take_operations();
+
+    primary.map_increment("casa", "andares", 1).unwrap();
+    primary.map_delete("casa", "cor").unwrap();
+    let operations = primary.take_operations();
+
+    assert_eq!(operations.len(), 2);
+    assert_eq!(operations[0].kind, OperationKind::MapSet);
+    assert_eq!(operations[0].field, Some("andares".into()));
+    assert_eq!(operations[0].current_value, Some("3".into()));
+    assert_eq!(operations[1].kind, OperationKind::MapDelete);
+    assert_eq!(operations[1].field, Some("cor".into()));
+
+    let mut replica = Dictionary::new();
+    replica.map_set("casa", "cor", "azul".to_string()).unwrap();
+    replica.map_set("casa", "andares", "2".to_string()).unwrap();
+    replica.replay(&operations);
+    assert_eq!(replica.entries, primary.entries);
+}
+
+#[test]
+fn test_list_push_and_range() {
+    let mut dictionary = Dictionary::new();
+    let values = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<String>>();
+
+    assert_eq!(dictionary.list_push("fila", ListSide::Right, values(&["b", "c"])), Ok(2));
+    assert_eq!(dictionary.list_push("fila", ListSide::Left, values(&["a", "z"])), Ok(4));
+
+    assert_eq!(dictionary.list_range("fila", 0, -1).unwrap(), values(&["z", "a", "b", "c"]));
+    assert_eq!(dictionary.list_range("fila", 1, 2).unwrap(), values(&["a", "b"]));
+    assert_eq!(dictionary.list_range("fila", -2, 100).unwrap(), values(&["b", "c"]));
+    assert!(dictionary.list_range("fila", 3, 1).unwrap().is_empty());
+    assert!(dictionary.list_range("vazia", 0, -1).unwrap().is_empty());
+    assert_eq!(dictionary.list_len("fila"), Ok(4));
+}
+
+#[test]
+fn test_list_pop() {
+    let mut dictionary = Dictionary::new();
+    dictionary.list_push("fila", ListSide::Right, vec!["a".to_string(), "b".to_string()]).unwrap();
+
+    assert_eq!(dictionary.list_pop("fila", ListSide::Right), Ok(Some("b".to_string())));
+    assert_eq!(dictionary.list_pop("fila", ListSide::Left), Ok(Some("a".to_string())));
+    assert_eq!(dictionary.list_pop("fila", ListSide::Left), Ok(None));
+    assert!(dictionary.get("fila").is_none());
+
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+    assert_eq!(dictionary.list_pop("casa", ListSide::Left), Err(DictionaryError::WrongType("casa".to_string())));
+}
+
+#[test]
+fn test_list_pops_replicate_deterministically() {
+    let mut primary = Dictionary::new();
+    primary.list_push("fila", ListSide::Right, vec!["a".to_string(), "b".to_string(), "c".to_string()]).unwrap();
+    primary.list_pop("fila", ListSide::Left).unwrap();
+    primary.list_push("fila", ListSide::Left, vec!["x".to_string()]).unwrap();
+    primary.list_pop("fila", ListSide::Right).unwrap();
+
+    let operations = primary.take_operations();
+    assert_eq!(operations[1].kind, OperationKind::ListPopLeft);
+    assert_eq!(operations[1].prev_value, Some("a".into()));
+
+    let mut replica = Dictionary::new();
+    replica.replay(&operations);
+    assert_eq!(replica.entries, primary.entries);
+    assert_eq!(replica.list_range("fila", 0, -1).unwrap(), vec!["x".to_string(), "b".to_string()]);
+}
+
+fn members(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn test_set_membership() {
+    let mut dictionary = Dictionary::new();
+    assert_eq!(dictionary.set_add("tags", members(&["rio", "mar", "rio"])), Ok(2));
+    assert_eq!(dictionary.set_add("tags", members(&["mar", "lago"])), Ok(1));
+    assert_eq!(dictionary.set_len("tags"), Ok(3));
+    assert_eq!(dictionary.set_is_member("tags", "lago"), Ok(true));
+    assert_eq!(dictionary.set_is_member("tags", "serra"), Ok(false));
+
+    assert_eq!(dictionary.set_remove("tags", members(&["lago", "serra"])), Ok(1));
+    assert_eq!(dictionary.set_remove("tags", members(&["rio", "mar"])), Ok(2));
+    assert!(dictionary.get("tags").is_none());
+
+    dictionary.list_push("fila", ListSide::Left, members(&["a"])).unwrap();
+    assert_eq!(dictionary.set_add("fila", members(&["a"])), Err(DictionaryError::WrongType("fila".to_string())));
+}
+
+#[test]
+fn test_set_algebra() {
+    let mut dictionary = Dictionary::new();
+    dictionary.set_add("a", members(&["rio", "mar", "lago"])).unwrap();
+    dictionary.set_add("b", members(&["mar", "serra"])).unwrap();
+    dictionary.set_add("c", members(&["mar", "lago"])).unwrap();
+
+    let sorted = |set: std::collections::HashSet<String>| {
+        let mut values = set.into_iter().collect::<Vec<String>>();
+        values.sort();
+        values
+    };
+
+    let keys = members(&["a", "b", "c"]);
+    assert_eq!(sorted(dictionary.set_union(&keys).unwrap()), members(&["lago", "mar", "rio", "serra"]));
+    assert_eq!(sorted(dictionary.set_intersection(&keys).unwrap()), members(&["mar"]));
+    assert_eq!(sorted(dictionary.set_difference(&keys).unwrap()), members(&["rio"]));
+    assert!(dictionary.set_intersection(&members(&["a", "inexistente"])).unwrap().is_empty());
+}
+
+#[test]
+fn test_set_operations_replicate() {
+    let mut primary = Dictionary::new();
+    primary.set_add("tags", members(&["rio", "mar"])).unwrap();
+    primary.set_add("tags", members(&["mar"])).unwrap();
+    primary.set_remove("tags", members(&["rio"])).unwrap();
+
+    let operations = primary.take_operations();
+    assert_eq!(operations.len(), 2);
+    assert_eq!(operations[0].kind, OperationKind::SetAdd);
+    assert_eq!(operations[1].kind, OperationKind::SetRemove);
+    assert_eq!(operations[1].prev_value, Some(vec!["rio"].into()));
+
+    let mut replica = Dictionary::new();
+    replica.replay(&operations);
+    assert_eq!(replica.entries, primary.entries);
+}
+
+#[test]
+fn test_restored_set_replays_as_set() {
+    let mut primary = Dictionary::new();
+    let tags = Value::Set(members(&["rio", "mar"]).into_iter().collect());
+    primary.restore("tags", Some(tags.clone()));
+
+    let mut replica = Dictionary::new();
+    replica.replay(&primary.take_operations());
+    assert_eq!(replica.get("tags"), Some(&tags));
+    assert_eq!(replica.set_add("tags", members(&["lago"])), Ok(1));
+}
+
+#[test]
+fn test_values_round_trip_through_operations() {
+    let mut dictionary = Dictionary::new();
+    dictionary.add_entry("texto".to_string(), "lar".to_string());
+    dictionary.add_sense("verbete", Sense::new("curso de água natural".to_string())).unwrap();
+    dictionary.map_set("mapa", "cor", "azul".to_string()).unwrap();
+    dictionary.list_push("lista", ListSide::Right, members(&["a", "b"])).unwrap();
+    dictionary.set_add("conjunto", members(&["rio", "mar"])).unwrap();
+    dictionary.crdt_increment("contador", 3).unwrap();
+    dictionary.crdt_set_add("crdt-conjunto", members(&["rio"])).unwrap();
+    dictionary.crdt_register_set("registro", "lar".to_string()).unwrap();
+    dictionary.crdt_map_set("crdt-mapa", "cor", "azul".to_string()).unwrap();
+
+    for (key, value) in &dictionary.entries {
+        let operation = OperationValue::from(value);
+        assert_eq!(Value::try_from(&operation).as_ref(), Ok(value), "{}", key);
+    }
+    let types = dictionary.entries.values().map(Value::type_name).collect::<std::collections::HashSet<&str>>();
+    assert_eq!(types.len(), dictionary.len());
+}
+
+#[test]
+fn test_versions_increase_on_every_mutation() {
+    let mut dictionary = Dictionary::new();
+    assert_eq!(dictionary.version("casa"), 0);
+
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+    let first = dictionary.version("casa");
+    assert!(first > 0);
+
+    dictionary.map_set("quarto", "cor", "azul".to_string()).unwrap();
+    assert_eq!(dictionary.version("casa"), first);
+
+    dictionary.add_sense("casa", Sense::new("família".to_string())).unwrap();
+    let second = dictionary.version("casa");
+    assert!(second > first);
+
+    dictionary.remove_entry("casa");
+    assert_eq!(dictionary.version("casa"), 0);
+    dictionary.add_entry("casa".to_string(), "lar".to_string());
+    assert!(dictionary.version("casa") > second);
+}
+
+#[test]
+fn test_compare_and_set() {
+    let mut dictionary = Dictionary::new();
+    let version = dictionary.compare_and_set("casa", 0, "lugar de morar".to_string()).unwrap();
+    assert_eq!(version, dictionary.version("casa"));
+
+    assert_eq!(
+        dictionary.compare_and_set("casa", 0, "lar".to_string()),
+        Err(DictionaryError::VersionConflict { expected: 0, current: version })
+    );
+    assert_eq!(dictionary.get_definition("casa").unwrap(), "lugar de morar");
+
+    let next = dictionary.compare_and_set("casa", version, "lar".to_string()).unwrap();
+    assert!(next > version);
+    assert_eq!(dictionary.get_definition("casa").unwrap(), "lar");
+}
+
+#[test]
+fn test_versions_persist_through_journal() {
+    let mut primary = Dictionary::new();
+    primary.add_entry("casa".to_string(), "lugar de morar".to_string());
+    primary.list_push("fila", ListSide::Right, vec!["a".to_string()]).unwrap();
+    primary.add_entry("casa".to_string(), "lar".to_string());
+
+    let operations = primary.take_operations();
+    assert_eq!(operations.last().unwrap().version, primary.version("casa"));
+
+    let mut replica = Dictionary::new();
+    replica.replay(&operations);
+    assert_eq!(replica.version("casa"), primary.version("casa"));
+    assert_eq!(replica.version("fila"), primary.version("fila"));
+    assert_eq!(replica.revision(), primary.revision());
+}
+
+#[test]
+fn test_get_definitions() {
+    let mut dictionary = Dictionary::new();
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+
+    let definitions = dictionary.get_definitions(&["casa".to_string(), "rio".to_string()]);
+    assert_eq!(definitions, vec![Some(&"lugar de morar".to_string()), None]);
+}
+
+#[test]
+fn test_add_entries_records_one_operation_per_key() {
+    let mut dictionary = Dictionary::new();
+    dictionary.add_entries(vec![
+        ("casa".to_string(), "lugar de morar".to_string()),
+        ("rio".to_string(), "curso de água".to_string()),
+    ]);
+
+    assert_eq!(dictionary.get_definition("rio").unwrap(), "curso de água");
+    let operations = dictionary.take_operations();
+    assert_eq!(operations.len(), 2);
+    assert!(operations.iter().all(|operation| operation.kind == OperationKind::Insert));
+}
+
+#[test]
+fn test_remove_entries() {
+    let mut dictionary = Dictionary::new();
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+    dictionary.add_entry("rio".to_string(), "curso de água".to_string());
+    dictionary.take_operations();
+
+    let removed = dictionary.remove_entries(&["casa".to_string(), "mar".to_string(), "rio".to_string()]);
+    assert_eq!(removed, 2);
+    assert!(dictionary.is_empty());
+    assert_eq!(dictionary.take_operations().len(), 2);
+}
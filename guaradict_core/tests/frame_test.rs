@@ -0,0 +1,35 @@
+use std::io::Cursor;
+use tokio::io::AsyncWriteExt;
+use guaradict_core::commands::frame;
+
+#[tokio::test]
+async fn test_frames_larger_than_a_read() {
+    let (mut client, mut server) = tokio::io::duplex(1024);
+    let large = format!("{}\nsegunda linha", "definição ".repeat(600));
+    assert!(large.len() > 4096);
+
+    tokio::spawn(async move {
+        for payload in [large.as_str(), "", "OK"] {
+            server.write_all(frame::encode(payload).as_bytes()).await.unwrap();
+        }
+    });
+
+    // Cada resposta chega inteira e a seguinte não se mistura com ela
+    let first = frame::read(&mut client).await.unwrap();
+    assert!(first.ends_with("\nsegunda linha"));
+    assert_eq!(first.len(), "definição ".len() * 600 + "\nsegunda linha".len());
+    assert_eq!(frame::read(&mut client).await.unwrap(), "");
+    assert_eq!(frame::read(&mut client).await.unwrap(), "OK");
+    assert!(frame::read(&mut client).await.is_err());
+}
+
+#[test]
+fn test_read_blocking() {
+    let mut reader = Cursor::new(format!("{}{}", frame::encode("ação"), frame::encode("[1,2]")));
+    assert_eq!(frame::read_blocking(&mut reader).unwrap(), "ação");
+    assert_eq!(frame::read_blocking(&mut reader).unwrap(), "[1,2]");
+
+    assert!(frame::read_blocking(&mut Cursor::new("OK")).is_err());
+    assert!(frame::read_blocking(&mut Cursor::new("$abc\n")).is_err());
+    assert!(frame::read_blocking(&mut Cursor::new(format!("${}\n", frame::MAX_FRAME + 1))).is_err());
+}
@@ -0,0 +1,23 @@
+use guaradict_core::metrics::Metrics;
+
+#[test]
+fn test_metrics_render() {
+    let mut metrics = Metrics::new();
+    metrics
+        .gauge("guaradict_replication_offset", "Versão da última operação do log")
+        .sample("guaradict_replication_offset", &[], 42.0)
+        .gauge("guaradict_replica_lag_seconds", "Idade da operação mais antiga não aplicada")
+        .sample("guaradict_replica_lag_seconds", &[("replica", "replica-node-1")], 0.5)
+        .sample("guaradict_replica_lag_seconds", &[("replica", "nó \"2\"")], 0.0);
+
+    assert_eq!(
+        metrics.render(),
+        "# HELP guaradict_replication_offset Versão da última operação do log\n\
+         # TYPE guaradict_replication_offset gauge\n\
+         guaradict_replication_offset 42\n\
+         # HELP guaradict_replica_lag_seconds Idade da operação mais antiga não aplicada\n\
+         # TYPE guaradict_replica_lag_seconds gauge\n\
+         guaradict_replica_lag_seconds{replica=\"replica-node-1\"} 0.5\n\
+         guaradict_replica_lag_seconds{replica=\"nó \\\"2\\\"\"} 0\n"
+    );
+}
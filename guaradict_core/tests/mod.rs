@@ -1,3 +1,12 @@
 pub mod replica;
 pub mod config_test;
 pub mod dictionary_test;
+pub mod search_test;
+pub mod transaction_test;
+pub mod pubsub_test;
+pub mod script_test;
+pub mod raft_test;
+pub mod crdt_test;
+pub mod cluster_test;
+pub mod metrics_test;
+pub mod frame_test;
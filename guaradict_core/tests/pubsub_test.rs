@@ -0,0 +1,139 @@
+use guaradict_core::{pattern_matches, PubSub, PubSubMessage};
+use guaradict_core::replica::OperationKind;
+use tokio::sync::mpsc;
+
+#[test]
+fn test_publish_to_channel_subscribers() {
+    let mut pubsub = PubSub::new();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let subscriber = pubsub.register(tx);
+
+    let confirmations = pubsub.subscribe(subscriber, vec!["noticias".to_string()]);
+    assert_eq!(confirmations, vec![PubSubMessage::Subscribe { channel: "noticias".to_string(), count: 1 }]);
+
+    assert_eq!(pubsub.publish("noticias", "nova palavra"), 1);
+    assert_eq!(pubsub.publish("alertas", "ignorada"), 0);
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        PubSubMessage::Message { channel: "noticias".to_string(), data: "nova palavra".to_string() }
+    );
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn test_publish_to_pattern_subscribers() {
+    let mut pubsub = PubSub::new();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let subscriber = pubsub.register(tx);
+    pubsub.psubscribe(subscriber, vec!["noticias.*".to_string()]);
+
+    assert_eq!(pubsub.publish("noticias.rio", "cheia"), 1);
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        PubSubMessage::PMessage {
+            pattern: "noticias.*".to_string(),
+            channel: "noticias.rio".to_string(),
+            data: "cheia".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_unsubscribe_all() {
+    let mut pubsub = PubSub::new();
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let subscriber = pubsub.register(tx);
+    pubsub.subscribe(subscriber, vec!["a".to_string(), "b".to_string()]);
+    pubsub.psubscribe(subscriber, vec!["c*".to_string()]);
+    assert_eq!(pubsub.subscription_count(subscriber), 3);
+
+    let confirmations = pubsub.unsubscribe(subscriber, Vec::new());
+    assert_eq!(confirmations.len(), 2);
+    assert_eq!(confirmations[1], PubSubMessage::Unsubscribe { channel: "b".to_string(), count: 1 });
+    assert_eq!(pubsub.subscription_count(subscriber), 1);
+    assert_eq!(pubsub.publish("a", "x"), 0);
+}
+
+#[test]
+fn test_unregister_stops_delivery() {
+    let mut pubsub = PubSub::new();
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let subscriber = pubsub.register(tx);
+    pubsub.subscribe(subscriber, vec!["noticias".to_string()]);
+    pubsub.unregister(subscriber);
+
+    assert_eq!(pubsub.publish("noticias", "x"), 0);
+    assert_eq!(pubsub.subscription_count(subscriber), 0);
+}
+
+#[test]
+fn test_message_line() {
+    let message = PubSubMessage::Message { channel: "noticias".to_string(), data: "oi".to_string() };
+    assert_eq!(message.to_line(), "{\"type\":\"message\",\"channel\":\"noticias\",\"data\":\"oi\"}\n");
+}
+
+#[test]
+fn test_pattern_matches() {
+    assert!(pattern_matches("*", "qualquer"));
+    assert!(pattern_matches("noticias.*", "noticias.rio"));
+    assert!(pattern_matches("n?ticias", "noticias"));
+    assert!(pattern_matches("*.rio.*", "noticias.rio.cheia"));
+    assert!(pattern_matches("a\\*", "a*"));
+    assert!(!pattern_matches("a\\*", "ab"));
+    assert!(!pattern_matches("noticias.*", "alertas.rio"));
+    assert!(!pattern_matches("n?", "n"));
+}
+
+#[test]
+fn test_notify_change_to_key_watchers() {
+    let mut pubsub = PubSub::new();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let subscriber = pubsub.register(tx);
+    assert!(!pubsub.has_watchers());
+
+    let confirmations = pubsub.watch_keys(subscriber, vec!["casa".to_string()]);
+    assert_eq!(confirmations, vec![PubSubMessage::WatchKey { key: "casa".to_string(), count: 1 }]);
+    assert!(pubsub.has_watchers());
+
+    assert_eq!(pubsub.notify_change(OperationKind::Insert, "casa", "lugar de morar".into(), 1), 1);
+    assert_eq!(pubsub.notify_change(OperationKind::Insert, "casarao", "casa grande".into(), 2), 0);
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        PubSubMessage::Change {
+            kind: OperationKind::Insert,
+            key: "casa".to_string(),
+            value: "lugar de morar".into(),
+            version: 1,
+        }
+    );
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn test_notify_change_to_prefix_watchers_once() {
+    let mut pubsub = PubSub::new();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let subscriber = pubsub.register(tx);
+    pubsub.watch_prefixes(subscriber, vec!["verbete:".to_string(), "verbete:r".to_string()]);
+    pubsub.watch_keys(subscriber, vec!["verbete:rio".to_string()]);
+
+    assert_eq!(pubsub.notify_change(OperationKind::Delete, "verbete:rio", serde_json::Value::Null, 3), 1);
+    assert!(matches!(rx.try_recv().unwrap(), PubSubMessage::Change { kind: OperationKind::Delete, .. }));
+    assert!(rx.try_recv().is_err());
+
+    pubsub.unwatch_prefixes(subscriber, Vec::new());
+    pubsub.unwatch_keys(subscriber, Vec::new());
+    assert!(!pubsub.has_watchers());
+    assert_eq!(pubsub.notify_change(OperationKind::Insert, "verbete:mar", "oceano".into(), 4), 0);
+}
+
+#[test]
+fn test_change_line() {
+    let message = PubSubMessage::Change {
+        kind: OperationKind::MapSet,
+        key: "casa".to_string(),
+        value: serde_json::json!({"cor": "azul"}),
+        version: 2,
+    };
+    assert_eq!(message.to_line(), "{\"type\":\"change\",\"kind\":\"MapSet\",\"key\":\"casa\",\"value\":{\"cor\":\"azul\"},\"version\":2}\n");
+}
@@ -0,0 +1,307 @@
+use std::collections::{BTreeMap, HashMap};
+use guaradict_core::errors::RaftError;
+use guaradict_core::replica::{Operation, RaftMessage, RaftNode, RaftPayload, RaftRole};
+use guaradict_core::Dictionary;
+
+const ELECTION_TICKS: u32 = 10;
+const HEARTBEAT_TICKS: u32 = 2;
+
+// Cluster em memória: as mensagens são entregues na hora, exceto entre nós
+// em partições diferentes, e as entradas confirmadas vão para o dicionário
+// de cada nó
+struct Cluster {
+    nodes: BTreeMap<String, RaftNode>,
+    dictionaries: HashMap<String, Dictionary>,
+    partitions: HashMap<String, usize>,
+    // Gera as operações propostas, com versões crescentes
+    client: Dictionary,
+}
+
+impl Cluster {
+    fn new(size: usize) -> Self {
+        let members = (1..=size).map(|i| format!("node{}", i)).collect::<Vec<String>>();
+        let mut cluster = Self {
+            nodes: BTreeMap::new(),
+            dictionaries: HashMap::new(),
+            partitions: HashMap::new(),
+            client: Dictionary::new(),
+        };
+        for member in &members {
+            cluster.add_node(member, &members);
+        }
+        cluster
+    }
+
+    fn add_node(&mut self, id: &str, members: &[String]) {
+        self.nodes.insert(id.to_string(), RaftNode::new(id, members, ELECTION_TICKS, HEARTBEAT_TICKS));
+        self.dictionaries.insert(id.to_string(), Dictionary::new());
+    }
+
+    fn node(&self, id: &str) -> &RaftNode {
+        &self.nodes[id]
+    }
+
+    fn node_mut(&mut self, id: &str) -> &mut RaftNode {
+        self.nodes.get_mut(id).unwrap()
+    }
+
+    fn partition(&mut self, groups: &[&[&str]]) {
+        for (group, ids) in groups.iter().enumerate() {
+            for id in *ids {
+                self.partitions.insert(id.to_string(), group);
+            }
+        }
+    }
+
+    fn heal(&mut self) {
+        self.partitions.clear();
+    }
+
+    fn connected(&self, a: &str, b: &str) -> bool {
+        self.partitions.get(a).copied().unwrap_or(0) == self.partitions.get(b).copied().unwrap_or(0)
+    }
+
+    fn run(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            for node in self.nodes.values_mut() {
+                node.tick();
+            }
+            self.deliver();
+        }
+    }
+
+    fn deliver(&mut self) {
+        loop {
+            let mut pending: Vec<(String, String, RaftMessage)> = Vec::new();
+            for (id, node) in self.nodes.iter_mut() {
+                pending.extend(node.take_messages().into_iter().map(|envelope| (id.clone(), envelope.to, envelope.message)));
+            }
+            if pending.is_empty() {
+                break;
+            }
+
+            for (from, to, message) in pending {
+                if !self.connected(&from, &to) {
+                    continue;
+                }
+                if let Some(node) = self.nodes.get_mut(&to) {
+                    node.step(&from, message);
+                }
+            }
+        }
+
+        for (id, node) in self.nodes.iter_mut() {
+            let dictionary = self.dictionaries.get_mut(id).unwrap();
+            for entry in node.take_committed() {
+                if let RaftPayload::Operations { operations } = entry.payload {
+                    for operation in &operations {
+                        dictionary.apply(operation);
+                    }
+                }
+            }
+        }
+    }
+
+    // Líder do maior termo entre os nós indicados
+    fn leader_among(&self, ids: &[&str]) -> Option<String> {
+        ids.iter()
+            .map(|id| self.node(id))
+            .filter(|node| node.role() == RaftRole::Leader)
+            .max_by_key(|node| node.term())
+            .map(|node| node.id().to_string())
+    }
+
+    fn leader(&self) -> Option<String> {
+        let ids = self.nodes.keys().map(String::as_str).collect::<Vec<&str>>();
+        self.leader_among(&ids)
+    }
+
+    fn entry(&mut self, word: &str, definition: &str) -> Vec<Operation> {
+        self.client.add_entry(word.to_string(), definition.to_string());
+        self.client.take_operations()
+    }
+
+    fn propose(&mut self, leader: &str, word: &str, definition: &str) -> Result<u64, RaftError> {
+        let operations = self.entry(word, definition);
+        let result = self.node_mut(leader).propose(operations);
+        self.deliver();
+        result
+    }
+
+    fn definition(&self, id: &str, word: &str) -> Option<String> {
+        self.dictionaries[id].get_definition(word).cloned()
+    }
+}
+
+#[test]
+fn test_raft_elects_single_leader() {
+    let mut cluster = Cluster::new(3);
+    cluster.run(ELECTION_TICKS as usize * 3);
+
+    let leader = cluster.leader().expect("nenhum líder eleito");
+    let term = cluster.node(&leader).term();
+
+    let leaders = cluster.nodes.values().filter(|node| node.role() == RaftRole::Leader).count();
+    assert_eq!(leaders, 1);
+    for node in cluster.nodes.values() {
+        assert_eq!(node.term(), term);
+        assert_eq!(node.leader(), Some(leader.as_str()));
+    }
+}
+
+#[test]
+fn test_raft_commits_on_majority() {
+    let mut cluster = Cluster::new(3);
+    cluster.run(ELECTION_TICKS as usize * 3);
+    let leader = cluster.leader().unwrap();
+
+    let index = cluster.propose(&leader, "casa", "lugar de morar").unwrap();
+    assert_eq!(cluster.node(&leader).commit_index(), index);
+
+    // O seguidor só sabe da confirmação no próximo heartbeat
+    cluster.run(HEARTBEAT_TICKS as usize);
+    for id in ["node1", "node2", "node3"] {
+        assert_eq!(cluster.definition(id, "casa"), Some("lugar de morar".to_string()));
+    }
+
+    let follower = cluster.nodes.keys().find(|id| **id != leader).unwrap().clone();
+    let result = cluster.propose(&follower, "rio", "curso");
+    assert_eq!(result, Err(RaftError::NotLeader(Some(leader.clone()))));
+}
+
+#[test]
+fn test_raft_commits_with_one_node_down() {
+    let mut cluster = Cluster::new(3);
+    cluster.run(ELECTION_TICKS as usize * 3);
+    let leader = cluster.leader().unwrap();
+    let followers = cluster.nodes.keys().filter(|id| **id != leader).cloned().collect::<Vec<String>>();
+
+    // Um seguidor fica isolado: os outros dois ainda são maioria
+    cluster.partition(&[&[leader.as_str(), followers[0].as_str()], &[followers[1].as_str()]]);
+    let index = cluster.propose(&leader, "casa", "lugar de morar").unwrap();
+    assert_eq!(cluster.node(&leader).commit_index(), index);
+    assert_eq!(cluster.definition(&followers[1], "casa"), None);
+
+    // Ao voltar, o seguidor recebe o que perdeu
+    cluster.heal();
+    cluster.run(ELECTION_TICKS as usize * 3);
+    assert_eq!(cluster.definition(&followers[1], "casa"), Some("lugar de morar".to_string()));
+}
+
+#[test]
+fn test_raft_partitioned_leader_cannot_commit() {
+    let mut cluster = Cluster::new(5);
+    cluster.run(ELECTION_TICKS as usize * 3);
+    let old_leader = cluster.leader().unwrap();
+    cluster.propose(&old_leader, "casa", "lugar de morar").unwrap();
+    cluster.run(HEARTBEAT_TICKS as usize);
+
+    let others = cluster.nodes.keys().filter(|id| **id != old_leader).cloned().collect::<Vec<String>>();
+    let minority = [old_leader.as_str(), others[0].as_str()];
+    let majority = [others[1].as_str(), others[2].as_str(), others[3].as_str()];
+    cluster.partition(&[&minority, &majority]);
+
+    // O líder antigo aceita a escrita, mas sem maioria não a confirma
+    let commit_before = cluster.node(&old_leader).commit_index();
+    let index = cluster.propose(&old_leader, "rio", "curso d'agua").unwrap();
+    cluster.run(ELECTION_TICKS as usize * 4);
+    assert!(cluster.node(&old_leader).commit_index() < index);
+    assert_eq!(cluster.node(&old_leader).commit_index(), commit_before);
+    assert_eq!(cluster.definition(&old_leader, "rio"), None);
+
+    // A maioria elege outro líder num termo mais novo e continua aceitando escritas
+    let new_leader = cluster.leader_among(&majority).expect("a maioria não elegeu um líder");
+    assert!(cluster.node(&new_leader).term() > cluster.node(&old_leader).term());
+    cluster.propose(&new_leader, "mar", "agua salgada").unwrap();
+    cluster.run(HEARTBEAT_TICKS as usize);
+    for id in majority {
+        assert_eq!(cluster.definition(id, "mar"), Some("agua salgada".to_string()));
+    }
+
+    // Ao reunir a rede, o líder antigo se torna seguidor e a entrada não
+    // confirmada é substituída pelo log do novo líder
+    cluster.heal();
+    cluster.run(ELECTION_TICKS as usize * 4);
+    let leader = cluster.leader().unwrap();
+    assert_ne!(leader, old_leader);
+    assert_eq!(cluster.node(&old_leader).role(), RaftRole::Follower);
+
+    let leader_entries = cluster.node(&leader).entries().to_vec();
+    for node in cluster.nodes.values() {
+        assert_eq!(node.entries(), leader_entries.as_slice());
+        assert_eq!(node.commit_index(), cluster.node(&leader).commit_index());
+    }
+    for id in ["node1", "node2", "node3", "node4", "node5"] {
+        assert_eq!(cluster.definition(id, "casa"), Some("lugar de morar".to_string()));
+        assert_eq!(cluster.definition(id, "mar"), Some("agua salgada".to_string()));
+        assert_eq!(cluster.definition(id, "rio"), None);
+    }
+}
+
+#[test]
+fn test_raft_membership_changes() {
+    let mut cluster = Cluster::new(3);
+    cluster.run(ELECTION_TICKS as usize * 3);
+    let leader = cluster.leader().unwrap();
+    cluster.propose(&leader, "casa", "lugar de morar").unwrap();
+
+    // O novo nó começa sem log e recebe tudo do líder
+    let members = ["node1", "node2", "node3", "node4"].map(String::from);
+    cluster.add_node("node4", &members);
+    cluster.node_mut(&leader).add_member("node4").unwrap();
+    assert_eq!(cluster.node_mut(&leader).add_member("node5"), Err(RaftError::MembershipChangePending));
+    cluster.run(ELECTION_TICKS as usize);
+
+    assert_eq!(cluster.node(&leader).members().len(), 4);
+    assert_eq!(cluster.node("node4").members().len(), 4);
+    assert_eq!(cluster.definition("node4", "casa"), Some("lugar de morar".to_string()));
+    assert_eq!(cluster.node_mut(&leader).add_member("node4"), Err(RaftError::AlreadyMember("node4".to_string())));
+
+    // Com quatro membros, a maioria é três: dois nós não confirmam sozinhos
+    let others = cluster.nodes.keys().filter(|id| **id != leader).cloned().collect::<Vec<String>>();
+    cluster.partition(&[&[leader.as_str(), others[0].as_str()], &[others[1].as_str(), others[2].as_str()]]);
+    let index = cluster.propose(&leader, "rio", "curso").unwrap();
+    assert!(cluster.node(&leader).commit_index() < index);
+    cluster.heal();
+    cluster.run(ELECTION_TICKS as usize * 4);
+
+    // O líder removido confirma a própria saída e deixa de liderar
+    let leader = cluster.leader().unwrap();
+    cluster.node_mut(&leader).remove_member(&leader).unwrap();
+    assert_eq!(cluster.node_mut(&leader).remove_member("node9"), Err(RaftError::UnknownMember("node9".to_string())));
+    cluster.run(HEARTBEAT_TICKS as usize);
+    assert_eq!(cluster.node(&leader).role(), RaftRole::Follower);
+
+    cluster.run(ELECTION_TICKS as usize * 4);
+    let new_leader = cluster.leader().expect("os membros restantes não elegeram um líder");
+    assert_ne!(new_leader, leader);
+    assert_eq!(cluster.node(&new_leader).members().len(), 3);
+    assert!(!cluster.node(&new_leader).members().contains(&leader));
+
+    cluster.propose(&new_leader, "mar", "agua").unwrap();
+    cluster.run(HEARTBEAT_TICKS as usize);
+    for id in cluster.node(&new_leader).members().clone() {
+        assert_eq!(cluster.definition(&id, "mar"), Some("agua".to_string()));
+    }
+}
+
+#[test]
+fn test_raft_message_roundtrip() {
+    let mut cluster = Cluster::new(1);
+    cluster.run(ELECTION_TICKS as usize * 2);
+    assert_eq!(cluster.leader(), Some("node1".to_string()));
+
+    // Com um único nó a entrada é confirmada na hora
+    let index = cluster.propose("node1", "casa", "lugar de morar").unwrap();
+    assert_eq!(cluster.node("node1").commit_index(), index);
+
+    let entries = cluster.node("node1").entries().to_vec();
+    let message = RaftMessage::AppendEntries { term: 1, prev_log_index: 0, prev_log_term: 0, entries, leader_commit: index };
+    let line = message.to_line();
+    assert!(line.contains(r#""type":"appendEntries""#));
+    assert!(line.contains(r#""leaderCommit":2"#));
+    assert_eq!(RaftMessage::from_line(&line).unwrap().term(), 1);
+
+    let vote = RaftMessage::from_line(r#"{"type":"vote","term":3,"granted":true}"#).unwrap();
+    assert_eq!(vote, RaftMessage::Vote { term: 3, granted: true });
+}
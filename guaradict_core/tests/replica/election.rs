@@ -0,0 +1,80 @@
+use std::time::Duration;
+use guaradict_core::replica::{Election, Role};
+
+fn replica(name: &str, timeout: Duration) -> Election {
+    Election::new(name.to_string(), Role::Replica, Some("primary-node".to_string()), timeout)
+}
+
+#[test]
+fn test_new_election() {
+    let primary = Election::new("primary-node".to_string(), Role::Primary, None, Duration::from_secs(3));
+    assert_eq!(primary.leader(), Some("primary-node"));
+    assert!(primary.is_leader(0));
+
+    let replica = replica("replica-node-1", Duration::from_secs(3));
+    assert_eq!(replica.role(), Role::Replica);
+    assert_eq!(replica.leader(), Some("primary-node"));
+    assert!(replica.leader_alive());
+}
+
+#[test]
+fn test_vote_requires_lost_leader() {
+    let mut voter = replica("replica-node-2", Duration::from_secs(3));
+    assert!(!voter.request_vote(1, "replica-node-1", 10, 10));
+    // O termo mais novo é acompanhado mesmo sem dar o voto
+    assert_eq!(voter.term(), 1);
+}
+
+#[test]
+fn test_vote_once_per_term() {
+    let mut voter = replica("replica-node-3", Duration::ZERO);
+    assert!(voter.request_vote(1, "replica-node-1", 10, 10));
+    assert!(voter.request_vote(1, "replica-node-1", 10, 10));
+    assert!(!voter.request_vote(1, "replica-node-2", 10, 10));
+    assert!(!voter.request_vote(0, "replica-node-2", 10, 10));
+    assert!(voter.request_vote(2, "replica-node-2", 10, 10));
+}
+
+#[test]
+fn test_vote_requires_up_to_date_log() {
+    let mut voter = replica("replica-node-2", Duration::ZERO);
+    assert!(!voter.request_vote(1, "replica-node-1", 9, 10));
+    assert!(voter.request_vote(1, "replica-node-1", 11, 10));
+}
+
+#[test]
+fn test_primary_does_not_vote() {
+    let mut primary = Election::new("primary-node".to_string(), Role::Primary, None, Duration::ZERO);
+    assert!(!primary.request_vote(5, "replica-node-1", 10, 0));
+    assert!(primary.is_leader(0));
+}
+
+#[test]
+fn test_become_primary() {
+    let mut candidate = replica("replica-node-1", Duration::ZERO);
+    let term = candidate.start_election();
+    assert_eq!(term, 1);
+    assert_eq!(candidate.leader(), None);
+
+    // Outro nó já está num termo mais novo
+    candidate.observe_term(2);
+    assert!(!candidate.become_primary(term));
+
+    let term = candidate.start_election();
+    assert!(candidate.become_primary(term));
+    assert!(candidate.is_leader(3));
+    assert_eq!(candidate.leader(), Some("replica-node-1"));
+}
+
+#[test]
+fn test_accept_leader() {
+    let mut node = Election::new("primary-node".to_string(), Role::Primary, None, Duration::from_secs(3));
+    assert!(node.accept_leader(2, "replica-node-1"));
+    assert_eq!(node.role(), Role::Replica);
+    assert_eq!(node.leader(), Some("replica-node-1"));
+    assert!(!node.is_leader(0));
+
+    // Um primário antigo voltando com termo desatualizado é recusado
+    assert!(!node.accept_leader(0, "primary-node"));
+    assert_eq!(node.term(), 2);
+}
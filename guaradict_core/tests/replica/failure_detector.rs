@@ -0,0 +1,55 @@
+use tokio::time::{Duration, Instant};
+use guaradict_core::replica::PhiAccrualDetector;
+
+// Detector com heartbeats a cada `interval`, somado ao jitter de cada um
+fn beating(interval: Duration, jitter: &[Duration]) -> (PhiAccrualDetector, Instant) {
+    let mut detector = PhiAccrualDetector::new(Duration::from_millis(100));
+    let mut now = Instant::now();
+    detector.heartbeat(now);
+    for jitter in jitter {
+        now += interval + *jitter;
+        detector.heartbeat(now);
+    }
+    (detector, now)
+}
+
+#[test]
+fn test_phi_without_history() {
+    let detector = PhiAccrualDetector::new(Duration::from_millis(100));
+    assert_eq!(detector.phi(Instant::now()), 0.0);
+
+    // Um heartbeat só ainda não forma um intervalo
+    let (detector, now) = beating(Duration::from_secs(1), &[]);
+    assert_eq!(detector.phi(now + Duration::from_secs(60)), 0.0);
+    assert!(!detector.has_history());
+
+    // Com poucos intervalos o phi ainda não decide sozinho
+    assert!(!beating(Duration::from_secs(1), &[Duration::ZERO; 2]).0.has_history());
+    assert!(beating(Duration::from_secs(1), &[Duration::ZERO; 3]).0.has_history());
+}
+
+#[test]
+fn test_phi_grows_with_silence() {
+    let (detector, now) = beating(Duration::from_secs(1), &[Duration::ZERO; 10]);
+
+    let on_time = detector.phi(now + Duration::from_secs(1));
+    let late = detector.phi(now + Duration::from_millis(1300));
+    let silent = detector.phi(now + Duration::from_secs(3));
+    assert!(on_time < 1.0, "phi {}", on_time);
+    assert!(on_time < late && late < silent);
+    assert!(detector.is_available(now + Duration::from_secs(1), 8.0));
+    assert!(!detector.is_available(now + Duration::from_secs(3), 8.0));
+}
+
+#[test]
+fn test_phi_adapts_to_jitter() {
+    let steady = [Duration::ZERO; 20];
+    let jittery = [0, 800, 100, 1200, 0, 600, 300, 1000, 0, 900].map(Duration::from_millis);
+    let (steady, steady_now) = beating(Duration::from_secs(1), &steady);
+    let (jittery, jittery_now) = beating(Duration::from_secs(1), &jittery);
+
+    // O mesmo atraso é suspeito numa rede regular e normal numa instável
+    let elapsed = Duration::from_millis(2500);
+    assert!(!steady.is_available(steady_now + elapsed, 8.0));
+    assert!(jittery.is_available(jittery_now + elapsed, 8.0));
+}
@@ -0,0 +1,85 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use guaradict_core::commands::server;
+use guaradict_core::errors::HandshakeError;
+use guaradict_core::replica::{read_line, Election, LocalIdentity, NodeIdentity, Role, PROTOCOL_VERSION};
+
+fn local(name: &str, role: Role, cluster_id: Option<&str>) -> LocalIdentity {
+    let election = Election::new(name.to_string(), role, Some("primary-node".to_string()), Duration::from_secs(3));
+    LocalIdentity::new(Some("dict".to_string()), cluster_id.map(str::to_string), Arc::new(Mutex::new(election)))
+}
+
+#[test]
+fn test_identity_from_election() {
+    let identity = local("replica-node-1", Role::Replica, Some("guaradict")).identity();
+    assert_eq!(identity.name, "replica-node-1");
+    assert_eq!(identity.role, Role::Replica);
+    assert_eq!(identity.database.as_deref(), Some("dict"));
+    assert_eq!(identity.protocol, PROTOCOL_VERSION);
+    assert_eq!(identity.cluster_id.as_deref(), Some("guaradict"));
+}
+
+#[test]
+fn test_check_rejects_mismatched_peer() {
+    let primary = local("primary-node", Role::Primary, Some("guaradict")).identity();
+    let replica = local("replica-node-1", Role::Replica, Some("guaradict")).identity();
+    assert_eq!(primary.check(&replica), Ok(()));
+
+    let peer = NodeIdentity { protocol: PROTOCOL_VERSION + 1, ..replica.clone() };
+    assert_eq!(primary.check(&peer), Err(HandshakeError::ProtocolMismatch { local: PROTOCOL_VERSION, remote: PROTOCOL_VERSION + 1 }));
+
+    let peer = NodeIdentity { database: Some("outro".to_string()), ..replica.clone() };
+    assert!(matches!(primary.check(&peer), Err(HandshakeError::DatabaseMismatch { .. })));
+
+    let peer = NodeIdentity { cluster_id: None, ..replica };
+    assert!(matches!(primary.check(&peer), Err(HandshakeError::ClusterMismatch { .. })));
+}
+
+#[test]
+fn test_accept_replies_welcome_or_denied() {
+    let primary = local("primary-node", Role::Primary, Some("guaradict"));
+
+    let replica = local("replica-node-1", Role::Replica, Some("guaradict")).identity();
+    assert_eq!(primary.accept(&replica), Ok(server::Command::Welcome(primary.identity())));
+
+    let stranger = local("replica-node-1", Role::Replica, Some("outro-cluster")).identity();
+    assert!(matches!(primary.accept(&stranger), Err(server::Command::Denied(_))));
+}
+
+// Nó falso que responde ao HELLO com a linha `reply(identidade recebida)`
+async fn peer(reply: fn(NodeIdentity) -> server::Command) -> std::net::SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let line = read_line(&mut socket).await.unwrap();
+        let Ok(server::Command::Hello(identity)) = server::Command::parse(&line) else {
+            panic!("HELLO esperado: {}", line);
+        };
+        let _ = socket.write_all(format!("{}\n", reply(identity).serialize()).as_bytes()).await;
+    });
+    addr
+}
+
+#[tokio::test]
+async fn test_connect_checks_peer_identity() {
+    let primary = local("primary-node", Role::Primary, Some("guaradict"));
+
+    let addr = peer(|identity| server::Command::Welcome(NodeIdentity { name: "replica-node-1".to_string(), role: Role::Replica, ..identity })).await;
+    let (_, replica) = primary.connect(addr, "replica-node-1").await.unwrap();
+    assert_eq!(replica.role, Role::Replica);
+
+    // Outro nó no endereço esperado da réplica
+    let addr = peer(|identity| server::Command::Welcome(NodeIdentity { name: "replica-node-2".to_string(), ..identity })).await;
+    let error = primary.connect(addr, "replica-node-1").await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        HandshakeError::UnexpectedNode { expected: "replica-node-1".to_string(), actual: "replica-node-2".to_string() }.to_string()
+    );
+
+    let addr = peer(|_| server::Command::Denied("Cluster diferente".to_string())).await;
+    let error = primary.connect(addr, "replica-node-1").await.unwrap_err();
+    assert_eq!(error.to_string(), HandshakeError::Denied("Cluster diferente".to_string()).to_string());
+}
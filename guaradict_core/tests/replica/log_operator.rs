@@ -1,4 +1,4 @@
-use guaradict_core::replica::{LogOperator, OperationKind};
+use guaradict_core::replica::{LogOperator, MaxLag, OperationKind, ReplicationLag};
 
 #[test]
 fn test_insert_operation() {
@@ -41,3 +41,83 @@ fn test_delete_operation() {
     assert_eq!(operation.current_value, None);
     assert_eq!(operation.prev_value, None);
 }
+
+#[test]
+fn test_map_set_operation() {
+    let mut log_operator = LogOperator::new();
+    log_operator.map_set("key4", "field", "new_value", Some("old_value"));
+
+    let operation = &log_operator.operations[0];
+    assert_eq!(operation.kind, OperationKind::MapSet);
+    assert_eq!(operation.key, "key4".into());
+    assert_eq!(operation.field, Some("field".into()));
+    assert_eq!(operation.current_value, Some("new_value".into()));
+    assert_eq!(operation.prev_value, Some("old_value".into()));
+}
+
+#[test]
+fn test_map_delete_operation() {
+    let mut log_operator = LogOperator::new();
+    log_operator.map_delete("key5", "field", Some("old_value"));
+
+    let operation = &log_operator.operations[0];
+    assert_eq!(operation.kind, OperationKind::MapDelete);
+    assert_eq!(operation.field, Some("field".into()));
+    assert_eq!(operation.current_value, None);
+    assert_eq!(operation.prev_value, Some("old_value".into()));
+}
+
+#[test]
+fn test_set_add_operation() {
+    let mut log_operator = LogOperator::new();
+    log_operator.set_add("key6", vec!["a", "b"]);
+
+    let operation = &log_operator.operations[0];
+    assert_eq!(operation.kind, OperationKind::SetAdd);
+    assert_eq!(operation.current_value, Some(vec!["a", "b"].into()));
+    assert_eq!(operation.prev_value, None);
+}
+
+#[test]
+fn test_append_batch() {
+    let mut single = LogOperator::new();
+    single.insert("key7", "value");
+
+    let mut transaction = LogOperator::new();
+    transaction.insert("key8", "value");
+    transaction.set_add("key9", vec!["a"]);
+
+    let mut log_operator = LogOperator::new();
+    log_operator.append_batch(single.operations);
+    let batch = log_operator.append_batch(transaction.operations);
+
+    let batches: Vec<_> = log_operator.iter_batches().collect();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[1].len(), 2);
+    assert!(batches[1].iter().all(|operation| operation.batch == batch));
+}
+
+#[test]
+fn test_replication_lag() {
+    let mut writes = LogOperator::new();
+    writes.insert("key10", "value");
+    writes.insert("key11", "value");
+    writes.delete("key10");
+    for (version, operation) in writes.operations.iter_mut().enumerate() {
+        operation.version = version as u64 + 1;
+    }
+
+    let mut log_operator = LogOperator::new();
+    log_operator.append_batch(writes.operations);
+
+    let lag = log_operator.lag(1);
+    assert_eq!(lag.operations, 2);
+    assert!(lag.bytes > 0);
+    assert!(lag.bytes < log_operator.lag(0).bytes);
+    assert_eq!(log_operator.lag(3), ReplicationLag::default());
+
+    let max_lag = MaxLag { operations: Some(1), ..MaxLag::default() };
+    assert!(max_lag.exceeded(&lag));
+    assert!(!max_lag.exceeded(&log_operator.lag(2)));
+    assert!(!MaxLag::default().exceeded(&lag));
+}
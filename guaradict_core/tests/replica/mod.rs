@@ -1 +1,8 @@
 pub mod log_operator;
+pub mod replication;
+pub mod election;
+pub mod multi_primary;
+pub mod handshake;
+pub mod replica_status;
+pub mod failure_detector;
+pub mod monitor;
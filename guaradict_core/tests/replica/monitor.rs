@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, watch};
+use tokio::time::{timeout, Duration};
+use guaradict_core::commands::server;
+use guaradict_core::replica::{read_line, Backoff, Detector, Election, LocalIdentity, LogOperator, MonitorSettings, NodeIdentity, ReplicaEvent, ReplicaMonitorServer, ReplicaState, ReplicaStatus, Role};
+
+fn settings() -> MonitorSettings {
+    MonitorSettings {
+        interval: Duration::from_millis(50),
+        ping_timeout: Duration::from_millis(100),
+        connect_timeout: Duration::from_millis(500),
+        failures: 2,
+        backoff: Duration::from_millis(20),
+        max_backoff: Duration::from_millis(100),
+        ..MonitorSettings::default()
+    }
+}
+
+fn monitor(replicas: &[(&str, SocketAddr)], settings: MonitorSettings) -> ReplicaMonitorServer {
+    let election = Election::new("primary-node".to_string(), Role::Primary, None, Duration::from_secs(3));
+    let identity = LocalIdentity::new(None, None, Arc::new(Mutex::new(election)));
+    let replicas = replicas
+        .iter()
+        .map(|(name, bus)| (name.to_string(), ReplicaStatus { bus: Some(*bus), ..ReplicaStatus::new(name.to_string(), false, None) }))
+        .collect::<HashMap<String, ReplicaStatus>>();
+    let (_, offset) = watch::channel(0);
+    ReplicaMonitorServer::new(replicas, identity, Arc::new(tokio::sync::Mutex::new(LogOperator::new())), offset, settings)
+}
+
+// Réplica falsa que aceita o HELLO e responde a cada PING conforme
+// `answer`, chamada com o número do PING contado entre todas as conexões:
+// `None` não responde e `Some((atraso, versão))` manda o PONG com a versão
+// depois do atraso
+async fn scripted_replica<F>(name: &'static str, answer: F) -> SocketAddr
+where
+    F: Fn(usize) -> Option<(Duration, u64)> + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let answer = Arc::new(answer);
+    let pings = Arc::new(AtomicUsize::new(0));
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let answer = Arc::clone(&answer);
+            let pings = Arc::clone(&pings);
+            tokio::spawn(async move {
+                let Ok(server::Command::Hello(identity)) = server::Command::parse(&read_line(&mut socket).await.unwrap()) else {
+                    return;
+                };
+                let welcome = server::Command::Welcome(NodeIdentity { name: name.to_string(), role: Role::Replica, ..identity });
+                socket.write_all(format!("{}\n", welcome.serialize()).as_bytes()).await.unwrap();
+                while read_line(&mut socket).await.is_ok() {
+                    if let Some((delay, offset)) = answer(pings.fetch_add(1, Ordering::SeqCst)) {
+                        tokio::time::sleep(delay).await;
+                        let pong = server::Command::Pong(name.to_string(), offset);
+                        if socket.write_all(format!("{}\n", pong.serialize()).as_bytes()).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    addr
+}
+
+// Réplica que responde na hora aos primeiros `pongs` PINGs
+async fn replica(name: &'static str, pongs: usize) -> SocketAddr {
+    scripted_replica(name, move |ping| (ping < pongs).then_some((Duration::ZERO, 0))).await
+}
+
+async fn next_event(events: &mut broadcast::Receiver<ReplicaEvent>, name: &str) -> (ReplicaState, ReplicaState) {
+    loop {
+        let event = timeout(Duration::from_secs(2), events.recv()).await.unwrap().unwrap();
+        if event.name == name {
+            return (event.from, event.to);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_unreachable_replica_does_not_stall_others() {
+    // Aceita a conexão mas nunca responde ao HELLO
+    let silent = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let healthy = replica("replica-node-1", usize::MAX).await;
+    let monitor = monitor(&[("replica-node-1", healthy), ("replica-node-2", silent.local_addr().unwrap())], settings());
+    let replicas = monitor.replicas();
+    let mut events = monitor.subscribe();
+    tokio::spawn(monitor.start());
+
+    // A réplica saudável fica pronta antes do prazo de conexão da outra
+    timeout(Duration::from_millis(400), next_event(&mut events, "replica-node-1")).await.unwrap();
+    let statuses = replicas.lock().await;
+    assert_eq!(statuses["replica-node-1"].state, ReplicaState::Ready);
+    assert!(statuses["replica-node-1"].ready);
+    assert_eq!(statuses["replica-node-1"].role, Some(Role::Replica));
+    assert_eq!(statuses["replica-node-2"].state, ReplicaState::Connecting);
+    drop(statuses);
+
+    assert_eq!(next_event(&mut events, "replica-node-2").await, (ReplicaState::Connecting, ReplicaState::Down));
+    assert_eq!(replicas.lock().await["replica-node-2"].failures, 1);
+}
+
+#[tokio::test]
+async fn test_replica_state_transitions() {
+    // Responde ao primeiro PING, deixa de responder aos dois seguintes e
+    // volta a responder depois
+    let addr = scripted_replica("replica-node-1", |ping| (ping == 0 || ping >= 3).then_some((Duration::ZERO, 0))).await;
+    let monitor = monitor(&[("replica-node-1", addr)], settings());
+    let mut events = monitor.subscribe();
+    tokio::spawn(monitor.start());
+
+    assert_eq!(next_event(&mut events, "replica-node-1").await, (ReplicaState::Connecting, ReplicaState::Ready));
+    assert_eq!(next_event(&mut events, "replica-node-1").await, (ReplicaState::Ready, ReplicaState::Suspect));
+    assert_eq!(next_event(&mut events, "replica-node-1").await, (ReplicaState::Suspect, ReplicaState::Down));
+    // Reconecta depois da espera
+    assert_eq!(next_event(&mut events, "replica-node-1").await, (ReplicaState::Down, ReplicaState::Connecting));
+    assert_eq!(next_event(&mut events, "replica-node-1").await, (ReplicaState::Connecting, ReplicaState::Ready));
+}
+
+#[tokio::test]
+async fn test_phi_detector_without_history_counts_failures() {
+    // Nunca responde: o phi não tem intervalos e fica em zero
+    let addr = replica("replica-node-1", 0).await;
+    let monitor = monitor(&[("replica-node-1", addr)], MonitorSettings { detector: Detector::Phi, ..settings() });
+    let mut events = monitor.subscribe();
+    tokio::spawn(monitor.start());
+
+    assert_eq!(next_event(&mut events, "replica-node-1").await, (ReplicaState::Connecting, ReplicaState::Suspect));
+    assert_eq!(next_event(&mut events, "replica-node-1").await, (ReplicaState::Suspect, ReplicaState::Down));
+}
+
+#[tokio::test]
+async fn test_late_pong_is_not_taken_as_next_reply() {
+    // O primeiro PONG chega depois do prazo; os seguintes trazem a versão
+    // do PING a que respondem
+    let addr = scripted_replica("replica-node-1", |ping| match ping {
+        0 => Some((Duration::from_millis(150), 1)),
+        ping => Some((Duration::ZERO, ping as u64 + 1)),
+    })
+    .await;
+    let monitor = monitor(&[("replica-node-1", addr)], settings());
+    let replicas = monitor.replicas();
+    let mut events = monitor.subscribe();
+    tokio::spawn(monitor.start());
+
+    assert_eq!(next_event(&mut events, "replica-node-1").await, (ReplicaState::Connecting, ReplicaState::Suspect));
+    assert_eq!(next_event(&mut events, "replica-node-1").await, (ReplicaState::Suspect, ReplicaState::Ready));
+    assert_eq!(replicas.lock().await["replica-node-1"].offset, 2);
+}
+
+#[test]
+fn test_backoff_grows_with_jitter() {
+    let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(1000));
+    for expected in [100, 200, 400, 800, 1000, 1000] {
+        let delay = backoff.next_delay();
+        assert!(delay >= Duration::from_millis(expected / 2) && delay <= Duration::from_millis(expected), "{:?} fora de {}", delay, expected);
+    }
+
+    backoff.reset();
+    assert!(backoff.next_delay() <= Duration::from_millis(100));
+}
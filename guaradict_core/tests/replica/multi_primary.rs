@@ -0,0 +1,172 @@
+use guaradict_core::replica::{Causality, ConflictResolution, ConflictResolver, HlcTimestamp, HybridClock, Operation, ReplicationMessage, VersionVector};
+use guaradict_core::{Dictionary, ListSide};
+
+struct Primary {
+    dictionary: Dictionary,
+    resolver: ConflictResolver,
+}
+
+impl Primary {
+    fn new(name: &str, resolution: ConflictResolution) -> Self {
+        Self {
+            dictionary: Dictionary::new(),
+            resolver: ConflictResolver::new(name, resolution),
+        }
+    }
+
+    fn write<F>(&mut self, write: F) -> Vec<Operation>
+    where
+        F: FnOnce(&mut Dictionary),
+    {
+        write(&mut self.dictionary);
+        let mut operations = self.dictionary.take_operations();
+        self.resolver.stamp(&mut operations, &self.dictionary);
+
+        // Passa pela serialização, como na replicação
+        let line = ReplicationMessage::Batch { operations }.to_line();
+        let ReplicationMessage::Batch { operations } = ReplicationMessage::from_line(&line).unwrap() else {
+            panic!("Mensagem inesperada");
+        };
+        operations
+    }
+
+    fn receive(&mut self, operations: &[Operation]) -> Vec<Operation> {
+        self.resolver.merge(operations, &mut self.dictionary)
+    }
+
+    fn definition(&self, word: &str) -> Option<String> {
+        self.dictionary.get_definition(word).cloned()
+    }
+}
+
+fn timestamp(operations: &[Operation]) -> HlcTimestamp {
+    operations.last().unwrap().stamp.as_ref().unwrap().timestamp.clone()
+}
+
+#[test]
+fn test_hybrid_clock_is_monotonic() {
+    let mut clock = HybridClock::new("primary-node-1");
+
+    let first = clock.now_at(100);
+    let second = clock.now_at(100);
+    // O relógio do sistema voltou atrás, mas o híbrido não
+    let third = clock.now_at(90);
+    assert!(first < second && second < third);
+    assert_eq!((third.wall, third.logical), (100, 2));
+
+    // Um instante remoto à frente puxa o relógio local
+    let remote = HlcTimestamp { wall: 500, logical: 3, node: "primary-node-2".to_string() };
+    clock.observe_at(&remote, 120);
+    let fourth = clock.now_at(130);
+    assert!(fourth > remote);
+    assert_eq!((fourth.wall, fourth.logical), (500, 5));
+}
+
+#[test]
+fn test_version_vector_causality() {
+    let mut a = VersionVector::new();
+    a.increment("primary-node-1");
+    let mut b = a.clone();
+    assert_eq!(a.compare(&b), Causality::Equal);
+
+    b.increment("primary-node-2");
+    assert_eq!(a.compare(&b), Causality::Before);
+    assert_eq!(b.compare(&a), Causality::After);
+
+    a.increment("primary-node-1");
+    assert_eq!(a.compare(&b), Causality::Concurrent);
+
+    a.merge(&b);
+    assert_eq!(a.get("primary-node-1"), 2);
+    assert_eq!(a.get("primary-node-2"), 1);
+    assert_eq!(a.compare(&b), Causality::After);
+}
+
+#[test]
+fn test_last_writer_wins_converges() {
+    let mut primary1 = Primary::new("primary-node-1", ConflictResolution::LastWriterWins);
+    let mut primary2 = Primary::new("primary-node-2", ConflictResolution::LastWriterWins);
+
+    let write1 = primary1.write(|dictionary| dictionary.add_entry("casa".to_string(), "lugar de morar".to_string()));
+    let write2 = primary2.write(|dictionary| dictionary.add_entry("casa".to_string(), "moradia".to_string()));
+    let expected = if timestamp(&write2) > timestamp(&write1) { "moradia" } else { "lugar de morar" };
+
+    // Cada primário recebe a escrita do outro; só a perdedora é descartada
+    let forwarded1 = primary1.receive(&write2);
+    let forwarded2 = primary2.receive(&write1);
+    assert_eq!(forwarded1.len() + forwarded2.len(), 1);
+
+    assert_eq!(primary1.definition("casa").as_deref(), Some(expected));
+    assert_eq!(primary2.definition("casa").as_deref(), Some(expected));
+
+    // As escritas reenviadas voltam à origem e não geram novas operações
+    assert!(primary2.receive(&forwarded1).is_empty());
+    assert!(primary1.receive(&forwarded2).is_empty());
+    assert!(primary1.resolver.conflicts().next().is_none());
+}
+
+#[test]
+fn test_delete_wins_over_older_write() {
+    let mut primary1 = Primary::new("primary-node-1", ConflictResolution::LastWriterWins);
+    let mut primary2 = Primary::new("primary-node-2", ConflictResolution::LastWriterWins);
+
+    let insert = primary1.write(|dictionary| dictionary.add_entry("casa".to_string(), "lugar de morar".to_string()));
+    primary2.receive(&insert);
+    let delete = primary2.write(|dictionary| dictionary.remove_entry("casa"));
+
+    // O insert atrasado chega depois da remoção e é descartado
+    primary1.receive(&delete);
+    assert!(primary2.receive(&insert).is_empty());
+
+    assert_eq!(primary1.definition("casa"), None);
+    assert_eq!(primary2.definition("casa"), None);
+}
+
+#[test]
+fn test_merge_keeps_value_type() {
+    let mut primary1 = Primary::new("primary-node-1", ConflictResolution::LastWriterWins);
+    let mut primary2 = Primary::new("primary-node-2", ConflictResolution::LastWriterWins);
+
+    let write = primary1.write(|dictionary| {
+        dictionary.set_add("cores", vec!["azul".to_string(), "verde".to_string()]).unwrap();
+        dictionary.list_push("fila", ListSide::Right, vec!["a".to_string(), "b".to_string()]).unwrap();
+    });
+    let forwarded = primary2.receive(&write);
+    assert_eq!(forwarded.len(), 2);
+
+    assert!(primary2.dictionary.set_is_member("cores", "verde").unwrap());
+    assert_eq!(primary2.dictionary.list_range("fila", 0, -1).unwrap(), vec!["a".to_string(), "b".to_string()]);
+    // As operações reenviadas usam as versões do próprio nó
+    assert_eq!(forwarded.last().unwrap().version, primary2.dictionary.revision());
+}
+
+#[test]
+fn test_vector_clock_detects_conflicts() {
+    let mut primary1 = Primary::new("primary-node-1", ConflictResolution::VectorClock);
+    let mut primary2 = Primary::new("primary-node-2", ConflictResolution::VectorClock);
+
+    // Escrita vista pelos dois antes da próxima: não há conflito
+    let first = primary1.write(|dictionary| dictionary.add_entry("casa".to_string(), "lugar de morar".to_string()));
+    primary2.receive(&first);
+    let second = primary2.write(|dictionary| dictionary.add_entry("casa".to_string(), "moradia".to_string()));
+    primary1.receive(&second);
+    assert_eq!(primary1.definition("casa").as_deref(), Some("moradia"));
+    assert!(primary1.resolver.conflicts().next().is_none());
+
+    // Escritas concorrentes são registradas nos dois lados com o mesmo vencedor
+    let write1 = primary1.write(|dictionary| dictionary.add_entry("casa".to_string(), "lar".to_string()));
+    let write2 = primary2.write(|dictionary| dictionary.add_entry("casa".to_string(), "residência".to_string()));
+    let forwarded1 = primary1.receive(&write2);
+    let forwarded2 = primary2.receive(&write1);
+
+    let conflict1 = primary1.resolver.conflicts().last().unwrap().clone();
+    let conflict2 = primary2.resolver.conflicts().last().unwrap().clone();
+    assert_eq!(conflict1.key, "casa");
+    assert_eq!(conflict1.winner, conflict2.winner);
+    assert_eq!(primary1.definition("casa"), primary2.definition("casa"));
+
+    // Depois da resolução os vetores se igualam e os reenvios são descartados
+    assert!(primary2.receive(&forwarded1).is_empty());
+    assert!(primary1.receive(&forwarded2).is_empty());
+    assert_eq!(primary1.resolver.conflicts().count(), 1);
+}
@@ -0,0 +1,26 @@
+use std::time::Duration;
+use guaradict_core::replica::{ReplicaState, ReplicaStatus, ReplicationLag, Role};
+
+#[test]
+fn test_replica_status_serialization() {
+    let mut status = ReplicaStatus::new("replica-node-1".to_string(), true, "127.0.0.1:13142".parse().ok());
+    status.role = Some(Role::Replica);
+    status.ping = Duration::from_micros(1500);
+    status.offset = 42;
+    status.lag = ReplicationLag { operations: 3, bytes: 120, seconds: 0.25 };
+
+    let json = serde_json::to_value(&status).unwrap();
+    assert_eq!(json["role"], "replica");
+    assert_eq!(json["state"], "ready");
+    // O ping vai em milissegundos
+    assert_eq!(json["ping"], 1.5);
+    assert_eq!(json["addr"], "127.0.0.1:13142");
+    assert_eq!(json["lag"]["operations"], 3);
+    assert!(json.get("stream").is_none());
+
+    let parsed = serde_json::from_value::<ReplicaStatus>(json).unwrap();
+    assert_eq!(parsed.ping, status.ping);
+    assert_eq!(parsed.role, Some(Role::Replica));
+    assert_eq!(parsed.lag, status.lag);
+    assert_eq!(parsed.state, ReplicaState::Ready);
+}
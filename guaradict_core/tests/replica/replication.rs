@@ -0,0 +1,114 @@
+use std::sync::Arc;
+use std::time::Duration;
+use guaradict_core::replica::{Acks, LogOperator, OperationValue, ReplicationAcks, ReplicationMessage};
+use guaradict_core::Dictionary;
+
+#[test]
+fn test_batches_after() {
+    let mut dictionary = Dictionary::new();
+    let mut log_operator = LogOperator::new();
+
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+    log_operator.append_batch(dictionary.take_operations());
+    dictionary.add_entries(vec![("rio".to_string(), "curso".to_string()), ("mar".to_string(), "agua".to_string())]);
+    log_operator.append_batch(dictionary.take_operations());
+
+    assert_eq!(log_operator.batches_after(0).len(), 2);
+
+    let batches = log_operator.batches_after(1);
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].iter().map(|operation| operation.version).collect::<Vec<u64>>(), vec![2, 3]);
+
+    assert!(log_operator.batches_after(3).is_empty());
+}
+
+#[test]
+fn test_replication_message_roundtrip() {
+    let mut log_operator = LogOperator::new();
+    log_operator.insert("casa", vec![("cor", "azul")]);
+    log_operator.set_add("tags", vec!["rio", "mar"]);
+    log_operator.map_set("casa", "portas", 2, None);
+
+    let message = ReplicationMessage::Batch { operations: log_operator.operations.clone() };
+    let line = message.to_line();
+    assert!(line.ends_with('\n'));
+
+    let ReplicationMessage::Batch { operations } = ReplicationMessage::from_line(&line).unwrap() else {
+        panic!("Mensagem inesperada");
+    };
+    assert_eq!(operations.len(), 3);
+    for (received, sent) in operations.iter().zip(&log_operator.operations) {
+        assert_eq!(received.kind, sent.kind);
+        assert_eq!(received.key, sent.key);
+        assert_eq!(received.field, sent.field);
+        assert_eq!(received.current_value, sent.current_value);
+    }
+    assert_eq!(operations[0].current_value, Some(OperationValue::from(vec![("cor", "azul")])));
+
+    assert_eq!(ReplicationMessage::from_line(r#"{"type":"ack","offset":7}"#).unwrap(), ReplicationMessage::Ack { offset: 7 });
+}
+
+#[test]
+fn test_replicated_operations_apply() {
+    let mut primary = Dictionary::new();
+    primary.add_entry("casa".to_string(), "lugar de morar".to_string());
+    primary.map_set("config", "cor", "azul".to_string()).unwrap();
+
+    let line = ReplicationMessage::Batch { operations: primary.take_operations() }.to_line();
+    let ReplicationMessage::Batch { operations } = ReplicationMessage::from_line(&line).unwrap() else {
+        panic!("Mensagem inesperada");
+    };
+
+    let mut replica = Dictionary::new();
+    for operation in &operations {
+        replica.apply(operation);
+    }
+
+    assert_eq!(replica.get_definition("casa"), Some(&"lugar de morar".to_string()));
+    assert_eq!(replica.map_get("config", "cor").unwrap(), Some(&"azul".to_string()));
+    assert_eq!(replica.revision(), primary.revision());
+}
+
+#[test]
+fn test_acks_required() {
+    assert_eq!(Acks::None.required(3), 0);
+    assert_eq!(Acks::Replicas(2).required(3), 2);
+    assert_eq!(Acks::Majority.required(1), 1);
+    assert_eq!(Acks::Majority.required(2), 1);
+    assert_eq!(Acks::Majority.required(4), 2);
+    assert_eq!(Acks::All.required(3), 3);
+}
+
+#[test]
+fn test_acks_parse() {
+    assert_eq!("0".parse::<Acks>(), Ok(Acks::None));
+    assert_eq!("2".parse::<Acks>(), Ok(Acks::Replicas(2)));
+    assert_eq!("majority".parse::<Acks>(), Ok(Acks::Majority));
+    assert_eq!("ALL".parse::<Acks>(), Ok(Acks::All));
+    assert!("algumas".parse::<Acks>().is_err());
+
+    assert_eq!(serde_yaml::from_str::<Acks>("1").unwrap(), Acks::Replicas(1));
+    assert_eq!(serde_yaml::from_str::<Acks>("majority").unwrap(), Acks::Majority);
+    assert!(serde_yaml::from_str::<Acks>("-1").is_err());
+}
+
+#[tokio::test]
+async fn test_wait_for_acks() {
+    let acks = Arc::new(ReplicationAcks::new(2));
+    acks.ack("replica-1", 5);
+    assert_eq!(acks.acked(5), 1);
+    assert_eq!(acks.acked(6), 0);
+
+    // Sem confirmações suficientes, espera até o tempo acabar
+    assert_eq!(acks.wait_for(5, 2, Some(Duration::from_millis(20))).await, 1);
+
+    let waiting = acks.clone();
+    let wait = tokio::spawn(async move { waiting.wait_for(8, 2, Some(Duration::from_secs(5))).await });
+    acks.ack("replica-1", 8);
+    acks.ack("replica-2", 9);
+    assert_eq!(wait.await.unwrap(), 2);
+
+    // Confirmações antigas não fazem o offset voltar
+    acks.ack("replica-2", 3);
+    assert_eq!(acks.acked(9), 1);
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+use guaradict_core::{script_hash, Dictionary, ListSide, ScriptEngine};
+use guaradict_core::errors::ScriptError;
+use guaradict_core::replica::OperationKind;
+
+fn engine() -> ScriptEngine {
+    ScriptEngine::new(Duration::from_secs(5))
+}
+
+#[test]
+fn test_eval_reads_and_writes_dictionary() {
+    let mut scripts = engine();
+    let mut dictionary = Dictionary::new();
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+
+    let result = scripts
+        .eval("let definicao = get(KEYS[0]); set(KEYS[1], definicao); definicao", vec!["casa".to_string(), "lar".to_string()], Vec::new(), &mut dictionary)
+        .unwrap();
+
+    assert_eq!(result, serde_json::json!("lugar de morar"));
+    assert_eq!(dictionary.get_definition("lar").unwrap(), "lugar de morar");
+}
+
+#[test]
+fn test_eval_records_resulting_writes() {
+    let mut scripts = engine();
+    let mut dictionary = Dictionary::new();
+    dictionary.list_push("origem", ListSide::Right, vec!["a".to_string()]).unwrap();
+    dictionary.take_operations();
+
+    // Move o valor entre listas
+    scripts.eval("let v = lpop(KEYS[0]); rpush(KEYS[1], v)", vec!["origem".to_string(), "destino".to_string()], Vec::new(), &mut dictionary).unwrap();
+
+    let kinds: Vec<OperationKind> = dictionary.take_operations().into_iter().map(|operation| operation.kind).collect();
+    assert_eq!(kinds, vec![OperationKind::ListPopLeft, OperationKind::ListPushRight]);
+}
+
+#[test]
+fn test_eval_arguments_and_structured_result() {
+    let mut scripts = engine();
+    let mut dictionary = Dictionary::new();
+
+    let result = scripts
+        .eval("hincrby(KEYS[0], ARGV[0], parse_int(ARGV[1])); #{ total: hget(KEYS[0], ARGV[0]) }", vec!["estoque".to_string()], vec!["casa".to_string(), "3".to_string()], &mut dictionary)
+        .unwrap();
+
+    assert_eq!(result, serde_json::json!({ "total": "3" }));
+}
+
+#[test]
+fn test_script_cache() {
+    let mut scripts = engine();
+    let mut dictionary = Dictionary::new();
+
+    let hash = scripts.load("40 + 2").unwrap();
+    assert_eq!(hash, script_hash("40 + 2"));
+    assert!(scripts.exists(&hash));
+    assert_eq!(scripts.eval_hash(&hash, Vec::new(), Vec::new(), &mut dictionary).unwrap(), serde_json::json!(42));
+
+    scripts.flush();
+    assert_eq!(scripts.eval_hash(&hash, Vec::new(), Vec::new(), &mut dictionary), Err(ScriptError::NotFound(hash)));
+}
+
+#[test]
+fn test_compile_error() {
+    let mut scripts = engine();
+    assert!(matches!(scripts.load("let ="), Err(ScriptError::Compile(_))));
+}
+
+#[test]
+fn test_runtime_error_keeps_dictionary() {
+    let mut scripts = engine();
+    let mut dictionary = Dictionary::new();
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+
+    let result = scripts.eval("hget(\"casa\", \"cor\")", Vec::new(), Vec::new(), &mut dictionary);
+    assert!(matches!(result, Err(ScriptError::Runtime(_))));
+    assert_eq!(dictionary.get_definition("casa").unwrap(), "lugar de morar");
+}
+
+#[test]
+fn test_time_limit() {
+    let mut scripts = ScriptEngine::new(Duration::from_millis(50));
+    let mut dictionary = Dictionary::new();
+
+    let result = scripts.eval("set(\"casa\", \"lar\"); loop { }", Vec::new(), Vec::new(), &mut dictionary);
+    assert_eq!(result, Err(ScriptError::Timeout(Duration::from_millis(50))));
+    // O estouro de tempo desfaz o que o script já tinha escrito
+    assert!(dictionary.get("casa").is_none());
+    assert!(dictionary.take_operations().is_empty());
+}
+
+#[test]
+fn test_error_rolls_back_writes() {
+    let mut scripts = engine();
+    let mut dictionary = Dictionary::with_search_index();
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+    let before = dictionary.clone();
+
+    let result = scripts.eval(
+        "set(\"casa\", \"lar\"); rpush(\"fila\", \"a\"); del(\"casa\"); throw \"falhou\"",
+        Vec::new(),
+        Vec::new(),
+        &mut dictionary,
+    );
+    assert!(matches!(result, Err(ScriptError::Runtime(_))));
+    assert_eq!(dictionary, before);
+    assert_eq!(dictionary.get_definition("casa").unwrap(), "lugar de morar");
+    assert_eq!(dictionary.version("casa"), before.version("casa"));
+    assert_eq!(dictionary.search("morar").unwrap(), before.search("morar").unwrap());
+    // Só a escrita anterior ao script vai para a replicação
+    assert_eq!(dictionary.take_operations().len(), 1);
+}
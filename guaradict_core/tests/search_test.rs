@@ -0,0 +1,55 @@
+use guaradict_core::{tokenize, SearchIndex, SearchQuery};
+
+#[test]
+fn test_tokenize_folds_accents() {
+    let tokens = tokenize("Ribeirão, Pontão e AÇÚCAR!");
+    assert_eq!(tokens, vec!["ribeirao", "pontao", "e", "acucar"]);
+}
+
+#[test]
+fn test_parse_query() {
+    let query = SearchQuery::parse("rio AND água OR mar").unwrap();
+    assert_eq!(query.groups, vec![vec!["rio".to_string(), "agua".to_string()], vec!["mar".to_string()]]);
+    assert!(SearchQuery::parse("OR").is_err());
+}
+
+#[test]
+fn test_search_and_or() {
+    let mut index = SearchIndex::new();
+    index.insert("riacho", "pequeno rio de água doce");
+    index.insert("oceano", "grande massa de água salgada");
+    index.insert("lago", "porção de água cercada de terra");
+
+    let hits = index.search(&SearchQuery::parse("rio agua").unwrap());
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].word, "riacho");
+
+    let hits = index.search(&SearchQuery::parse("rio OR salgada").unwrap());
+    let words = hits.iter().map(|hit| hit.word.as_str()).collect::<Vec<&str>>();
+    assert_eq!(words.len(), 2);
+    assert!(words.contains(&"riacho"));
+    assert!(words.contains(&"oceano"));
+}
+
+#[test]
+fn test_search_ranking() {
+    let mut index = SearchIndex::new();
+    index.insert("rio", "curso de água, água corrente");
+    index.insert("chuva", "água que cai das nuvens sobre a terra e o mar");
+
+    let hits = index.search(&SearchQuery::parse("agua").unwrap());
+    assert_eq!(hits[0].word, "rio");
+    assert!(hits[0].score > hits[1].score);
+}
+
+#[test]
+fn test_remove_from_index() {
+    let mut index = SearchIndex::new();
+    index.insert("rio", "curso de água");
+    index.insert("rio", "corrente natural");
+    assert!(index.search(&SearchQuery::parse("agua").unwrap()).is_empty());
+
+    index.remove("rio");
+    assert!(index.is_empty());
+    assert!(index.search(&SearchQuery::parse("corrente").unwrap()).is_empty());
+}
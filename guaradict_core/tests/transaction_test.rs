@@ -0,0 +1,107 @@
+use guaradict_core::{Dictionary, Transaction};
+use guaradict_core::commands::client::Command;
+use guaradict_core::errors::TransactionError;
+
+#[test]
+fn test_exec_returns_queued_commands() {
+    let dictionary = Dictionary::new();
+    let mut transaction = Transaction::new();
+
+    transaction.begin().unwrap();
+    transaction.queue(Command::Set("casa".to_string(), "lugar de morar".to_string()));
+    transaction.queue(Command::Get("casa".to_string()));
+
+    let commands = transaction.exec(&dictionary).unwrap();
+    assert_eq!(commands.len(), 2);
+    assert!(!transaction.is_active());
+}
+
+#[test]
+fn test_exec_without_multi() {
+    let dictionary = Dictionary::new();
+    let mut transaction = Transaction::new();
+
+    assert_eq!(transaction.exec(&dictionary), Err(TransactionError::NotStarted("EXEC")));
+    assert_eq!(transaction.discard(), Err(TransactionError::NotStarted("DISCARD")));
+}
+
+#[test]
+fn test_nested_multi() {
+    let mut transaction = Transaction::new();
+    transaction.begin().unwrap();
+    assert_eq!(transaction.begin(), Err(TransactionError::Nested));
+}
+
+#[test]
+fn test_discard() {
+    let dictionary = Dictionary::new();
+    let mut transaction = Transaction::new();
+
+    transaction.begin().unwrap();
+    transaction.queue(Command::Del("casa".to_string()));
+    transaction.discard().unwrap();
+
+    assert!(!transaction.is_active());
+    assert_eq!(transaction.exec(&dictionary), Err(TransactionError::NotStarted("EXEC")));
+}
+
+#[test]
+fn test_failed_transaction_is_discarded() {
+    let dictionary = Dictionary::new();
+    let mut transaction = Transaction::new();
+
+    transaction.begin().unwrap();
+    transaction.fail();
+
+    assert_eq!(transaction.exec(&dictionary), Err(TransactionError::Failed));
+    assert!(!transaction.is_active());
+}
+
+#[test]
+fn test_watched_key_changed() {
+    let mut dictionary = Dictionary::new();
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+
+    let mut transaction = Transaction::new();
+    transaction.watch(vec!["casa".to_string()], &dictionary).unwrap();
+    transaction.begin().unwrap();
+    transaction.queue(Command::Set("casa".to_string(), "moradia".to_string()));
+
+    dictionary.add_entry("casa".to_string(), "habitação".to_string());
+
+    assert_eq!(transaction.exec(&dictionary), Err(TransactionError::WatchedKeyChanged));
+}
+
+#[test]
+fn test_watched_missing_key_created() {
+    let mut dictionary = Dictionary::new();
+
+    let mut transaction = Transaction::new();
+    transaction.watch(vec!["casa".to_string()], &dictionary).unwrap();
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+    transaction.begin().unwrap();
+
+    assert_eq!(transaction.exec(&dictionary), Err(TransactionError::WatchedKeyChanged));
+}
+
+#[test]
+fn test_unwatch() {
+    let mut dictionary = Dictionary::new();
+
+    let mut transaction = Transaction::new();
+    transaction.watch(vec!["casa".to_string()], &dictionary).unwrap();
+    transaction.unwatch();
+    dictionary.add_entry("casa".to_string(), "lugar de morar".to_string());
+    transaction.begin().unwrap();
+
+    assert!(transaction.exec(&dictionary).is_ok());
+}
+
+#[test]
+fn test_watch_inside_multi() {
+    let dictionary = Dictionary::new();
+    let mut transaction = Transaction::new();
+
+    transaction.begin().unwrap();
+    assert_eq!(transaction.watch(vec!["casa".to_string()], &dictionary), Err(TransactionError::WatchInsideMulti));
+}
@@ -1,6 +1,7 @@
 "use strict";
 
-const { createDriver, connect, disconnect, set, get } = require('./index.node');
+const { createDriver, connect, disconnect, set, get, getEntry, addSense, delSense, mget, mset, mdel, clusterStatus, publish, subscribe, unsubscribe } = require('./index.node');
+const { messages, prefixChanges } = require('./subscription');
 
 let currentIndex
 
@@ -9,6 +10,7 @@ global.onEvent = function (event) {
 };
 
 async function main() {
+    // Com réplicas, createDriver(addr, { readPreference: 'replica' }) manda as leituras a elas
     const driver = createDriver('127.0.0.1:13141');
     try {
         const index = await connect.call(driver);
@@ -17,6 +19,44 @@ async function main() {
         const response = await get.call(driver, index, "my-key");
         console.log('GET my-key:', response);
 
+        await addSense.call(driver, index, "casa", {
+            definition: "edifício destinado à habitação",
+            partOfSpeech: "substantivo",
+            language: "pt-BR",
+            examples: ["comprou uma casa no interior"],
+            synonyms: ["lar", "moradia"],
+        });
+        const entry = await getEntry.call(driver, index, "casa");
+        console.log('GETENTRY casa:', entry);
+        await delSense.call(driver, index, "casa", 0);
+
+        await mset.call(driver, index, { rio: "curso de água", mar: "grande extensão de água salgada" });
+        const definitions = await mget.call(driver, index, ["rio", "mar", "lago"]);
+        console.log('MGET rio mar lago:', definitions);
+        const removed = await mdel.call(driver, index, ["rio", "mar"]);
+        console.log('MDEL rio mar:', removed);
+
+        const nodes = await clusterStatus.call(driver, index);
+        console.table(nodes.map(({ name, role, addr, ready, ping, failures, offset, lag }) => ({ name, role, addr, ready, ping, failures, offset, lagOperations: lag.operations })));
+
+        const subscription = await subscribe.call(driver, ["noticias"], (message) => {
+            console.log('Callback:', message);
+        });
+        const iterator = await messages(driver, ["noticias"]);
+        await publish.call(driver, index, "noticias", "nova palavra");
+        for await (const message of iterator) {
+            console.log('Iterador:', message);
+            break;
+        }
+        unsubscribe(subscription);
+
+        const changes = await prefixChanges(driver, ["verbete:"]);
+        await set.call(driver, index, "verbete:rio", "curso de água");
+        for await (const change of changes) {
+            console.log('Alteração:', change);
+            break;
+        }
+
         await disconnect.call(driver, index);
     } catch (err) {
         console.error('Error:', err);
@@ -1,9 +1,18 @@
-use std::io::{self, Write, Read};
-use std::net::{SocketAddr, TcpStream};
+use std::collections::HashMap;
+use std::io::{self, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
 use std::sync::{Arc, Mutex, mpsc};
 use std::thread;
 use std::time::{Duration, Instant};
 use neon::prelude::*;
+use guaradict_core::cluster::{NodeRole, ReadPreference, Redirect, Topology};
+use guaradict_core::commands::{client, frame};
+use guaradict_core::{Entry, Sense};
+
+// Idade máxima da topologia antes de consultá-la de novo
+const TOPOLOGY_MAX_AGE: Duration = Duration::from_secs(30);
+// Redirecionamentos seguidos num mesmo comando antes de desistir
+const MAX_REDIRECTS: usize = 5;
 
 #[derive(Clone)]
 struct Connection {
@@ -29,14 +38,24 @@ struct GuaradictDriver {
     addr: SocketAddr,
     pool: Arc<Mutex<Vec<Option<Connection>>>>,
     event_sender: mpsc::Sender<Event>,
+    read_preference: ReadPreference,
+    topology: Mutex<Topology>,
+    // Conexões com os demais nós do cluster, compartilhadas pelo pool
+    nodes: Mutex<HashMap<String, Arc<Mutex<TcpStream>>>>,
 }
 
 impl GuaradictDriver {
-    fn new(addr: SocketAddr, event_sender: mpsc::Sender<Event>) -> Self {
+    fn new(addr: SocketAddr, read_preference: ReadPreference, event_sender: mpsc::Sender<Event>) -> Self {
+        let mut topology = Topology::single(&addr.to_string());
+        topology.invalidate();
+
         let driver = Self {
             addr,
             pool: Arc::new(Mutex::new(Vec::with_capacity(10))),
             event_sender,
+            read_preference,
+            topology: Mutex::new(topology),
+            nodes: Mutex::new(HashMap::new()),
         };
 
         let pool_clone = Arc::clone(&driver.pool);
@@ -71,10 +90,7 @@ impl GuaradictDriver {
             return Ok(index);
         }
 
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Connection pool is full",
-        ))
+        Err(io::Error::other("Connection pool is full"))
     }
 
     fn get_connection(&self, index: usize) -> Option<Arc<Mutex<TcpStream>>> {
@@ -102,41 +118,335 @@ impl GuaradictDriver {
     }
 
     fn set(&self, index: usize, key: String, value: String) -> io::Result<()> {
-        if let Some(stream) = self.get_connection(index) {
-            let mut stream = stream.lock().unwrap();
-            let command = format!("SET {} {}\n", key, value);
-            stream.write_all(command.as_bytes())?;
-            stream.flush()?;
-
-            let mut buffer = [0; 512];
-            stream.read(&mut buffer)?;
-        } else {
+        self.request(index, client::Command::Set(key, value))?;
+        Ok(())
+    }
+
+    fn get(&self, index: usize, key: String) -> io::Result<String> {
+        self.request(index, client::Command::Get(key))
+    }
+
+    /// Manda o comando ao nó que atende as suas chaves: as escritas ao
+    /// primário dono do slot e as leituras conforme a `readPreference`.
+    /// Comandos sem chaves usam a conexão `index` do pool. Segue os `MOVED`
+    /// e `ASK` e consulta de novo a topologia quando ela muda.
+    fn request(&self, index: usize, command: client::Command) -> io::Result<String> {
+        let Some(stream) = self.get_connection(index) else {
             return Err(io::Error::new(
                 io::ErrorKind::NotFound,
                 "Connection not found",
             ));
+        };
+
+        if self.topology.lock().unwrap().is_stale(TOPOLOGY_MAX_AGE) {
+            // Sem a topologia, os redirecionamentos ainda levam ao nó certo
+            let _ = self.refresh();
+        }
+        let target = self
+            .topology
+            .lock()
+            .unwrap()
+            .route(&command.keys(), command.is_read_only(), self.read_preference)
+            .filter(|addr| addr.parse() != Ok(self.addr));
+
+        let line = command.execute();
+        let mut response = match &target {
+            Some(addr) => self.send_to(addr, &[&line])?,
+            None => exchange(&mut stream.lock().unwrap(), &line)?,
+        };
+        for _ in 0..MAX_REDIRECTS {
+            match Redirect::parse(&response) {
+                Some(Redirect::Moved { slot, addr }) => {
+                    self.topology.lock().unwrap().moved(slot, &addr);
+                    response = self.send_to(&addr, &[&line])?;
+                }
+                Some(Redirect::Ask { addr, .. }) => {
+                    response = self.send_to(&addr, &[&client::Command::Asking.execute(), &line])?;
+                }
+                None => break,
+            }
+        }
+
+        Ok(response)
+    }
+
+    // Consulta os slots no nó inicial e as réplicas de cada primário
+    fn refresh(&self) -> io::Result<()> {
+        let seed = self.addr.to_string();
+        let response = self.send_to(&seed, &[&client::Command::ClusterSlots.execute()])?;
+        let mut topology = Topology::from_cluster_slots(&seed, &response).map_err(io::Error::other)?;
+
+        for primary in topology.primaries() {
+            let role = self
+                .send_to(&primary, &[&client::Command::Role.execute()])
+                .and_then(|response| serde_json::from_str::<NodeRole>(&response).map_err(io::Error::other));
+            if let Ok(role) = role {
+                topology.set_role(&primary, role);
+            }
         }
+
+        *self.topology.lock().unwrap() = topology;
         Ok(())
     }
 
-    fn get(&self, index: usize, key: String) -> io::Result<String> {
-        if let Some(stream) = self.get_connection(index) {
-            let mut stream = stream.lock().unwrap();
-            let command = format!("GET {}\n", key);
-            stream.write_all(command.as_bytes())?;
-            stream.flush()?;
-
-            let mut buffer = [0; 512];
-            let n = stream.read(&mut buffer)?;
-            let response = String::from_utf8_lossy(&buffer[..n]);
-            Ok(response.to_string())
-        } else {
-            Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "Connection not found",
-            ))
+    // Envia as linhas em sequência pela conexão com o nó, sem que outra
+    // requisição se intercale, e retorna a última resposta
+    fn send_to(&self, addr: &str, lines: &[&str]) -> io::Result<String> {
+        let stream = self.node(addr)?;
+        let mut stream = stream.lock().unwrap();
+
+        let mut response = String::new();
+        for line in lines {
+            response = match exchange(&mut stream, line) {
+                Ok(response) => response,
+                Err(e) => {
+                    // Sem conexão com o nó, a topologia pode ter mudado
+                    self.nodes.lock().unwrap().remove(addr);
+                    self.topology.lock().unwrap().invalidate();
+                    return Err(e);
+                }
+            };
+        }
+        Ok(response)
+    }
+
+    fn node(&self, addr: &str) -> io::Result<Arc<Mutex<TcpStream>>> {
+        if let Some(stream) = self.nodes.lock().unwrap().get(addr) {
+            return Ok(Arc::clone(stream));
+        }
+
+        let socket_addr = addr
+            .parse()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid node address: {}", addr)))?;
+        let stream = Arc::new(Mutex::new(TcpStream::connect_timeout(&socket_addr, Duration::from_secs(30))?));
+        self.nodes.lock().unwrap().insert(addr.to_string(), Arc::clone(&stream));
+        Ok(stream)
+    }
+
+    fn get_with_version(&self, index: usize, key: String) -> io::Result<Option<(String, u64)>> {
+        let response = self.request(index, client::Command::GetWithVersion(key))?;
+        if response == "Key not found" {
+            return Ok(None);
         }
+
+        let value: serde_json::Value = serde_json::from_str(&response)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        match (value["definition"].as_str(), value["version"].as_u64()) {
+            (Some(definition), Some(version)) => Ok(Some((definition.to_string(), version))),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, response)),
+        }
+    }
+
+    fn cas(&self, index: usize, key: String, version: u64, value: String) -> io::Result<u64> {
+        let response = self.request(index, client::Command::Cas(key, version, value))?;
+        response.parse().map_err(|_| io::Error::other(response))
+    }
+
+    fn get_entry(&self, index: usize, key: String) -> io::Result<Option<Entry>> {
+        let response = self.request(index, client::Command::GetEntry(key))?;
+        if response == "Key not found" {
+            return Ok(None);
+        }
+
+        serde_json::from_str(&response)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn add_sense(&self, index: usize, key: String, sense: Sense) -> io::Result<()> {
+        self.request(index, client::Command::AddSense(key, sense))?;
+        Ok(())
+    }
+
+    fn del_sense(&self, index: usize, key: String, position: usize) -> io::Result<()> {
+        let response = self.request(index, client::Command::DelSense(key, position))?;
+        if response != "Sense removed successfully" {
+            return Err(io::Error::other(response));
+        }
+        Ok(())
+    }
+
+    fn mget(&self, index: usize, keys: Vec<String>) -> io::Result<Vec<Option<String>>> {
+        let response = self.request(index, client::Command::MGet(keys))?;
+        serde_json::from_str(&response).map_err(|_| io::Error::other(response))
+    }
+
+    fn mset(&self, index: usize, entries: Vec<(String, String)>) -> io::Result<()> {
+        let response = self.request(index, client::Command::MSet(entries))?;
+        if response != "Entries added successfully" {
+            return Err(io::Error::other(response));
+        }
+        Ok(())
+    }
+
+    fn mdel(&self, index: usize, keys: Vec<String>) -> io::Result<usize> {
+        let response = self.request(index, client::Command::MDel(keys))?;
+        response.parse().map_err(|_| io::Error::other(response))
+    }
+
+    /// Nós vistos pelo primário, como no CLUSTER STATUS.
+    fn cluster_status(&self, index: usize) -> io::Result<serde_json::Value> {
+        let response = self.request(index, client::Command::ClusterStatus)?;
+        serde_json::from_str(&response).map_err(|_| io::Error::other(response))
+    }
+
+    fn publish(&self, index: usize, channel: String, message: String) -> io::Result<usize> {
+        let response = self.request(index, client::Command::Publish(channel, message))?;
+        response.parse().map_err(|_| io::Error::other(response))
+    }
+
+    /// Abre uma conexão dedicada, fora do pool, e a inscreve nos canais ou
+    /// padrões. Retorna a conexão e o leitor posicionado após as confirmações.
+    fn subscribe(&self, command: &str, targets: &[String]) -> io::Result<(TcpStream, BufReader<TcpStream>)> {
+        let mut stream = TcpStream::connect_timeout(&self.addr, Duration::from_secs(30))?;
+        stream.write_all(format!("{} {}\n", command, targets.join(" ")).as_bytes())?;
+        stream.flush()?;
+
+        // As confirmações chegam juntas, uma linha por canal ou padrão
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let response = frame::read_blocking(&mut reader)?;
+        let confirmations = response.lines().collect::<Vec<&str>>();
+        if confirmations.len() != targets.len() {
+            return Err(io::Error::other(response.trim().to_string()));
+        }
+        for line in confirmations {
+            let confirmation: serde_json::Value = serde_json::from_str(line)
+                .map_err(|_| io::Error::other(line.trim().to_string()))?;
+            if confirmation["count"].as_u64().is_none() {
+                return Err(io::Error::other(line.trim().to_string()));
+            }
+        }
+
+        Ok((stream, reader))
+    }
+}
+
+// Envia uma linha e lê a resposta
+fn exchange(stream: &mut TcpStream, line: &str) -> io::Result<String> {
+    stream.write_all(format!("{}\n", line).as_bytes())?;
+    stream.flush()?;
+
+    frame::read_blocking(stream)
+}
+
+struct Subscription {
+    stream: TcpStream,
+}
+
+impl Finalize for Subscription {}
+
+fn message_to_js<'a, C: Context<'a>>(cx: &mut C, message: &serde_json::Value) -> JsResult<'a, JsObject> {
+    let obj = cx.empty_object();
+    for key in ["type", "pattern", "channel", "data", "kind", "key"] {
+        if let Some(value) = message[key].as_str() {
+            let value = cx.string(value);
+            obj.set(cx, key, value)?;
+        }
+    }
+    if let Some(version) = message["version"].as_u64() {
+        let version = cx.number(version as f64);
+        obj.set(cx, "version", version)?;
+    }
+    if message["type"] == "change" {
+        let value = json_to_js(cx, &message["value"])?;
+        obj.set(cx, "value", value)?;
     }
+    Ok(obj)
+}
+
+fn json_to_js<'a, C: Context<'a>>(cx: &mut C, value: &serde_json::Value) -> JsResult<'a, JsValue> {
+    Ok(match value {
+        serde_json::Value::Null => cx.null().upcast(),
+        serde_json::Value::Bool(value) => cx.boolean(*value).upcast(),
+        serde_json::Value::Number(value) => cx.number(value.as_f64().unwrap_or_default()).upcast(),
+        serde_json::Value::String(value) => cx.string(value).upcast(),
+        serde_json::Value::Array(values) => {
+            let array = JsArray::new(cx, values.len());
+            for (i, value) in values.iter().enumerate() {
+                let value = json_to_js(cx, value)?;
+                array.set(cx, i as u32, value)?;
+            }
+            array.upcast()
+        }
+        serde_json::Value::Object(map) => {
+            let obj = cx.empty_object();
+            for (key, value) in map {
+                let value = json_to_js(cx, value)?;
+                obj.set(cx, key.as_str(), value)?;
+            }
+            obj.upcast()
+        }
+    })
+}
+
+fn entry_to_js<'a, C: Context<'a>>(cx: &mut C, entry: &Entry) -> JsResult<'a, JsObject> {
+    let obj = cx.empty_object();
+    let senses = JsArray::new(cx, entry.senses.len());
+
+    for (i, sense) in entry.senses.iter().enumerate() {
+        let js_sense = cx.empty_object();
+
+        let definition = cx.string(&sense.definition);
+        js_sense.set(cx, "definition", definition)?;
+        if let Some(part_of_speech) = &sense.part_of_speech {
+            let part_of_speech = cx.string(part_of_speech);
+            js_sense.set(cx, "partOfSpeech", part_of_speech)?;
+        }
+        if let Some(language) = &sense.language {
+            let language = cx.string(language);
+            js_sense.set(cx, "language", language)?;
+        }
+        let examples = strings_to_js(cx, &sense.examples)?;
+        js_sense.set(cx, "examples", examples)?;
+        let synonyms = strings_to_js(cx, &sense.synonyms)?;
+        js_sense.set(cx, "synonyms", synonyms)?;
+
+        senses.set(cx, i as u32, js_sense)?;
+    }
+
+    obj.set(cx, "senses", senses)?;
+    Ok(obj)
+}
+
+fn strings_to_js<'a, C: Context<'a>>(cx: &mut C, values: &[String]) -> JsResult<'a, JsArray> {
+    let array = JsArray::new(cx, values.len());
+    for (i, value) in values.iter().enumerate() {
+        let value = cx.string(value);
+        array.set(cx, i as u32, value)?;
+    }
+    Ok(array)
+}
+
+fn sense_from_js<'a, C: Context<'a>>(cx: &mut C, obj: Handle<'a, JsObject>) -> NeonResult<Sense> {
+    let definition = obj.get::<JsString, _, _>(cx, "definition")?.value(cx);
+    let part_of_speech = obj.get_opt::<JsString, _, _>(cx, "partOfSpeech")?.map(|v| v.value(cx));
+    let language = obj.get_opt::<JsString, _, _>(cx, "language")?.map(|v| v.value(cx));
+    let examples = strings_from_js(cx, obj, "examples")?;
+    let synonyms = strings_from_js(cx, obj, "synonyms")?;
+
+    Ok(Sense {
+        definition,
+        part_of_speech,
+        examples,
+        language,
+        synonyms,
+    })
+}
+
+fn string_array_from_js<'a, C: Context<'a>>(cx: &mut C, array: Handle<'a, JsArray>) -> NeonResult<Vec<String>> {
+    array
+        .to_vec(cx)?
+        .into_iter()
+        .map(|value| Ok(value.downcast_or_throw::<JsString, _>(cx)?.value(cx)))
+        .collect()
+}
+
+fn strings_from_js<'a, C: Context<'a>>(cx: &mut C, obj: Handle<'a, JsObject>, key: &str) -> NeonResult<Vec<String>> {
+    let Some(array) = obj.get_opt::<JsArray, _, _>(cx, key)? else {
+        return Ok(Vec::new());
+    };
+
+    string_array_from_js(cx, array)
 }
 
 struct NeonGuaradictDriver {
@@ -148,8 +458,22 @@ impl Finalize for NeonGuaradictDriver {}
 impl NeonGuaradictDriver {
     fn js_new(mut cx: FunctionContext) -> JsResult<JsBox<NeonGuaradictDriver>> {
         let addr = cx.argument::<JsString>(0)?.value(&mut cx).parse().unwrap();
+        // Opções: { readPreference: "primary" | "replica" | "nearest" }
+        let read_preference = match cx.argument_opt(1) {
+            Some(options) => {
+                let options = options.downcast_or_throw::<JsObject, _>(&mut cx)?;
+                match options.get_opt::<JsString, _, _>(&mut cx, "readPreference")? {
+                    Some(preference) => match preference.value(&mut cx).parse() {
+                        Ok(preference) => preference,
+                        Err(e) => return cx.throw_error(e),
+                    },
+                    None => ReadPreference::Primary,
+                }
+            }
+            None => ReadPreference::Primary,
+        };
         let (event_tx, event_rx) = mpsc::channel();
-        let driver = GuaradictDriver::new(addr, event_tx);
+        let driver = GuaradictDriver::new(addr, read_preference, event_tx);
 
         let neon_driver = NeonGuaradictDriver {
             inner: Arc::new(driver),
@@ -274,6 +598,323 @@ impl NeonGuaradictDriver {
 
         Ok(promise)
     }
+
+    fn js_get_with_version(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let neon_driver = cx.this_value().downcast_or_throw::<JsBox<NeonGuaradictDriver>, _>(&mut cx)?;
+        let index = cx.argument::<JsNumber>(0)?.value(&mut cx) as usize;
+        let key = cx.argument::<JsString>(1)?.value(&mut cx);
+        let (deferred, promise) = cx.promise();
+
+        let driver = Arc::clone(&neon_driver.inner);
+        let channel = cx.channel();
+        thread::spawn(move || {
+            let result = driver.get_with_version(index, key);
+            deferred.settle_with(&channel, move |mut cx| {
+                match result {
+                    Ok(Some((definition, version))) => {
+                        let obj = cx.empty_object();
+                        let js_definition = cx.string(definition);
+                        obj.set(&mut cx, "definition", js_definition)?;
+                        let js_version = cx.number(version as f64);
+                        obj.set(&mut cx, "version", js_version)?;
+                        Ok(obj.upcast::<JsValue>())
+                    }
+                    Ok(None) => Ok(cx.null().upcast::<JsValue>()),
+                    Err(err) => cx.throw_error(err.to_string()),
+                }
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn js_cas(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let neon_driver = cx.this_value().downcast_or_throw::<JsBox<NeonGuaradictDriver>, _>(&mut cx)?;
+        let index = cx.argument::<JsNumber>(0)?.value(&mut cx) as usize;
+        let key = cx.argument::<JsString>(1)?.value(&mut cx);
+        let version = cx.argument::<JsNumber>(2)?.value(&mut cx) as u64;
+        let value = cx.argument::<JsString>(3)?.value(&mut cx);
+        let (deferred, promise) = cx.promise();
+
+        let driver = Arc::clone(&neon_driver.inner);
+        let channel = cx.channel();
+        thread::spawn(move || {
+            let result = driver.cas(index, key, version, value);
+            deferred.settle_with(&channel, move |mut cx| {
+                match result {
+                    Ok(version) => Ok(cx.number(version as f64).upcast::<JsValue>()),
+                    Err(err) => cx.throw_error(err.to_string()),
+                }
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn js_get_entry(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let neon_driver = cx.this_value().downcast_or_throw::<JsBox<NeonGuaradictDriver>, _>(&mut cx)?;
+        let index = cx.argument::<JsNumber>(0)?.value(&mut cx) as usize;
+        let key = cx.argument::<JsString>(1)?.value(&mut cx);
+        let (deferred, promise) = cx.promise();
+
+        let driver = Arc::clone(&neon_driver.inner);
+        let channel = cx.channel();
+        thread::spawn(move || {
+            let result = driver.get_entry(index, key);
+            deferred.settle_with(&channel, move |mut cx| {
+                match result {
+                    Ok(Some(entry)) => Ok(entry_to_js(&mut cx, &entry)?.upcast::<JsValue>()),
+                    Ok(None) => Ok(cx.null().upcast::<JsValue>()),
+                    Err(err) => cx.throw_error(err.to_string()),
+                }
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn js_add_sense(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let neon_driver = cx.this_value().downcast_or_throw::<JsBox<NeonGuaradictDriver>, _>(&mut cx)?;
+        let index = cx.argument::<JsNumber>(0)?.value(&mut cx) as usize;
+        let key = cx.argument::<JsString>(1)?.value(&mut cx);
+        let sense = cx.argument::<JsObject>(2)?;
+        let sense = sense_from_js(&mut cx, sense)?;
+        let (deferred, promise) = cx.promise();
+
+        let driver = Arc::clone(&neon_driver.inner);
+        let channel = cx.channel();
+        thread::spawn(move || {
+            let result = driver.add_sense(index, key, sense);
+            deferred.settle_with(&channel, move |mut cx| {
+                match result {
+                    Ok(_) => Ok(cx.undefined().upcast::<JsValue>()),
+                    Err(err) => cx.throw_error(err.to_string()),
+                }
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn js_del_sense(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let neon_driver = cx.this_value().downcast_or_throw::<JsBox<NeonGuaradictDriver>, _>(&mut cx)?;
+        let index = cx.argument::<JsNumber>(0)?.value(&mut cx) as usize;
+        let key = cx.argument::<JsString>(1)?.value(&mut cx);
+        let position = cx.argument::<JsNumber>(2)?.value(&mut cx) as usize;
+        let (deferred, promise) = cx.promise();
+
+        let driver = Arc::clone(&neon_driver.inner);
+        let channel = cx.channel();
+        thread::spawn(move || {
+            let result = driver.del_sense(index, key, position);
+            deferred.settle_with(&channel, move |mut cx| {
+                match result {
+                    Ok(_) => Ok(cx.undefined().upcast::<JsValue>()),
+                    Err(err) => cx.throw_error(err.to_string()),
+                }
+            });
+        });
+
+        Ok(promise)
+    }
+    fn js_mget(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let neon_driver = cx.this_value().downcast_or_throw::<JsBox<NeonGuaradictDriver>, _>(&mut cx)?;
+        let index = cx.argument::<JsNumber>(0)?.value(&mut cx) as usize;
+        let keys = cx.argument::<JsArray>(1)?;
+        let keys = string_array_from_js(&mut cx, keys)?;
+        let (deferred, promise) = cx.promise();
+
+        let driver = Arc::clone(&neon_driver.inner);
+        let channel = cx.channel();
+        thread::spawn(move || {
+            let result = driver.mget(index, keys);
+            deferred.settle_with(&channel, move |mut cx| {
+                match result {
+                    Ok(definitions) => {
+                        let array = JsArray::new(&mut cx, definitions.len());
+                        for (i, definition) in definitions.into_iter().enumerate() {
+                            let value = match definition {
+                                Some(definition) => cx.string(definition).upcast::<JsValue>(),
+                                None => cx.null().upcast::<JsValue>(),
+                            };
+                            array.set(&mut cx, i as u32, value)?;
+                        }
+                        Ok(array.upcast::<JsValue>())
+                    }
+                    Err(err) => cx.throw_error(err.to_string()),
+                }
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn js_mset(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let neon_driver = cx.this_value().downcast_or_throw::<JsBox<NeonGuaradictDriver>, _>(&mut cx)?;
+        let index = cx.argument::<JsNumber>(0)?.value(&mut cx) as usize;
+        let obj = cx.argument::<JsObject>(1)?;
+        let keys = obj.get_own_property_names(&mut cx)?;
+        let keys = string_array_from_js(&mut cx, keys)?;
+        let mut entries = Vec::with_capacity(keys.len());
+        for key in keys {
+            let value = obj.get::<JsString, _, _>(&mut cx, key.as_str())?.value(&mut cx);
+            entries.push((key, value));
+        }
+        let (deferred, promise) = cx.promise();
+
+        let driver = Arc::clone(&neon_driver.inner);
+        let channel = cx.channel();
+        thread::spawn(move || {
+            let result = driver.mset(index, entries);
+            deferred.settle_with(&channel, move |mut cx| {
+                match result {
+                    Ok(_) => Ok(cx.undefined().upcast::<JsValue>()),
+                    Err(err) => cx.throw_error(err.to_string()),
+                }
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn js_mdel(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let neon_driver = cx.this_value().downcast_or_throw::<JsBox<NeonGuaradictDriver>, _>(&mut cx)?;
+        let index = cx.argument::<JsNumber>(0)?.value(&mut cx) as usize;
+        let keys = cx.argument::<JsArray>(1)?;
+        let keys = string_array_from_js(&mut cx, keys)?;
+        let (deferred, promise) = cx.promise();
+
+        let driver = Arc::clone(&neon_driver.inner);
+        let channel = cx.channel();
+        thread::spawn(move || {
+            let result = driver.mdel(index, keys);
+            deferred.settle_with(&channel, move |mut cx| {
+                match result {
+                    Ok(removed) => Ok(cx.number(removed as f64).upcast::<JsValue>()),
+                    Err(err) => cx.throw_error(err.to_string()),
+                }
+            });
+        });
+
+        Ok(promise)
+    }
+    fn js_cluster_status(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let neon_driver = cx.this_value().downcast_or_throw::<JsBox<NeonGuaradictDriver>, _>(&mut cx)?;
+        let index = cx.argument::<JsNumber>(0)?.value(&mut cx) as usize;
+        let (deferred, promise) = cx.promise();
+
+        let driver = Arc::clone(&neon_driver.inner);
+        let channel = cx.channel();
+        thread::spawn(move || {
+            let result = driver.cluster_status(index);
+            deferred.settle_with(&channel, move |mut cx| {
+                match result {
+                    Ok(nodes) => json_to_js(&mut cx, &nodes),
+                    Err(err) => cx.throw_error(err.to_string()),
+                }
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn js_publish(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let neon_driver = cx.this_value().downcast_or_throw::<JsBox<NeonGuaradictDriver>, _>(&mut cx)?;
+        let index = cx.argument::<JsNumber>(0)?.value(&mut cx) as usize;
+        let channel = cx.argument::<JsString>(1)?.value(&mut cx);
+        let message = cx.argument::<JsString>(2)?.value(&mut cx);
+        let (deferred, promise) = cx.promise();
+
+        let driver = Arc::clone(&neon_driver.inner);
+        let channel_js = cx.channel();
+        thread::spawn(move || {
+            let result = driver.publish(index, channel, message);
+            deferred.settle_with(&channel_js, move |mut cx| {
+                match result {
+                    Ok(receivers) => Ok(cx.number(receivers as f64).upcast::<JsValue>()),
+                    Err(err) => cx.throw_error(err.to_string()),
+                }
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn js_subscribe(cx: FunctionContext) -> JsResult<JsPromise> {
+        Self::subscribe_with(cx, "SUBSCRIBE")
+    }
+
+    fn js_psubscribe(cx: FunctionContext) -> JsResult<JsPromise> {
+        Self::subscribe_with(cx, "PSUBSCRIBE")
+    }
+
+    fn js_watch_keys(cx: FunctionContext) -> JsResult<JsPromise> {
+        Self::subscribe_with(cx, "WATCHKEY")
+    }
+
+    fn js_watch_prefixes(cx: FunctionContext) -> JsResult<JsPromise> {
+        Self::subscribe_with(cx, "WATCHPREFIX")
+    }
+
+    // Cada mensagem publicada chama o callback com { type, channel, data } e,
+    // em inscrições por padrão, também `pattern`. Alterações em chaves
+    // observadas chegam como { type: "change", kind, key, value, version }
+    fn subscribe_with<'a>(mut cx: FunctionContext<'a>, command: &'static str) -> JsResult<'a, JsPromise> {
+        let neon_driver = cx.this_value().downcast_or_throw::<JsBox<NeonGuaradictDriver>, _>(&mut cx)?;
+        let targets = cx.argument::<JsArray>(0)?;
+        let targets = string_array_from_js(&mut cx, targets)?;
+        let callback = Arc::new(cx.argument::<JsFunction>(1)?.root(&mut cx));
+        let (deferred, promise) = cx.promise();
+
+        let driver = Arc::clone(&neon_driver.inner);
+        let channel = cx.channel();
+        thread::spawn(move || {
+            let (stream, mut reader) = match driver.subscribe(command, &targets) {
+                Ok(subscription) => subscription,
+                Err(err) => {
+                    deferred.settle_with(&channel, move |mut cx| cx.throw_error::<_, Handle<JsValue>>(err.to_string()));
+                    return;
+                }
+            };
+
+            let subscription = stream.try_clone().map(|stream| Subscription { stream });
+            deferred.settle_with(&channel, move |mut cx| {
+                match subscription {
+                    Ok(subscription) => Ok(cx.boxed(subscription).upcast::<JsValue>()),
+                    Err(err) => cx.throw_error(err.to_string()),
+                }
+            });
+
+            // Encerra quando a inscrição é cancelada e a conexão fechada
+            while let Ok(line) = frame::read_blocking(&mut reader) {
+                let Ok(message) = serde_json::from_str::<serde_json::Value>(&line) else {
+                    continue;
+                };
+                if !matches!(message["type"].as_str(), Some("message" | "pmessage" | "change")) {
+                    continue;
+                }
+
+                let callback = Arc::clone(&callback);
+                channel.send(move |mut cx| {
+                    let js_message = message_to_js(&mut cx, &message)?;
+                    let callback = callback.to_inner(&mut cx);
+                    let this = cx.undefined();
+                    callback.call(&mut cx, this, vec![js_message.upcast::<JsValue>()])?;
+                    Ok(())
+                });
+            }
+        });
+
+        Ok(promise)
+    }
+
+    fn js_unsubscribe(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+        let subscription = cx.argument::<JsBox<Subscription>>(0)?;
+        let mut stream = &subscription.stream;
+        let _ = stream.write_all(b"QUIT\n");
+        let _ = stream.shutdown(Shutdown::Both);
+        Ok(cx.undefined())
+    }
 }
 
 #[neon::main]
@@ -283,5 +924,20 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
     cx.export_function("disconnect", NeonGuaradictDriver::js_disconnect)?;
     cx.export_function("set", NeonGuaradictDriver::js_set)?;
     cx.export_function("get", NeonGuaradictDriver::js_get)?;
+    cx.export_function("getWithVersion", NeonGuaradictDriver::js_get_with_version)?;
+    cx.export_function("cas", NeonGuaradictDriver::js_cas)?;
+    cx.export_function("getEntry", NeonGuaradictDriver::js_get_entry)?;
+    cx.export_function("addSense", NeonGuaradictDriver::js_add_sense)?;
+    cx.export_function("delSense", NeonGuaradictDriver::js_del_sense)?;
+    cx.export_function("mget", NeonGuaradictDriver::js_mget)?;
+    cx.export_function("mset", NeonGuaradictDriver::js_mset)?;
+    cx.export_function("mdel", NeonGuaradictDriver::js_mdel)?;
+    cx.export_function("clusterStatus", NeonGuaradictDriver::js_cluster_status)?;
+    cx.export_function("publish", NeonGuaradictDriver::js_publish)?;
+    cx.export_function("subscribe", NeonGuaradictDriver::js_subscribe)?;
+    cx.export_function("psubscribe", NeonGuaradictDriver::js_psubscribe)?;
+    cx.export_function("unsubscribe", NeonGuaradictDriver::js_unsubscribe)?;
+    cx.export_function("watchKeys", NeonGuaradictDriver::js_watch_keys)?;
+    cx.export_function("watchPrefixes", NeonGuaradictDriver::js_watch_prefixes)?;
     Ok(())
 }
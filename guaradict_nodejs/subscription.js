@@ -0,0 +1,60 @@
+"use strict";
+
+const { subscribe, psubscribe, watchKeys, watchPrefixes, unsubscribe } = require('./index.node');
+
+// Adapta a inscrição por callback para um iterador assíncrono, resolvido
+// quando o servidor confirma a inscrição. Encerrar o laço `for await`
+// cancela a inscrição e fecha a conexão dedicada.
+async function iterate(driver, targets, subscribeFn) {
+    const queue = [];
+    const waiting = [];
+    let done = false;
+
+    const subscription = await subscribeFn.call(driver, targets, (message) => {
+        const resolve = waiting.shift();
+        if (resolve) {
+            resolve({ value: message, done: false });
+        } else {
+            queue.push(message);
+        }
+    });
+
+    return {
+        [Symbol.asyncIterator]() {
+            return this;
+        },
+        async next() {
+            if (queue.length > 0) {
+                return { value: queue.shift(), done: false };
+            }
+            if (done) {
+                return { value: undefined, done: true };
+            }
+            return new Promise((resolve) => waiting.push(resolve));
+        },
+        async return() {
+            unsubscribe(subscription);
+            done = true;
+            waiting.splice(0).forEach((resolve) => resolve({ value: undefined, done: true }));
+            return { value: undefined, done: true };
+        },
+    };
+}
+
+function messages(driver, channels) {
+    return iterate(driver, channels, subscribe);
+}
+
+function patternMessages(driver, patterns) {
+    return iterate(driver, patterns, psubscribe);
+}
+
+function keyChanges(driver, keys) {
+    return iterate(driver, keys, watchKeys);
+}
+
+function prefixChanges(driver, prefixes) {
+    return iterate(driver, prefixes, watchPrefixes);
+}
+
+module.exports = { messages, patternMessages, keyChanges, prefixChanges };
@@ -1,10 +1,13 @@
 use std::collections::HashMap;
 use std::env;
-use tokio::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
 use tokio::net::TcpListener;
-use guaradict_core::Dictionary;
-use guaradict_core::replica::{LogOperator, ReplicaMonitorServer, ReplicaStatus, SynchronizerServer};
-use guaradict_core::config::parse_config_file;
+use std::time::Duration;
+use guaradict_core::{Dictionary, ScriptEngine};
+use guaradict_core::cluster::{ClusterNode, SlotMap};
+use guaradict_core::replica::{Acks, ConflictResolver, Election, FailoverMonitor, LocalIdentity, LogOperator, RaftNode, RaftServer, ReplicaMonitorServer, ReplicaStatus, ReplicationAcks, ReplicationServer, Role, SynchronizerServer};
+use guaradict_core::config::{bus_port, parse_config_file, Monitor};
 
 mod replica_sync;
 mod server_logic;
@@ -35,6 +38,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let (tx, rx) = mpsc::channel(10);
 
+    // O log de operações é consumido também pela replicação, se houver réplicas
+    let operations_log = LogOperator::new();
+    let synchronizer_server = SynchronizerServer::new(rx, operations_log);
+    let replica_count = config.replicas.as_ref().map_or(0, Vec::len);
+    let acks = Arc::new(ReplicationAcks::new(replica_count));
+
+    let role = if config.node_type == "primary" { Role::Primary } else { Role::Replica };
+    let leader = config.primary.as_ref().map(|primary| primary.name.clone());
+    let failover_timeout = config.failover.as_ref().and_then(|failover| failover.timeout).unwrap_or(3000);
+    let election = Arc::new(Mutex::new(Election::new(config.name.clone(), role, leader, Duration::from_millis(failover_timeout))));
+    let identity = LocalIdentity::new(config.database.clone(), config.cluster_id.clone(), election.clone());
+
+    let mut replica_statuses = None;
     if let Some(replicas) = config.replicas {
         // @TODO: mover para o construtor do ReplicaMonitorServer
         let replicas = replicas
@@ -42,34 +58,135 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .map(|r| (r.name.to_string(), ReplicaStatus::from(r.clone())))
             .collect::<HashMap<String, ReplicaStatus>>();
 
-        let operations_log = LogOperator::new();
+        let replication_server = ReplicationServer::new(
+            replicas.values().cloned().collect(),
+            synchronizer_server.operations_log(),
+            synchronizer_server.subscribe_offset(),
+            acks.clone(),
+            election.clone(),
+            identity.clone(),
+        );
+        let replica_monitor_server = ReplicaMonitorServer::new(
+            replicas,
+            identity.clone(),
+            synchronizer_server.operations_log(),
+            synchronizer_server.subscribe_offset(),
+            config.monitor.as_ref().map(Monitor::settings).unwrap_or_default(),
+        );
+        replica_statuses = Some(replica_monitor_server.replicas());
 
-        let replica_monitor_server = ReplicaMonitorServer::new(replicas);
-        let synchronizer_server = SynchronizerServer::new(rx, operations_log);
-
-        // Spawna a tarefa para monitorar o ping das réplicas
+        // Registra as mudanças de estado das réplicas
+        let mut events = replica_monitor_server.subscribe();
         tokio::spawn(async move {
-            replica_monitor_server.start().await;
+            loop {
+                match events.recv().await {
+                    Ok(event) => println!("Réplica {}: {} -> {}", event.name, event.from, event.to),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
         });
 
-        // Spawna a tarefa para sincronizar as replicas
+        // Spawna a tarefa para monitorar o ping das réplicas
         tokio::spawn(async move {
-            synchronizer_server.start().await;
+            replica_monitor_server.start().await;
         });
 
-        // replica_sync::start(replica_statuses.clone(), dictionary.clone()).await;
+        // Spawna as conexões que enviam o log de operações às réplicas
+        replication_server.start().await;
     } else {
         println!("Nenhuma réplica encontrada na configuração.");
     }
 
+    // Numa réplica com failover, monitora o primário e se candidata se ele cair
+    if config.failover.is_some() {
+        let nodes = config
+            .primary
+            .iter()
+            .chain(config.peers.iter().flatten())
+            .map(|node| ReplicaStatus::from(node.clone()))
+            .collect();
+        let failover_monitor = FailoverMonitor::new(
+            nodes,
+            election.clone(),
+            identity.clone(),
+            synchronizer_server.operations_log(),
+            synchronizer_server.subscribe_offset(),
+            acks.clone(),
+        );
+        tokio::spawn(async move {
+            failover_monitor.start().await;
+        });
+    }
+
+    // Spawna a tarefa que consome as operações do dicionário para o log
+    tokio::spawn(async move {
+        synchronizer_server.start().await;
+    });
+
     let addr = format!("{}:{}", config.ip, config.port);
     let listener = TcpListener::bind(&addr).await?;
-    let dictionary = Dictionary::new();
-    let client_server = server_logic::ServerLogic::new(tx, dictionary);
+    // Os demais nós se conectam pelo barramento, separado da porta dos clientes
+    let bus_addr = format!("{}:{}", config.ip, bus_port(config.port, config.bus_port));
+    let bus_listener = TcpListener::bind(&bus_addr).await?;
+    let mut dictionary = if config.search_index.unwrap_or(false) {
+        Dictionary::with_search_index()
+    } else {
+        Dictionary::new()
+    };
+    dictionary.set_node(&config.name);
+    let scripts = ScriptEngine::new(Duration::from_millis(config.script_timeout.unwrap_or(5000)));
+    let (write_concern, ack_timeout) = config
+        .write_concern
+        .map_or((Acks::None, None), |write_concern| (write_concern.acks, write_concern.timeout));
+    let mut client_server = server_logic::ServerLogic::new(tx, dictionary, scripts, election)
+        .with_identity(identity.clone())
+        .with_write_concern(acks, write_concern, Duration::from_millis(ack_timeout.unwrap_or(1000)));
+
+    if let Some(replicas) = replica_statuses {
+        client_server = client_server.with_replicas(replicas, config.max_lag);
+    }
+
+    if let Some(multi_primary) = config.multi_primary {
+        let resolution = multi_primary.conflict_resolution.unwrap_or_default();
+        client_server = client_server.with_multi_primary(ConflictResolver::new(&config.name, resolution));
+    }
+
+    // No modo cluster cada primário atende só as chaves dos seus slots
+    if let Some(cluster) = config.cluster {
+        let local = ClusterNode { name: config.name.clone(), addr: addr.clone(), bus: bus_addr.clone() };
+        let nodes = std::iter::once((local, cluster.slots))
+            .chain(cluster.nodes.unwrap_or_default().into_iter().map(|node| {
+                let addr = format!("{}:{}", node.ip, node.port);
+                let bus = format!("{}:{}", node.ip, node.bus_port());
+                (ClusterNode { name: node.name, addr, bus }, node.slots)
+            }))
+            .collect();
+        client_server = client_server.with_cluster(SlotMap::new(&config.name, nodes)?);
+    }
+
+    // No modo Raft o nó e seus peers elegem o líder e confirmam as escritas por maioria
+    if let Some(raft) = config.raft {
+        let peers = config.peers.unwrap_or_default();
+        let members = std::iter::once(config.name.clone())
+            .chain(peers.iter().map(|peer| peer.name.clone()))
+            .collect::<Vec<String>>();
+        let node = RaftNode::new(&config.name, &members, raft.election_ticks.unwrap_or(10), raft.heartbeat_ticks.unwrap_or(2));
+        let (raft_server, entries) = RaftServer::new(node, bus_addr.clone(), identity);
+
+        for peer in peers {
+            if let Some(peer_addr) = ReplicaStatus::from(peer.clone()).bus {
+                raft_server.connect(&peer.name, peer_addr);
+            }
+        }
+        raft_server.start(Duration::from_millis(raft.tick.unwrap_or(100)));
+
+        client_server = client_server.with_raft(raft_server, entries);
+    }
 
-    // Spawna a tarefa para servir comaandos para os clients (e PING PONG heartbeat)
+    // Spawna a tarefa para servir comandos para os clients e, pelo barramento, para os demais nós
     let _ = tokio::spawn(async move {
-        client_server.start(listener).await;
+        client_server.start(listener, bus_listener).await;
     }).await;
 
     Ok(())
@@ -7,16 +7,18 @@ use guaradict_core::replica::ReplicaStatus;
 
 pub async fn _start(replica_statuses: Arc<Mutex<HashMap<String, ReplicaStatus>>>, _dictionary: Arc<Mutex<Dictionary>>) {
     loop {
-        // Obtém uma cópia do mapa de status das réplicas
-        let replica_statuses = replica_statuses.lock().unwrap();
+        {
+            // Obtém uma cópia do mapa de status das réplicas
+            let replica_statuses = replica_statuses.lock().unwrap();
 
-        // Itera sobre cada réplica e executa a sincronização delta
-        for (_, status) in replica_statuses.iter() {
-            // Verifica se a réplica está pronta e se possui um socket ativo
-            if status.ready && status.addr.is_some() {
-                // Execute a lógica de sincronização delta usando o socket ativo da réplica
-                // Você pode implementar essa lógica aqui
-                // Exemplo: enviar atualizações delta para a réplica
+            // Itera sobre cada réplica e executa a sincronização delta
+            for (_, status) in replica_statuses.iter() {
+                // Verifica se a réplica está pronta e se possui um socket ativo
+                if status.ready && status.addr.is_some() {
+                    // Execute a lógica de sincronização delta usando o socket ativo da réplica
+                    // Você pode implementar essa lógica aqui
+                    // Exemplo: enviar atualizações delta para a réplica
+                }
             }
         }
 
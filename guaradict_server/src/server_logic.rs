@@ -1,26 +1,175 @@
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::mpsc::Sender;
-use tokio::sync::Mutex;
+use tokio::sync::mpsc::{self, Sender, UnboundedReceiver};
+use tokio::sync::{Mutex, Notify};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use guaradict_core::{commands::client, Dictionary};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::time::{Duration, Instant};
+use guaradict_core::{commands::{client, frame, server}, Dictionary, ListSide, PubSub, ScriptEngine, Sense, Transaction, Value};
+use guaradict_core::cluster::{key_slot, ClusterNode, NodeRole, SlotMap, TopologyNode, CLUSTER_DISABLED};
+use guaradict_core::errors::{ClusterError, DictionaryError, FailoverError, HandshakeError, RaftError, TransactionError};
+use guaradict_core::metrics::Metrics;
+use guaradict_core::replica::{read_line, read_line_up_to, Acks, ConflictResolver, LocalIdentity, MaxLag, NodeIdentity, Operation, OperationKind, RaftEntry, RaftPayload, RaftServer, ReplicaStatus, ReplicationAcks, ReplicationMessage, Role, SharedElection};
 
-pub struct ServerLogic {
-    tx: Arc<Mutex<Sender<client::Command>>>,
+// Tempo máximo para copiar as chaves de um MIGRATE para o destino
+const MIGRATE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Métrica de cada réplica no METRICS: nome, descrição e valor
+type ReplicaGauge = (&'static str, &'static str, fn(&ReplicaStatus) -> f64);
+
+/// Estado compartilhado por todas as conexões.
+#[derive(Clone)]
+struct Context {
     dictionary: Arc<Mutex<Dictionary>>,
+    tx: Sender<Vec<Operation>>,
+    // Acorda os clientes bloqueados em BLPOP/BRPOP quando uma lista recebe valores
+    list_pushed: Arc<Notify>,
+    pubsub: Arc<Mutex<PubSub>>,
+    scripts: Arc<Mutex<ScriptEngine>>,
+    // Confirmações das réplicas e quantas delas cada escrita exige por padrão
+    acks: Arc<ReplicationAcks>,
+    write_concern: Acks,
+    ack_timeout: Duration,
+    // Papel e termo do nó, para votar e reconhecer o primário atual
+    election: SharedElection,
+    // Identidade apresentada aos demais nós no HELLO
+    identity: LocalIdentity,
+    raft: Option<Arc<RaftState>>,
+    multi_primary: Option<Arc<MultiPrimaryState>>,
+    // Dono de cada slot, no modo cluster
+    cluster: Option<Arc<std::sync::Mutex<SlotMap>>>,
+    // Réplicas deste primário, com o ping e o atraso medidos pelo monitor
+    replicas: Option<Arc<Mutex<HashMap<String, ReplicaStatus>>>>,
+    // Atraso acima do qual uma réplica deixa de receber leituras
+    max_lag: Option<MaxLag>,
+    // Endereços em que este nó atende os clientes e o barramento
+    addr: Option<SocketAddr>,
+    bus: Option<SocketAddr>,
+}
+
+/// Estado do modo multi-primário.
+struct MultiPrimaryState {
+    resolver: std::sync::Mutex<ConflictResolver>,
+    // Versão, no log de cada primário, da última operação recebida dele
+    received: std::sync::Mutex<HashMap<String, u64>>,
+}
+
+/// Estado do modo Raft. O líder aplica as próprias escritas ao propô-las e
+/// só responde depois que a maioria as confirma; as demais entradas são
+/// aplicadas quando confirmadas.
+struct RaftState {
+    server: RaftServer,
+    // Entradas propostas por este nó e já aplicadas ao dicionário: índice → termo
+    proposed: std::sync::Mutex<HashMap<u64, u64>>,
+    // Escritas aguardando confirmação: versão → (índice, termo)
+    writes: std::sync::Mutex<HashMap<u64, (u64, u64)>>,
+    // Índice da última entrada confirmada já aplicada
+    applied: AtomicU64,
+}
+
+pub struct ServerLogic {
+    context: Context,
 }
 
 impl ServerLogic {
-    pub fn new(tx: Sender<client::Command>, dictionary: Dictionary) -> Self {
+    pub fn new(tx: Sender<Vec<Operation>>, dictionary: Dictionary, scripts: ScriptEngine, election: SharedElection) -> Self {
         Self {
-            tx: Arc::new(Mutex::new(tx)),
-            dictionary: Arc::new(Mutex::new(dictionary)),
+            context: Context {
+                dictionary: Arc::new(Mutex::new(dictionary)),
+                tx,
+                list_pushed: Arc::new(Notify::new()),
+                pubsub: Arc::new(Mutex::new(PubSub::new())),
+                scripts: Arc::new(Mutex::new(scripts)),
+                acks: Arc::new(ReplicationAcks::new(0)),
+                write_concern: Acks::None,
+                ack_timeout: Duration::from_secs(1),
+                identity: LocalIdentity::new(None, None, election.clone()),
+                election,
+                raft: None,
+                multi_primary: None,
+                cluster: None,
+                replicas: None,
+                max_lag: None,
+                addr: None,
+                bus: None,
+            },
         }
     }
 
-    pub async fn start(&self, listener: TcpListener) {
+    /// Faz as escritas esperarem, por padrão, as confirmações `write_concern`
+    /// das réplicas por até `timeout`.
+    pub fn with_write_concern(mut self, acks: Arc<ReplicationAcks>, write_concern: Acks, timeout: Duration) -> Self {
+        self.context.acks = acks;
+        self.context.write_concern = write_concern;
+        self.context.ack_timeout = timeout;
+        self
+    }
+
+    /// Identidade conferida no HELLO dos demais nós, no lugar de uma sem
+    /// base de dados nem cluster.
+    pub fn with_identity(mut self, identity: LocalIdentity) -> Self {
+        self.context.identity = identity;
+        self
+    }
+
+    /// Aceita escritas de outros primários, resolvendo os conflitos com
+    /// `resolver`. As escritas locais passam a ser carimbadas.
+    pub fn with_multi_primary(mut self, resolver: ConflictResolver) -> Self {
+        self.context.multi_primary = Some(Arc::new(MultiPrimaryState {
+            resolver: std::sync::Mutex::new(resolver),
+            received: std::sync::Mutex::new(HashMap::new()),
+        }));
+        self
+    }
+
+    /// Passa a atender só as chaves dos slots deste nó, redirecionando as
+    /// demais ao dono com `MOVED`.
+    pub fn with_cluster(mut self, slots: SlotMap) -> Self {
+        self.context.cluster = Some(Arc::new(std::sync::Mutex::new(slots)));
+        self
+    }
+
+    /// Informa no ROLE as réplicas acompanhadas pelo monitor, que os
+    /// drivers usam para mandar leituras às réplicas. As réplicas acima de
+    /// `max_lag` são marcadas para não receber leituras.
+    pub fn with_replicas(mut self, replicas: Arc<Mutex<HashMap<String, ReplicaStatus>>>, max_lag: Option<MaxLag>) -> Self {
+        self.context.replicas = Some(replicas);
+        self.context.max_lag = max_lag;
+        self
+    }
+
+    /// Passa a replicar as escritas pelo Raft. As entradas confirmadas
+    /// chegam por `entries` e vão para o dicionário e para o log.
+    pub fn with_raft(mut self, server: RaftServer, entries: UnboundedReceiver<RaftEntry>) -> Self {
+        self.context.raft = Some(Arc::new(RaftState {
+            server,
+            proposed: std::sync::Mutex::new(HashMap::new()),
+            writes: std::sync::Mutex::new(HashMap::new()),
+            applied: AtomicU64::new(0),
+        }));
+
+        let context = self.context.clone();
+        tokio::spawn(async move {
+            Self::apply_committed(entries, context).await;
+        });
+        self
+    }
+
+    /// Atende os clientes em `listener` e os demais nós do cluster em `bus`.
+    pub async fn start(&self, listener: TcpListener, bus: TcpListener) {
         println!("Servidor ouvindo em {:?}", listener.local_addr());
-        let tx = self.tx.as_ref().lock().await;
+        println!("Barramento do cluster ouvindo em {:?}", bus.local_addr());
+
+        let mut context = self.context.clone();
+        context.addr = listener.local_addr().ok();
+        context.bus = bus.local_addr().ok();
+
+        let bus_context = context.clone();
+        tokio::spawn(async move {
+            Self::serve_bus(bus, bus_context).await;
+        });
 
         // Loop principal para lidar com conexões de clientes
         loop {
@@ -28,61 +177,245 @@ impl ServerLogic {
 
             println!("Nova conexão {} {}", socket.peer_addr().unwrap().ip(), socket.peer_addr().unwrap().port());
 
-            let dictionary = self.dictionary.clone();
-            let tx = tx.clone();
+            let context = context.clone();
 
             // Lidar com o cliente em uma nova tarefa
             tokio::spawn(async move {
-                if let Err(e) = Self::handle_client(socket, dictionary, tx).await {
+                if let Err(e) = Self::handle_client(socket, context).await {
                     eprintln!("Error handling client: {}", e);
                 }
             });
         }
     }
 
-    async fn handle_client(mut socket: TcpStream, dictionary: Arc<Mutex<Dictionary>>, tx: Sender<client::Command>) -> Result<(), Box<dyn std::error::Error>> {
-        let mut buffer = [0; 1024];
-        let dictionary = dictionary.clone();
+    async fn serve_bus(bus: TcpListener, context: Context) {
+        loop {
+            let (socket, peer_addr) = match bus.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    eprintln!("Falha ao aceitar conexão no barramento: {}", e);
+                    continue;
+                }
+            };
 
-        while let Ok(n) = socket.read(&mut buffer).await {
-            let request = String::from_utf8_lossy(&buffer[..n]);
+            println!("Nova conexão no barramento {} {}", peer_addr.ip(), peer_addr.port());
 
-            if n == 0 {
-                continue;
-            }
+            let context = context.clone();
+            tokio::spawn(async move {
+                if let Err(e) = Self::handle_peer(socket, context).await {
+                    eprintln!("Erro na conexão com o nó: {}", e);
+                }
+            });
+        }
+    }
 
-            if request.trim() == "QUIT" {
-                // Fechar a conexão com o cliente e sair da função
-                return Ok(());
+    /// Conexão de outro nó pelo barramento. O nó se identifica com HELLO e
+    /// só então pode mandar heartbeats, abrir a replicação ou o Raft, pedir
+    /// votos e copiar as chaves de um slot migrado com RESTORE.
+    async fn handle_peer(mut socket: TcpStream, context: Context) -> Result<(), Box<dyn std::error::Error>> {
+        // Nó do outro lado, depois do HELLO
+        let mut peer: Option<NodeIdentity> = None;
+
+        loop {
+            // Linha a linha, sem ler adiante: a conexão pode passar à
+            // replicação ou ao Raft, que seguem mandando logo depois
+            let read = match peer {
+                Some(_) => read_line_up_to(&mut socket, frame::MAX_FRAME).await,
+                None => read_line(&mut socket).await,
+            };
+            let Ok(request) = read else {
+                break;
+            };
+
+            let command = match server::Command::parse(&request) {
+                Ok(server::Command::Hello(identity)) => {
+                    let (reply, accepted) = match context.identity.accept(&identity) {
+                        Ok(welcome) => (welcome, true),
+                        Err(denied) => (denied, false),
+                    };
+                    socket.write_all(format!("{}\n", reply.serialize()).as_bytes()).await?;
+                    if !accepted {
+                        println!("Conexão do nó {} recusada: {}", identity.name, reply.serialize());
+                        break;
+                    }
+                    peer = Some(identity);
+                    continue;
+                }
+                _ if peer.is_none() => {
+                    let denied = server::Command::Denied(HandshakeError::Required.to_string());
+                    socket.write_all(format!("{}\n", denied.serialize()).as_bytes()).await?;
+                    println!("{} recusado: conexão sem HELLO", request);
+                    break;
+                }
+                Ok(command) => command,
+                Err(_) => {
+                    let response = match client::Command::parse(&request) {
+                        Ok(client::Command::Quit) => break,
+                        // Chaves de um slot migrado para este nó, e as apagadas durante a migração
+                        Ok(command @ (client::Command::Restore(..) | client::Command::Del(_))) => {
+                            Self::execute_in_transaction(command, true, &mut Transaction::new(), &mut 0, &context).await
+                        }
+                        _ => "Invalid command".to_string(),
+                    };
+                    socket.write_all(format!("{}\n", response).as_bytes()).await?;
+                    continue;
+                }
+            };
+
+            match command {
+                // Heartbeat do monitor: responde com o nome e a versão aplicada
+                server::Command::Ping(..) => {
+                    let name = context.election.lock().unwrap().name().to_string();
+                    let revision = context.dictionary.lock().await.revision();
+                    let pong = server::Command::Pong(name, revision);
+                    socket.write_all(format!("{}\n", pong.serialize()).as_bytes()).await?;
+                }
+                // O primário passa a usar a conexão só para enviar operações
+                server::Command::Sync(term, leader) => {
+                    if let Some(multi_primary) = &context.multi_primary {
+                        return Self::receive_from_primary(socket, leader, multi_primary, &context).await;
+                    }
+                    return Self::receive_replication(socket, term, leader, &context).await;
+                }
+                // Um membro do Raft passa a usar a conexão só para enviar mensagens
+                server::Command::Raft(from, addr) if context.raft.is_some() => {
+                    let raft = context.raft.as_ref().unwrap();
+                    raft.server.receive(&from, &addr, socket).await?;
+                    return Ok(());
+                }
+                server::Command::RequestVote(term, candidate, offset) => {
+                    let vote = Self::vote(term, &candidate, offset, &context).await;
+                    socket.write_all(format!("{}\n", vote.serialize()).as_bytes()).await?;
+                }
+                command => {
+                    println!("Comando inesperado de {}: {}", peer.as_ref().map_or("", |peer| &peer.name), command.serialize());
+                    socket.write_all(b"Invalid command\n").await?;
+                }
             }
+        }
 
-            if request.trim() == "PING" {
-                if let Err(e) = socket.write_all(b"PONG\n").await {
-                    eprintln!("Falhano PONG: {}", e);
-                } else {
-                    println!("< PONG");
+        Ok(())
+    }
+
+    async fn handle_client(mut socket: TcpStream, context: Context) -> Result<(), Box<dyn std::error::Error>> {
+        // Um comando por linha; as respostas vão com `frame::encode`
+        let (reader, mut writer) = socket.split();
+        let mut lines = BufReader::new(reader).lines();
+        let mut transaction = Transaction::new();
+        // Versão da última escrita feita pela conexão, usada pelo WAIT
+        let mut last_write = 0;
+        // O comando anterior foi ASKING: o próximo pode usar um slot sendo importado
+        let mut asking = false;
+
+        // Mensagens publicadas nos canais em que a conexão está inscrita
+        let (messages_tx, mut messages) = mpsc::unbounded_channel();
+        let subscriber = context.pubsub.lock().await.register(messages_tx);
+
+        loop {
+            let request = tokio::select! {
+                line = lines.next_line() => match line {
+                    Ok(Some(line)) => line,
+                    Ok(None) | Err(_) => break,
+                },
+                Some(message) = messages.recv() => {
+                    if let Err(e) = writer.write_all(frame::encode(&message.to_line()).as_bytes()).await {
+                        eprintln!("Falha ao enviar mensagem publicada: {}", e);
+                        break;
+                    }
+                    continue;
                 }
-                continue;
+            };
+
+            let asked = std::mem::take(&mut asking);
+
+            if request.trim() == "QUIT" {
+                // Fechar a conexão com o cliente e sair da função
+                break;
             }
 
-            // @TODO Refatorar usar frame com header e payload
-            // @TODO Refatorar para não responder ping de replica com "Invalid command"
-            let tx = tx.clone();
-            let dictionary = dictionary.clone();
             let response = match client::Command::parse(request.trim()) {
-                Ok(command) => {
-                    match command {
-                        client::Command::Add(key, value) => Self::add_entry(key, value, dictionary, tx).await,
-                        client::Command::Set(key, value) => Self::add_entry(key, value, dictionary, tx).await,
-                        client::Command::Get(key) => Self::get_definition(key, dictionary, tx).await,
-                        client::Command::Del(key) => Self::remove_entry(key, dictionary, tx).await,
-                        _ => "Invalid command".to_string(),
+                Ok(client::Command::Ping) => "PONG".to_string(),
+                Ok(command) if Self::is_subscription(&command) && !transaction.is_active() => {
+                    Self::subscription(command, subscriber, &context.pubsub).await
+                }
+                Ok(_) if context.pubsub.lock().await.subscription_count(subscriber) > 0 => {
+                    "Only (P)SUBSCRIBE, (P)UNSUBSCRIBE, WATCHKEY, UNWATCHKEY, WATCHPREFIX, UNWATCHPREFIX, PING and QUIT are allowed while subscribed".to_string()
+                }
+                Ok(client::Command::Publish(channel, message)) if !transaction.is_active() => {
+                    context.pubsub.lock().await.publish(&channel, &message).to_string()
+                }
+                // O WAIT não entra na transação: espera pelas escritas já feitas
+                Ok(client::Command::Wait(replicas, timeout)) => {
+                    let timeout = (timeout > 0).then(|| Duration::from_millis(timeout));
+                    context.acks.wait_for(last_write, replicas, timeout).await.to_string()
+                }
+                Ok(command @ (client::Command::RaftAdd(..) | client::Command::RaftRemove(_) | client::Command::RaftStatus)) => {
+                    Self::raft_command(command, &context)
+                }
+                Ok(client::Command::Asking) => {
+                    asking = true;
+                    "OK".to_string()
+                }
+                Ok(
+                    command @ (client::Command::ClusterKeySlot(_)
+                    | client::Command::ClusterSlots
+                    | client::Command::ClusterNodes
+                    | client::Command::ClusterSetSlot(..)
+                    | client::Command::ClusterGetKeysInSlot(..)
+                    | client::Command::ClusterCountKeysInSlot(_)),
+                ) => Self::cluster_command(command, &context).await,
+                Ok(client::Command::Conflicts) => match &context.multi_primary {
+                    Some(multi_primary) => {
+                        let resolver = multi_primary.resolver.lock().unwrap();
+                        serde_json::to_string(&resolver.conflicts().collect::<Vec<_>>()).unwrap()
                     }
+                    None => "Multi-primary is not enabled".to_string(),
                 },
-                Err(_) => "Invalid command".to_string(),
+                Ok(client::Command::Role) => Self::role(&context).await,
+                Ok(client::Command::Lag) => Self::lag(&context).await,
+                Ok(client::Command::ClusterStatus) => Self::cluster_status(&context).await,
+                Ok(client::Command::Metrics) => Self::metrics(&context).await,
+                Ok(command) => {
+                    let (command, acks) = match command {
+                        client::Command::Acks(acks, command) => (*command, acks),
+                        command => (command, context.write_concern),
+                    };
+
+                    match &context.raft {
+                        // O RESTORE da migração de slots só é aceito pelo barramento, depois do HELLO
+                        _ if matches!(command, client::Command::Restore(..)) => {
+                            transaction.fail();
+                            "Invalid command".to_string()
+                        }
+                        // No Raft só o líder aceita escritas
+                        Some(raft) if !command.is_read_only() && !raft.server.is_leader() => {
+                            transaction.fail();
+                            RaftError::NotLeader(raft.server.leader()).to_string()
+                        }
+                        // Fora do Raft só o primário eleito aceita escritas; as de uma
+                        // réplica seriam perdidas no próximo SYNC
+                        None if !command.is_read_only() && context.election.lock().unwrap().role() == Role::Replica => {
+                            transaction.fail();
+                            FailoverError::NotPrimary(context.election.lock().unwrap().leader().map(str::to_string)).to_string()
+                        }
+                        raft => {
+                            let previous_write = last_write;
+                            let response = Self::execute_in_transaction(command, asked, &mut transaction, &mut last_write, &context).await;
+                            match raft {
+                                Some(raft) if last_write > previous_write => Self::wait_for_commit(response, last_write, raft, &context).await,
+                                None if last_write > previous_write => Self::wait_for_acks(response, acks, last_write, &context).await,
+                                _ => response,
+                            }
+                        }
+                    }
+                }
+                Err(_) => {
+                    transaction.fail();
+                    "Invalid command".to_string()
+                }
             };
 
-            if let Err(e) = socket.write_all(response.as_bytes()).await {
+            if let Err(e) = writer.write_all(frame::encode(&response).as_bytes()).await {
                 eprintln!("Falha na resposta: {}", e);
             } else {
                 // Log da resposta enviada
@@ -90,76 +423,1035 @@ impl ServerLogic {
             }
         }
 
+        context.pubsub.lock().await.unregister(subscriber);
+
         Ok(())
     }
 
-    // @TODO add deve verificar se existe antes, set deve ser o update
-    async fn add_entry(key: String, value: String, dictionary: Arc<Mutex<Dictionary>>, tx: Sender<client::Command>) -> String {
-        let mut dictionary = dictionary.as_ref().lock().await;
-        dictionary.add_entry(key.to_string(), value.to_string());
+    async fn vote(term: u64, candidate: &str, offset: u64, context: &Context) -> server::Command {
+        let revision = context.dictionary.lock().await.revision();
+        let mut election = context.election.lock().unwrap();
+        let granted = election.request_vote(term, candidate, offset, revision);
+        println!("Voto para {} no termo {}: {}", candidate, term, granted);
 
-        match dictionary.get_definition(&key) {
-            Some(_prev_value) => {
-                // @TODO cria enum propriio para log de operaçoes para substituir o client::Command
-                match tx.send(client::Command::Set(key.to_string(), value.to_string())).await {
-                    Ok(_) => {},
-                    Err(e) => {
-                        println!("Erro Command::Add: {}", e)
-                    }
+        server::Command::Vote(election.term(), granted)
+    }
+
+    /// Lado da réplica: aplica os lotes enviados pelo primário e confirma a
+    /// versão aplicada após cada um. As operações aplicadas seguem pelo
+    /// mesmo caminho das escritas locais (log e notificações de alteração).
+    /// Um primário de termo desatualizado é recusado.
+    async fn receive_replication(socket: TcpStream, term: u64, leader: String, context: &Context) -> Result<(), Box<dyn std::error::Error>> {
+        let (reader, mut writer) = socket.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        let rejected = {
+            let mut election = context.election.lock().unwrap();
+            (!election.accept_leader(term, &leader)).then(|| ReplicationMessage::Rejected {
+                term: election.term(),
+                leader: election.leader().map(str::to_string),
+            })
+        };
+        if let Some(rejected) = rejected {
+            println!("Replicação de {} recusada: termo {} desatualizado", leader, term);
+            writer.write_all(rejected.to_line().as_bytes()).await?;
+            return Ok(());
+        }
+
+        println!("Recebendo replicação de {} no termo {}", leader, term);
+
+        let offset = context.dictionary.lock().await.revision();
+        writer.write_all(ReplicationMessage::Ack { offset }.to_line().as_bytes()).await?;
+
+        while let Some(line) = lines.next_line().await? {
+            let ReplicationMessage::Batch { operations } = ReplicationMessage::from_line(&line)? else {
+                continue;
+            };
+
+            // Deixa de aplicar se outro primário foi reconhecido nesse meio tempo
+            {
+                let mut election = context.election.lock().unwrap();
+                if election.term() != term || election.leader() != Some(leader.as_str()) {
+                    break;
+                }
+                election.touch();
+            }
+
+            let mut dictionary = context.dictionary.lock().await;
+            for operation in &operations {
+                dictionary.apply(operation);
+            }
+            let offset = dictionary.revision();
+            Self::replicate(&mut dictionary, context).await;
+            drop(dictionary);
+
+            writer.write_all(ReplicationMessage::Ack { offset }.to_line().as_bytes()).await?;
+        }
+
+        println!("Replicação de {} encerrada", leader);
+
+        Ok(())
+    }
+
+    /// Recebe as escritas de outro primário. Ele é sempre aceito, sem termo:
+    /// cada escrita passa pela resolução de conflitos, e as vencedoras são
+    /// aplicadas e seguem no log deste nó para as suas réplicas. As
+    /// confirmações usam as versões do log do primário que enviou.
+    async fn receive_from_primary(socket: TcpStream, primary: String, multi_primary: &MultiPrimaryState, context: &Context) -> Result<(), Box<dyn std::error::Error>> {
+        let (reader, mut writer) = socket.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        println!("Recebendo escritas do primário {}", primary);
+
+        let offset = multi_primary.received.lock().unwrap().get(&primary).copied().unwrap_or(0);
+        writer.write_all(ReplicationMessage::Ack { offset }.to_line().as_bytes()).await?;
+
+        while let Some(line) = lines.next_line().await? {
+            let ReplicationMessage::Batch { operations } = ReplicationMessage::from_line(&line)? else {
+                continue;
+            };
+            let Some(offset) = operations.last().map(|operation| operation.version) else {
+                continue;
+            };
+
+            let mut dictionary = context.dictionary.lock().await;
+            let merged = multi_primary.resolver.lock().unwrap().merge(&operations, &mut dictionary);
+            if !merged.is_empty() {
+                Self::publish(&dictionary, merged, context).await;
+            }
+            drop(dictionary);
+
+            multi_primary.received.lock().unwrap().insert(primary.clone(), offset);
+            writer.write_all(ReplicationMessage::Ack { offset }.to_line().as_bytes()).await?;
+        }
+
+        println!("Escritas do primário {} encerradas", primary);
+
+        Ok(())
+    }
+
+    // Segura a resposta de uma escrita até as réplicas exigidas confirmarem.
+    // Se o tempo acabar, a escrita continua valendo no primário, mas o
+    // cliente recebe um erro dizendo quantas réplicas confirmaram
+    async fn wait_for_acks(response: String, acks: Acks, offset: u64, context: &Context) -> String {
+        let replicas = context.acks.replica_count();
+        let required = acks.required(replicas);
+        if required == 0 {
+            return response;
+        }
+        if required > replicas {
+            return format!("Not enough replicas: {} acknowledgements required, {} replicas configured", required, replicas);
+        }
+
+        let acked = context.acks.wait_for(offset, required, Some(context.ack_timeout)).await;
+        if acked >= required {
+            response
+        } else {
+            format!("Write acknowledged by {} of {} replicas before timeout", acked, required)
+        }
+    }
+
+    // Segura a resposta de uma escrita no Raft até a maioria do cluster
+    // confirmá-la. Uma escrita não confirmada pode ainda ser aplicada, se o
+    // líder seguinte a tiver recebido
+    async fn wait_for_commit(response: String, offset: u64, raft: &RaftState, context: &Context) -> String {
+        let proposal = raft.writes.lock().unwrap().remove(&offset);
+        match proposal {
+            Some((index, term)) if raft.server.wait_commit(index, term, context.ack_timeout).await => response,
+            _ => "Write not committed by a majority of the cluster".to_string(),
+        }
+    }
+
+    // Verifica, com o dicionário travado, se as chaves são deste nó. Assim
+    // nenhuma chave muda de nó entre a verificação e a execução do comando
+    fn route<'a, I>(keys: I, asking: bool, dictionary: &Dictionary, context: &Context) -> Result<(), ClusterError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        match &context.cluster {
+            Some(cluster) => cluster.lock().unwrap().route(keys, asking, |key| dictionary.get(key).is_some()),
+            None => Ok(()),
+        }
+    }
+
+    // Papel do nó e, num primário, as réplicas como o monitor as vê
+    async fn role(context: &Context) -> String {
+        let (role, name, leader) = {
+            let election = context.election.lock().unwrap();
+            (election.role(), election.name().to_string(), election.leader().map(str::to_string))
+        };
+
+        let mut replicas = Vec::new();
+        if let Some(statuses) = &context.replicas {
+            for replica in statuses.lock().await.values() {
+                let Some(addr) = replica.addr else {
+                    continue;
                 };
+                replicas.push(TopologyNode {
+                    name: replica.name.clone(),
+                    addr: addr.to_string(),
+                    ping: replica.ping.as_secs_f64() * 1000.0,
+                    ready: replica.ready,
+                    lag: replica.lag,
+                    lagging: Self::is_lagging(replica, &context.max_lag),
+                });
+            }
+        }
+        replicas.sort_by(|a, b| a.name.cmp(&b.name));
+
+        serde_json::to_string(&NodeRole { role, name, leader, replicas }).unwrap()
+    }
+
+    /// Este nó e as réplicas acompanhadas pelo monitor, com o papel, os
+    /// endereços, o ping, as falhas, a versão aplicada e o atraso de cada
+    /// um, para o CLUSTER STATUS.
+    async fn cluster_status(context: &Context) -> String {
+        let (name, role) = {
+            let election = context.election.lock().unwrap();
+            (election.name().to_string(), election.role())
+        };
+        let mut local = ReplicaStatus::new(name, true, context.addr);
+        local.role = Some(role);
+        local.bus = context.bus;
+        local.offset = context.dictionary.lock().await.revision();
+
+        let mut replicas = match &context.replicas {
+            Some(statuses) => statuses.lock().await.values().cloned().collect(),
+            None => Vec::new(),
+        };
+        replicas.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let nodes = std::iter::once(local).chain(replicas).collect::<Vec<ReplicaStatus>>();
+        serde_json::to_string(&nodes).unwrap()
+    }
+
+    fn is_lagging(replica: &ReplicaStatus, max_lag: &Option<MaxLag>) -> bool {
+        max_lag.is_some_and(|max_lag| max_lag.exceeded(&replica.lag))
+    }
+
+    /// Versão do log deste nó e, num primário, a versão aplicada e o atraso
+    /// de cada réplica, para o comando LAG.
+    async fn lag(context: &Context) -> String {
+        let offset = context.dictionary.lock().await.revision();
+
+        let mut replicas = Vec::new();
+        if let Some(statuses) = &context.replicas {
+            for replica in statuses.lock().await.values() {
+                replicas.push(serde_json::json!({
+                    "name": replica.name,
+                    "offset": replica.offset,
+                    "ready": replica.ready,
+                    "lag": replica.lag,
+                    "lagging": Self::is_lagging(replica, &context.max_lag),
+                }));
+            }
+        }
+        replicas.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+
+        serde_json::json!({ "offset": offset, "replicas": replicas }).to_string()
+    }
+
+    async fn metrics(context: &Context) -> String {
+        let mut metrics = Metrics::new();
+        let offset = context.dictionary.lock().await.revision();
+        metrics
+            .gauge("guaradict_replication_offset", "Versão da última operação aplicada neste nó")
+            .sample("guaradict_replication_offset", &[], offset as f64);
+
+        let Some(statuses) = &context.replicas else {
+            return metrics.render();
+        };
+        let statuses = statuses.lock().await;
+        let mut replicas = statuses.values().collect::<Vec<&ReplicaStatus>>();
+        replicas.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let gauges: [ReplicaGauge; 6] = [
+            ("guaradict_replica_ready", "Réplica conectada e respondendo aos heartbeats", |replica| replica.ready as u8 as f64),
+            ("guaradict_replica_ping_seconds", "Tempo de resposta do último heartbeat", |replica| replica.ping.as_secs_f64()),
+            ("guaradict_replica_offset", "Versão da última operação aplicada pela réplica", |replica| replica.offset as f64),
+            ("guaradict_replica_lag_operations", "Operações do log ainda não aplicadas pela réplica", |replica| replica.lag.operations as f64),
+            ("guaradict_replica_lag_bytes", "Tamanho das operações ainda não aplicadas pela réplica", |replica| replica.lag.bytes as f64),
+            ("guaradict_replica_lag_seconds", "Idade da operação mais antiga ainda não aplicada pela réplica", |replica| replica.lag.seconds),
+        ];
+        for (name, help, value) in gauges {
+            metrics.gauge(name, help);
+            for replica in &replicas {
+                metrics.sample(name, &[("replica", &replica.name)], value(replica));
+            }
+        }
+
+        metrics.render()
+    }
+
+    async fn cluster_command(command: client::Command, context: &Context) -> String {
+        if let client::Command::ClusterKeySlot(key) = command {
+            return key_slot(&key).to_string();
+        }
+        let Some(cluster) = &context.cluster else {
+            return CLUSTER_DISABLED.to_string();
+        };
+
+        match command {
+            client::Command::ClusterGetKeysInSlot(slot, count) => {
+                serde_json::to_string(&context.dictionary.lock().await.keys_in_slot(slot, count)).unwrap()
+            }
+            client::Command::ClusterCountKeysInSlot(slot) => context.dictionary.lock().await.count_keys_in_slot(slot).to_string(),
+            client::Command::ClusterSetSlot(slot, state) => match cluster.lock().unwrap().set_slot(slot, state) {
+                Ok(()) => "OK".to_string(),
+                Err(e) => e.to_string(),
             },
-            None => {
-                match tx.send(client::Command::Add(key.to_string(), value.to_string())).await {
-                    Ok(_) => {},
-                    Err(e) => {
-                        println!("Erro Command::Add: {}", e)
-                    }
-                };
+            client::Command::ClusterSlots => serde_json::to_string(&cluster.lock().unwrap().assignments()).unwrap(),
+            client::Command::ClusterNodes => {
+                let cluster = cluster.lock().unwrap();
+                let nodes = cluster
+                    .nodes()
+                    .iter()
+                    .map(|node| {
+                        serde_json::json!({
+                            "name": node.name,
+                            "addr": node.addr,
+                            "slots": cluster.node_slots(&node.name),
+                            "myself": node.name == cluster.local(),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                serde_json::to_string(&nodes).unwrap()
             }
+            _ => "Invalid command".to_string(),
+        }
+    }
+
+    /// Move até `count` chaves de um slot em migração para o destino. As
+    /// chaves são copiadas com a versão e o dicionário é liberado durante o
+    /// envio por RESTORE pelo barramento; depois só são removidas daqui as
+    /// que não mudaram no meio tempo. As alteradas ficam e vão num próximo
+    /// lote, e as apagadas são apagadas também no destino. Retorna quantas
+    /// chaves foram enviadas; 0 indica que o slot esvaziou.
+    async fn migrate(slot: u16, count: usize, last_write: &mut u64, context: &Context) -> String {
+        let Some(cluster) = &context.cluster else {
+            return CLUSTER_DISABLED.to_string();
+        };
+
+        let (target, batch) = {
+            let dictionary = context.dictionary.lock().await;
+            let Some(target) = cluster.lock().unwrap().migrating(slot).cloned() else {
+                return ClusterError::NotMigrating(slot).to_string();
+            };
+            let batch = dictionary
+                .keys_in_slot(slot, count)
+                .into_iter()
+                .filter_map(|key| {
+                    let value = dictionary.get(&key)?.clone();
+                    let version = dictionary.version(&key);
+                    Some((key, value, version))
+                })
+                .collect::<Vec<(String, Value, u64)>>();
+            (target, batch)
+        };
+        if batch.is_empty() {
+            return "0".to_string();
         }
 
-        println!("{:#?}", dictionary);
+        let restores = batch
+            .iter()
+            .map(|(key, value, _)| client::Command::Restore(key.clone(), value.clone()))
+            .collect::<Vec<client::Command>>();
+        if let Err(e) = Self::send_keys(&target, &restores, context).await {
+            return e.to_string();
+        }
 
+        let mut dictionary = context.dictionary.lock().await;
+        let mut moved = 0;
+        let mut deleted = Vec::new();
+        for (key, _, version) in &batch {
+            if dictionary.version(key) == *version {
+                dictionary.remove_entry(key);
+                moved += 1;
+            } else if dictionary.get(key).is_none() {
+                deleted.push(client::Command::Del(key.clone()));
+            }
+        }
+        if let Some(offset) = Self::replicate(&mut dictionary, context).await {
+            *last_write = offset;
+        }
         drop(dictionary);
 
-        "Entry added successfully".to_string()
+        // A cópia enviada de uma chave apagada daqui não pode sobreviver no destino
+        if let Err(e) = Self::send_keys(&target, &deleted, context).await {
+            return e.to_string();
+        }
+        println!("{} chaves do slot {} movidas para {}", moved, slot, target.name);
+
+        batch.len().to_string()
     }
 
-    async fn get_definition(key: String, dictionary: Arc<Mutex<Dictionary>>, tx: Sender<client::Command>) -> String {
-        let dictionary = dictionary.as_ref().lock().await;
+    // Os comandos vão pelo barramento do destino, que aceita RESTORE e DEL da migração depois do HELLO
+    async fn send_keys(target: &ClusterNode, commands: &[client::Command], context: &Context) -> Result<(), ClusterError> {
+        if commands.is_empty() {
+            return Ok(());
+        }
 
-        let result = match dictionary.get_definition(&key) {
-            Some(definition) => definition.to_string(),
-            None => "Key not found".to_string(),
+        match tokio::time::timeout(MIGRATE_TIMEOUT, Self::send_to_bus(target, commands, context)).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(ClusterError::MigrationFailed(e.to_string())),
+            Err(_) => Err(ClusterError::MigrationFailed(format!("sem resposta de {}", target.name))),
+        }
+    }
+
+    async fn send_to_bus(target: &ClusterNode, commands: &[client::Command], context: &Context) -> std::io::Result<()> {
+        let bus = target.bus.parse().map_err(|_| std::io::Error::other(format!("Endereço do barramento inválido: {}", target.bus)))?;
+        let (mut stream, _) = context.identity.connect(bus, &target.name).await?;
+        for command in commands {
+            stream.write_all(format!("{}\n", command.execute()).as_bytes()).await?;
+
+            let response = read_line(&mut stream).await?;
+            let accepted = match command {
+                client::Command::Del(_) => response == "Entry removed successfully",
+                _ => response == "OK",
+            };
+            if !accepted {
+                return Err(std::io::Error::other(format!("{} recusou {}: {}", target.name, command.execute(), response)));
+            }
+        }
+        stream.write_all(b"QUIT\n").await
+    }
+
+    fn raft_command(command: client::Command, context: &Context) -> String {
+        let Some(raft) = &context.raft else {
+            return "Raft is not enabled".to_string();
         };
 
-        drop(dictionary);
+        let result = match command {
+            client::Command::RaftAdd(name, addr) => match addr.parse() {
+                Ok(addr) => raft.server.add_member(&name, addr),
+                Err(_) => return "Invalid address".to_string(),
+            },
+            client::Command::RaftRemove(name) => raft.server.remove_member(&name),
+            client::Command::RaftStatus => return raft.server.status().to_string(),
+            _ => return "Invalid command".to_string(),
+        };
+
+        match result {
+            Ok(_) => "OK".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
 
-        match tx.send(client::Command::Get(key.to_string())).await {
-            Ok(_) => {},
-            Err(e) => {
-                println!("Erro Command::Get: {}", e)
+    /// Aplica as entradas confirmadas pelo Raft. As que este nó propôs já
+    /// estão no dicionário; se outra entrada ocupou o lugar de uma delas, as
+    /// escritas locais não confirmadas são desfeitas reconstruindo o
+    /// dicionário a partir do log.
+    async fn apply_committed(mut entries: UnboundedReceiver<RaftEntry>, context: Context) {
+        let raft = context.raft.clone().unwrap();
+
+        while let Some(entry) = entries.recv().await {
+            let mut dictionary = context.dictionary.lock().await;
+            let proposed = raft.proposed.lock().unwrap().remove(&entry.index);
+
+            let applied = match proposed {
+                Some(term) if term == entry.term => true,
+                Some(_) => {
+                    println!("Entrada {} substituída por outro líder, reconstruindo o dicionário", entry.index);
+                    Self::rebuild(&mut dictionary, &raft);
+                    false
+                }
+                None => false,
+            };
+
+            if let RaftPayload::Operations { operations } = entry.payload {
+                if !applied {
+                    for operation in &operations {
+                        dictionary.apply(operation);
+                    }
+                    dictionary.take_operations();
+                    Self::notify(&dictionary, &operations, &context).await;
+                }
+
+                if let Err(e) = context.tx.send(operations).await {
+                    println!("Erro ao enviar operações para o log: {}", e);
+                }
             }
+
+            raft.applied.store(entry.index, Ordering::SeqCst);
+        }
+    }
+
+    // Descarta as escritas locais ainda não confirmadas, refazendo o
+    // dicionário só com as entradas confirmadas e já aplicadas
+    fn rebuild(dictionary: &mut Dictionary, raft: &RaftState) {
+        let operations = raft.server.committed_operations(raft.applied.load(Ordering::SeqCst));
+        let mut rebuilt = if dictionary.has_search_index() {
+            Dictionary::with_search_index()
+        } else {
+            Dictionary::new()
         };
+        rebuilt.set_node(dictionary.node());
+        rebuilt.replay(&operations);
 
-        result
+        *dictionary = rebuilt;
+        raft.proposed.lock().unwrap().clear();
     }
 
-    async fn remove_entry(key: String, dictionary: Arc<Mutex<Dictionary>>, tx: Sender<client::Command>) -> String {
-        let mut dictionary = dictionary.as_ref().lock().await;
+    fn is_subscription(command: &client::Command) -> bool {
+        matches!(
+            command,
+            client::Command::Subscribe(_)
+                | client::Command::Unsubscribe(_)
+                | client::Command::PSubscribe(_)
+                | client::Command::PUnsubscribe(_)
+                | client::Command::WatchKey(_)
+                | client::Command::UnwatchKey(_)
+                | client::Command::WatchPrefix(_)
+                | client::Command::UnwatchPrefix(_)
+        )
+    }
 
-        dictionary.remove_entry(&key);
+    // Responde com uma linha JSON de confirmação por canal ou padrão, no
+    // mesmo formato das mensagens publicadas
+    async fn subscription(command: client::Command, subscriber: u64, pubsub: &Arc<Mutex<PubSub>>) -> String {
+        let mut pubsub = pubsub.as_ref().lock().await;
+        let confirmations = match command {
+            client::Command::Subscribe(channels) => pubsub.subscribe(subscriber, channels),
+            client::Command::Unsubscribe(channels) => pubsub.unsubscribe(subscriber, channels),
+            client::Command::PSubscribe(patterns) => pubsub.psubscribe(subscriber, patterns),
+            client::Command::PUnsubscribe(patterns) => pubsub.punsubscribe(subscriber, patterns),
+            client::Command::WatchKey(keys) => pubsub.watch_keys(subscriber, keys),
+            client::Command::UnwatchKey(keys) => pubsub.unwatch_keys(subscriber, keys),
+            client::Command::WatchPrefix(prefixes) => pubsub.watch_prefixes(subscriber, prefixes),
+            client::Command::UnwatchPrefix(prefixes) => pubsub.unwatch_prefixes(subscriber, prefixes),
+            _ => return "Invalid command".to_string(),
+        };
 
+        confirmations.iter().map(|message| message.to_line()).collect()
+    }
+
+    // Comandos de controle de transação são executados de imediato; os demais
+    // são enfileirados enquanto houver um MULTI em andamento
+    async fn execute_in_transaction(command: client::Command, asking: bool, transaction: &mut Transaction, last_write: &mut u64, context: &Context) -> String {
+        let result = match command {
+            client::Command::Multi => transaction.begin(),
+            client::Command::Discard => transaction.discard(),
+            client::Command::Unwatch => {
+                transaction.unwatch();
+                Ok(())
+            }
+            client::Command::Watch(keys) => {
+                let locked_dictionary = context.dictionary.lock().await;
+                if let Err(e) = Self::route(keys.iter().map(String::as_str), asking, &locked_dictionary, context) {
+                    return e.to_string();
+                }
+                transaction.watch(keys, &locked_dictionary)
+            }
+            client::Command::Exec => return Self::exec(transaction, last_write, context).await,
+            command if transaction.is_active() => {
+                let routed = Self::route(command.keys(), asking, &*context.dictionary.lock().await, context);
+                if let Err(e) = routed {
+                    transaction.fail();
+                    return e.to_string();
+                }
+                transaction.queue(command);
+                return "QUEUED".to_string();
+            }
+            command => return Self::execute(command, asking, last_write, context).await,
+        };
+
+        match result {
+            Ok(()) => "OK".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    /// Executa os comandos enfileirados com o dicionário travado do início ao
+    /// fim, e envia todas as alterações ao log como um único lote.
+    async fn exec(transaction: &mut Transaction, last_write: &mut u64, context: &Context) -> String {
+        let mut locked_dictionary = context.dictionary.lock().await;
+
+        let commands = match transaction.exec(&locked_dictionary) {
+            Ok(commands) => commands,
+            Err(TransactionError::WatchedKeyChanged) => return "Transaction aborted".to_string(),
+            Err(e) => return e.to_string(),
+        };
+
+        // As chaves podem ter mudado de nó desde que os comandos entraram na fila
+        for command in &commands {
+            if let Err(e) = Self::route(command.keys(), false, &locked_dictionary, context) {
+                return e.to_string();
+            }
+        }
+
+        let mut responses = Vec::with_capacity(commands.len());
+        for command in commands {
+            responses.push(match command {
+                // Dentro de uma transação os pops bloqueantes não esperam
+                client::Command::BLPop(key, _) => Self::dispatch(client::Command::LPop(key), &mut locked_dictionary),
+                client::Command::BRPop(key, _) => Self::dispatch(client::Command::RPop(key), &mut locked_dictionary),
+                client::Command::Publish(channel, message) => context.pubsub.lock().await.publish(&channel, &message).to_string(),
+                command if Self::is_script(&command) => Self::script(command, &mut *context.scripts.lock().await, &mut locked_dictionary),
+                command => Self::dispatch(command, &mut locked_dictionary),
+            });
+        }
+
+        if let Some(offset) = Self::replicate(&mut locked_dictionary, context).await {
+            *last_write = offset;
+        }
+        drop(locked_dictionary);
+
+        serde_json::to_string(&responses).unwrap()
+    }
+
+    async fn execute(command: client::Command, asking: bool, last_write: &mut u64, context: &Context) -> String {
+        let command = match command {
+            client::Command::BLPop(key, timeout) => return Self::blocking_pop(key, ListSide::Left, timeout, asking, last_write, context).await,
+            client::Command::BRPop(key, timeout) => return Self::blocking_pop(key, ListSide::Right, timeout, asking, last_write, context).await,
+            client::Command::Migrate(slot, count) => return Self::migrate(slot, count, last_write, context).await,
+            command => command,
+        };
+
+        let mut dictionary = context.dictionary.lock().await;
+        if let Err(e) = Self::route(command.keys(), asking, &dictionary, context) {
+            return e.to_string();
+        }
+        let response = if Self::is_script(&command) {
+            Self::script(command, &mut *context.scripts.lock().await, &mut dictionary)
+        } else {
+            Self::dispatch(command, &mut dictionary)
+        };
+        if let Some(offset) = Self::replicate(&mut dictionary, context).await {
+            *last_write = offset;
+        }
         drop(dictionary);
 
-        match tx.send(client::Command::Del(key.to_string())).await {
-            Ok(_) => {},
-            Err(e) => {
-                println!("Erro Command::Del: {}", e)
+        response
+    }
+
+    fn is_script(command: &client::Command) -> bool {
+        matches!(
+            command,
+            client::Command::Eval(..)
+                | client::Command::EvalSha(..)
+                | client::Command::ScriptLoad(_)
+                | client::Command::ScriptExists(_)
+                | client::Command::ScriptFlush
+        )
+    }
+
+    // Executado com o dicionário travado: o script vê e altera o dicionário
+    // sem intercalar com outros comandos, e suas escritas vão ao log como um lote
+    fn script(command: client::Command, scripts: &mut ScriptEngine, dictionary: &mut Dictionary) -> String {
+        let result = match command {
+            client::Command::Eval(script, keys, args) => scripts.eval(&script, keys, args, dictionary),
+            client::Command::EvalSha(hash, keys, args) => scripts.eval_hash(&hash, keys, args, dictionary),
+            client::Command::ScriptLoad(script) => {
+                return match scripts.load(&script) {
+                    Ok(hash) => hash,
+                    Err(e) => e.to_string(),
+                }
             }
+            client::Command::ScriptExists(hashes) => {
+                let exists = hashes.iter().map(|hash| scripts.exists(hash)).collect::<Vec<bool>>();
+                return serde_json::to_string(&exists).unwrap();
+            }
+            client::Command::ScriptFlush => {
+                scripts.flush();
+                return "OK".to_string();
+            }
+            _ => return "Invalid command".to_string(),
         };
 
+        match result {
+            Ok(serde_json::Value::String(value)) => value,
+            Ok(value) => value.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn dispatch(command: client::Command, dictionary: &mut Dictionary) -> String {
+        match command {
+            client::Command::Add(key, value) => Self::add_entry(key, value, dictionary),
+            client::Command::Set(key, value) => Self::add_entry(key, value, dictionary),
+            client::Command::Get(key) => Self::get_definition(key, dictionary),
+            client::Command::GetWithVersion(key) => Self::get_definition_with_version(key, dictionary),
+            client::Command::Cas(key, version, value) => Self::compare_and_set(key, version, value, dictionary),
+            client::Command::Del(key) => Self::remove_entry(key, dictionary),
+            client::Command::MGet(keys) => serde_json::to_string(&dictionary.get_definitions(&keys)).unwrap(),
+            client::Command::MSet(entries) => Self::add_entries(entries, dictionary),
+            client::Command::MDel(keys) => dictionary.remove_entries(&keys).to_string(),
+            client::Command::GetEntry(key) => Self::get_entry(key, dictionary),
+            client::Command::AddSense(key, sense) => Self::add_sense(key, sense, dictionary),
+            client::Command::DelSense(key, position) => Self::remove_sense(key, position, dictionary),
+            client::Command::HSet(key, field, value) => Self::map_set(key, field, value, dictionary),
+            client::Command::HGet(key, field) => Self::map_get(key, field, dictionary),
+            client::Command::HDel(key, field) => Self::map_delete(key, field, dictionary),
+            client::Command::HGetAll(key) => Self::map_get_all(key, dictionary),
+            client::Command::HIncrBy(key, field, by) => Self::map_increment(key, field, by, dictionary),
+            client::Command::LPush(key, values) => Self::list_push(key, ListSide::Left, values, dictionary),
+            client::Command::RPush(key, values) => Self::list_push(key, ListSide::Right, values, dictionary),
+            client::Command::LPop(key) => Self::list_pop(key, ListSide::Left, dictionary),
+            client::Command::RPop(key) => Self::list_pop(key, ListSide::Right, dictionary),
+            client::Command::LRange(key, start, stop) => Self::list_range(key, start, stop, dictionary),
+            client::Command::LLen(key) => Self::list_len(key, dictionary),
+            client::Command::SAdd(key, members) => Self::set_add(key, members, dictionary),
+            client::Command::SRem(key, members) => Self::set_remove(key, members, dictionary),
+            client::Command::SIsMember(key, member) => Self::set_is_member(key, member, dictionary),
+            client::Command::SMembers(key) => Self::set_members(key, dictionary),
+            client::Command::SCard(key) => Self::set_len(key, dictionary),
+            client::Command::SUnion(keys) => Self::set_members_response(dictionary.set_union(&keys)),
+            client::Command::SInter(keys) => Self::set_members_response(dictionary.set_intersection(&keys)),
+            client::Command::SDiff(keys) => Self::set_members_response(dictionary.set_difference(&keys)),
+            client::Command::Search(query) => Self::search(query, dictionary),
+            client::Command::CrdtIncrBy(key, by) => Self::crdt_increment(key, by, dictionary),
+            client::Command::CrdtSAdd(key, members) => Self::crdt_set_add(key, members, dictionary),
+            client::Command::CrdtSRem(key, members) => Self::crdt_set_remove(key, members, dictionary),
+            client::Command::CrdtSet(key, value) => Self::crdt_register_set(key, value, dictionary),
+            client::Command::CrdtHSet(key, field, value) => Self::crdt_map_set(key, field, value, dictionary),
+            client::Command::CrdtHDel(key, field) => Self::crdt_map_delete(key, field, dictionary),
+            client::Command::CrdtGet(key) => Self::crdt_get(key, dictionary),
+            client::Command::Restore(key, value) => {
+                dictionary.restore(&key, Some(value));
+                "OK".to_string()
+            }
+            _ => "Invalid command".to_string(),
+        }
+    }
+
+    // Envia as alterações ainda com o dicionário travado para manter a ordem do
+    // log e das notificações de alteração. Retorna a versão da última
+    // alteração enviada, se houve alguma
+    async fn replicate(dictionary: &mut Dictionary, context: &Context) -> Option<u64> {
+        let mut operations = dictionary.take_operations();
+        let offset = operations.last()?.version;
+
+        if let Some(multi_primary) = &context.multi_primary {
+            multi_primary.resolver.lock().unwrap().stamp(&mut operations, dictionary);
+        }
+
+        // No Raft as operações vão ao log quando confirmadas
+        if let Some(raft) = &context.raft {
+            match raft.server.propose(operations.clone()) {
+                Ok((index, term)) => {
+                    raft.proposed.lock().unwrap().insert(index, term);
+                    raft.writes.lock().unwrap().insert(offset, (index, term));
+                }
+                Err(e) => {
+                    println!("Escrita descartada: {}", e);
+                    Self::rebuild(dictionary, raft);
+                    return Some(offset);
+                }
+            }
+
+            Self::notify(dictionary, &operations, context).await;
+            return Some(offset);
+        }
+
+        Self::publish(dictionary, operations, context).await;
+
+        Some(offset)
+    }
+
+    // Notifica as alterações e envia as operações ao log
+    async fn publish(dictionary: &Dictionary, operations: Vec<Operation>, context: &Context) {
+        Self::notify(dictionary, &operations, context).await;
+
+        if let Err(e) = context.tx.send(operations).await {
+            println!("Erro ao enviar operações para o log: {}", e);
+        }
+    }
+
+    // Notifica os inscritos nas chaves alteradas e acorda os pops bloqueantes
+    async fn notify(dictionary: &Dictionary, operations: &[Operation], context: &Context) {
+        let pubsub = context.pubsub.lock().await;
+        if pubsub.has_watchers() {
+            for operation in operations {
+                let key = operation.key.to_string();
+                let value = dictionary.get(&key).map_or(serde_json::Value::Null, |value| value.to_json());
+                pubsub.notify_change(operation.kind.clone(), &key, value, operation.version);
+            }
+        }
+        drop(pubsub);
+
+        if operations
+            .iter()
+            .any(|op| matches!(op.kind, OperationKind::ListPushLeft | OperationKind::ListPushRight))
+        {
+            context.list_pushed.notify_waiters();
+        }
+    }
+
+    /// Remove um valor da lista, aguardando até `timeout` segundos (0 espera
+    /// indefinidamente) que outro cliente insira valores.
+    async fn blocking_pop(key: String, side: ListSide, timeout: u64, asking: bool, last_write: &mut u64, context: &Context) -> String {
+        let deadline = (timeout > 0).then(|| Instant::now() + Duration::from_secs(timeout));
+
+        loop {
+            // Registra o interesse antes de consultar a lista para não perder notificações
+            let notified = context.list_pushed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let mut locked_dictionary = context.dictionary.lock().await;
+            // O slot pode ter mudado de nó enquanto o cliente esperava
+            if let Err(e) = Self::route([key.as_str()], asking, &locked_dictionary, context) {
+                return e.to_string();
+            }
+            match locked_dictionary.list_pop(&key, side) {
+                Ok(Some(value)) => {
+                    if let Some(offset) = Self::replicate(&mut locked_dictionary, context).await {
+                        *last_write = offset;
+                    }
+                    return value;
+                }
+                Ok(None) => drop(locked_dictionary),
+                Err(e) => return e.to_string(),
+            }
+
+            match deadline {
+                Some(deadline) => {
+                    if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                        return "Timeout".to_string();
+                    }
+                }
+                None => notified.await,
+            }
+        }
+    }
+
+    // @TODO add deve verificar se existe antes, set deve ser o update
+    fn add_entry(key: String, value: String, dictionary: &mut Dictionary) -> String {
+        dictionary.add_entry(key, value);
+
+        "Entry added successfully".to_string()
+    }
+
+    fn get_definition(key: String, dictionary: &Dictionary) -> String {
+        match dictionary.get_definition(&key) {
+            Some(definition) => definition.to_string(),
+            None => "Key not found".to_string(),
+        }
+    }
+
+    fn get_definition_with_version(key: String, dictionary: &Dictionary) -> String {
+        match dictionary.get_definition(&key) {
+            Some(definition) => serde_json::json!({
+                "definition": definition,
+                "version": dictionary.version(&key),
+            })
+            .to_string(),
+            None => "Key not found".to_string(),
+        }
+    }
+
+    fn compare_and_set(key: String, version: u64, value: String, dictionary: &mut Dictionary) -> String {
+        match dictionary.compare_and_set(&key, version, value) {
+            Ok(version) => version.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn remove_entry(key: String, dictionary: &mut Dictionary) -> String {
+        dictionary.remove_entry(&key);
+
         "Entry removed successfully".to_string()
     }
 
+    fn add_entries(entries: Vec<(String, String)>, dictionary: &mut Dictionary) -> String {
+        dictionary.add_entries(entries);
+
+        "Entries added successfully".to_string()
+    }
+
+    fn get_entry(key: String, dictionary: &Dictionary) -> String {
+        match dictionary.get_entry(&key) {
+            Ok(Some(entry)) => serde_json::to_string(&entry).unwrap(),
+            Ok(None) => "Key not found".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn add_sense(key: String, sense: Sense, dictionary: &mut Dictionary) -> String {
+        match dictionary.add_sense(&key, sense) {
+            Ok(_) => "Sense added successfully".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn remove_sense(key: String, position: usize, dictionary: &mut Dictionary) -> String {
+        match dictionary.remove_sense(&key, position) {
+            Ok(_) => "Sense removed successfully".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn map_set(key: String, field: String, value: String, dictionary: &mut Dictionary) -> String {
+        match dictionary.map_set(&key, &field, value) {
+            Ok(_) => "Field set successfully".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn map_get(key: String, field: String, dictionary: &Dictionary) -> String {
+        match dictionary.map_get(&key, &field) {
+            Ok(Some(value)) => value.to_string(),
+            Ok(None) => "Field not found".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn map_delete(key: String, field: String, dictionary: &mut Dictionary) -> String {
+        match dictionary.map_delete(&key, &field) {
+            Ok(true) => "Field removed successfully".to_string(),
+            Ok(false) => "Field not found".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn map_get_all(key: String, dictionary: &Dictionary) -> String {
+        match dictionary.map_get_all(&key) {
+            Ok(Some(map)) => serde_json::to_string(map).unwrap(),
+            Ok(None) => "{}".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn map_increment(key: String, field: String, by: i64, dictionary: &mut Dictionary) -> String {
+        match dictionary.map_increment(&key, &field, by) {
+            Ok(value) => value.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn list_push(key: String, side: ListSide, values: Vec<String>, dictionary: &mut Dictionary) -> String {
+        match dictionary.list_push(&key, side, values) {
+            Ok(len) => len.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn list_pop(key: String, side: ListSide, dictionary: &mut Dictionary) -> String {
+        match dictionary.list_pop(&key, side) {
+            Ok(Some(value)) => value,
+            Ok(None) => "Key not found".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn list_range(key: String, start: i64, stop: i64, dictionary: &Dictionary) -> String {
+        match dictionary.list_range(&key, start, stop) {
+            Ok(values) => serde_json::to_string(&values).unwrap(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn list_len(key: String, dictionary: &Dictionary) -> String {
+        match dictionary.list_len(&key) {
+            Ok(len) => len.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn set_add(key: String, members: Vec<String>, dictionary: &mut Dictionary) -> String {
+        match dictionary.set_add(&key, members) {
+            Ok(added) => added.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn set_remove(key: String, members: Vec<String>, dictionary: &mut Dictionary) -> String {
+        match dictionary.set_remove(&key, members) {
+            Ok(removed) => removed.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn set_is_member(key: String, member: String, dictionary: &Dictionary) -> String {
+        match dictionary.set_is_member(&key, &member) {
+            Ok(is_member) => is_member.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn set_members(key: String, dictionary: &Dictionary) -> String {
+        Self::set_members_response(dictionary.set_members(&key).map(|set| set.cloned().unwrap_or_default()))
+    }
+
+    fn set_len(key: String, dictionary: &Dictionary) -> String {
+        match dictionary.set_len(&key) {
+            Ok(len) => len.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    // Membros ordenados para que a resposta seja estável
+    fn set_members_response(result: Result<HashSet<String>, DictionaryError>) -> String {
+        match result {
+            Ok(set) => {
+                let mut members = set.into_iter().collect::<Vec<String>>();
+                members.sort();
+                serde_json::to_string(&members).unwrap()
+            }
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn crdt_increment(key: String, by: i64, dictionary: &mut Dictionary) -> String {
+        match dictionary.crdt_increment(&key, by) {
+            Ok(value) => value.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn crdt_set_add(key: String, members: Vec<String>, dictionary: &mut Dictionary) -> String {
+        match dictionary.crdt_set_add(&key, members) {
+            Ok(added) => added.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn crdt_set_remove(key: String, members: Vec<String>, dictionary: &mut Dictionary) -> String {
+        match dictionary.crdt_set_remove(&key, members) {
+            Ok(removed) => removed.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn crdt_register_set(key: String, value: String, dictionary: &mut Dictionary) -> String {
+        match dictionary.crdt_register_set(&key, value) {
+            Ok(()) => "OK".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn crdt_map_set(key: String, field: String, value: String, dictionary: &mut Dictionary) -> String {
+        match dictionary.crdt_map_set(&key, &field, value) {
+            Ok(()) => "Field set successfully".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn crdt_map_delete(key: String, field: String, dictionary: &mut Dictionary) -> String {
+        match dictionary.crdt_map_delete(&key, &field) {
+            Ok(true) => "Field removed successfully".to_string(),
+            Ok(false) => "Field not found".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn crdt_get(key: String, dictionary: &Dictionary) -> String {
+        match dictionary.crdt_get(&key) {
+            Ok(Some(crdt)) => serde_json::json!({ "type": crdt.type_name(), "value": crdt.to_json() }).to_string(),
+            Ok(None) => "Key not found".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn search(query: String, dictionary: &Dictionary) -> String {
+        match dictionary.search(&query) {
+            Ok(hits) if hits.is_empty() => "No matches found".to_string(),
+            Ok(hits) => hits
+                .iter()
+                .map(|hit| format!("{} {:.3}", hit.word, hit.score))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            Err(e) => e.to_string(),
+        }
+    }
 }
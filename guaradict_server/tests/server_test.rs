@@ -1,3 +1,105 @@
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::process::{Child, Command, Stdio};
+    use std::thread;
+    use std::time::Duration;
+    use guaradict_core::commands::{client, frame};
+    use guaradict_core::Value;
+
+    // Servidor rodando com uma configuração mínima, encerrado no drop
+    struct Server(Child);
+
+    impl Server {
+        fn start(name: &str, port: u16) -> (Self, TcpStream) {
+            Self::start_with(name, port, "nodeType: primary\n")
+        }
+
+        // `node` traz o tipo do nó e o que mais a configuração precisar
+        fn start_with(name: &str, port: u16, node: &str) -> (Self, TcpStream) {
+            let config = std::env::temp_dir().join(format!("{}.yaml", name));
+            std::fs::write(
+                &config,
+                format!(
+                    "{}name: {}\nip: 127.0.0.1\nhost: 127.0.0.1\nport: {}\njournal:\n  size: 10\n  strategy: sync\n",
+                    node, name, port
+                ),
+            )
+            .unwrap();
+
+            let child = Command::new(env!("CARGO_BIN_EXE_guaradict_server"))
+                .args(["--config", config.to_str().unwrap()])
+                .stdout(Stdio::null())
+                .spawn()
+                .unwrap();
+            let server = Server(child);
+
+            for _ in 0..50 {
+                if let Ok(stream) = TcpStream::connect(("127.0.0.1", port)) {
+                    return (server, stream);
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+            panic!("Servidor não subiu na porta {}", port);
+        }
+    }
+
+    impl Drop for Server {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    fn request(stream: &mut TcpStream, command: &client::Command) -> String {
+        stream.write_all(format!("{}\n", command.execute()).as_bytes()).unwrap();
+        frame::read_blocking(stream).unwrap()
+    }
+
+    #[test]
+    fn test_batch_larger_than_a_read_buffer() {
+        let (_server, mut stream) = Server::start("batch-server", 13991);
+
+        let entries = (0..200)
+            .map(|i| (format!("palavra-{:03}", i), format!("definição {} {}", i, "x".repeat(40))))
+            .collect::<Vec<(String, String)>>();
+        let mset = client::Command::MSet(entries.clone());
+        assert!(mset.execute().len() > 4096);
+        assert_eq!(request(&mut stream, &mset), "Entries added successfully");
+
+        let keys = entries.iter().map(|(key, _)| key.clone()).collect::<Vec<String>>();
+        let response = request(&mut stream, &client::Command::MGet(keys));
+        assert!(response.len() > 4096);
+        let definitions = serde_json::from_str::<Vec<Option<String>>>(&response).unwrap();
+        assert_eq!(definitions, entries.iter().map(|(_, value)| Some(value.clone())).collect::<Vec<_>>());
+
+        // A conexão continua alinhada: a próxima resposta é a do próximo comando
+        assert_eq!(request(&mut stream, &client::Command::Get("palavra-007".to_string())), entries[7].1);
+        assert_eq!(request(&mut stream, &client::Command::Ping), "PONG");
+    }
+
+    #[test]
+    fn test_restore_is_rejected_on_client_port() {
+        let (_server, mut stream) = Server::start("restore-server", 13992);
+
+        let restore = client::Command::Restore("casa".to_string(), Value::Text("lar".to_string()));
+        assert_eq!(request(&mut stream, &restore), "Invalid command");
+        let acks = client::Command::parse(&format!("ACKS 0 {}", restore.execute())).unwrap();
+        assert_eq!(request(&mut stream, &acks), "Invalid command");
+        assert_eq!(request(&mut stream, &client::Command::Get("casa".to_string())), "Key not found");
+    }
+
+    #[test]
+    fn test_replica_rejects_writes() {
+        let primary = "primary:\n  name: primary-node\n  nodeType: primary\n  ip: 127.0.0.1\n  host: 127.0.0.1\n  port: 13994\n";
+        let (_server, mut stream) = Server::start_with("replica-server", 13993, &format!("nodeType: replica\n{}", primary));
+
+        let set = client::Command::Set("casa".to_string(), "lar".to_string());
+        assert_eq!(request(&mut stream, &set), "O nó é uma réplica; o primário atual é primary-node");
+        let acks = client::Command::parse(&format!("ACKS 0 {}", set.execute())).unwrap();
+        assert_eq!(request(&mut stream, &acks), "O nó é uma réplica; o primário atual é primary-node");
+        // As leituras continuam sendo atendidas
+        assert_eq!(request(&mut stream, &client::Command::Get("casa".to_string())), "Key not found");
+    }
 }